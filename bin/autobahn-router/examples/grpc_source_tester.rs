@@ -1,5 +1,5 @@
 use autobahn_router::source::grpc_plugin_source::feed_data_geyser;
-use router_config_lib::{AccountDataSourceConfig, GrpcSourceConfig};
+use router_config_lib::GrpcSourceConfig;
 use solana_program::pubkey::Pubkey;
 use std::collections::HashSet;
 use std::env;
@@ -19,19 +19,6 @@ pub async fn main() {
         tls: None,
     };
 
-    let rpc_http_addr = env::var("RPC_HTTP_ADDR").expect("need rpc http url");
-    let snapshot_config = AccountDataSourceConfig {
-        region: None,
-        quic_sources: None,
-        rpc_http_url: rpc_http_addr.clone(),
-        rpc_support_compression: Some(false), /* no compression */
-        re_snapshot_interval_secs: None,
-        grpc_sources: Some(vec![]),
-        dedup_queue_size: 0,
-        request_timeout_in_seconds: None,
-        number_of_accounts_per_gma: None,
-    };
-
     // Raydium
     let raydium_program_id =
         Pubkey::from_str("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8").unwrap();
@@ -45,9 +32,9 @@ pub async fn main() {
     info!("starting grpc_plugin_source...");
     // blocking
     feed_data_geyser(
+        0,
         &grpc_config,
         None,
-        snapshot_config,
         &account_sub,
         &program_sub,
         &token_account_sub,
@@ -0,0 +1,209 @@
+use crate::prelude::*;
+use crate::routing::Routing;
+use crate::server::alt_provider::{AltProvider, RpcAltProvider};
+use crate::util::tokio_spawn;
+use router_config_lib::AltManagerConfig;
+use router_lib::alt_manager;
+use router_lib::alt_manager::AltLimits;
+use router_lib::dex::AccountProviderView;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::{EncodableKey, Signer};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const DEFAULT_INTERVAL_SECS: u64 = 600;
+const DEFAULT_MIN_ROUTE_COUNT: u64 = 10;
+const DEFAULT_MAX_TABLES: usize = 4;
+const DEFAULT_MAX_LAMPORTS: u64 = 1_000_000_000;
+
+/// Number of quoted routes that went through each edge since the last alt manager run
+#[derive(Default)]
+pub struct EdgeUsage {
+    routes_per_edge: Mutex<HashMap<(Pubkey, Pubkey), u64>>,
+}
+
+impl EdgeUsage {
+    pub fn record(&self, edges: &HashSet<(Pubkey, Pubkey)>) {
+        let mut routes_per_edge = self.routes_per_edge.lock().unwrap();
+        for edge in edges {
+            *routes_per_edge.entry(*edge).or_default() += 1;
+        }
+    }
+
+    /// Edges used by at least `min_route_count` routes, counters start again from zero
+    pub fn take_frequent(&self, min_route_count: u64) -> HashSet<(Pubkey, Pubkey)> {
+        let routes_per_edge = std::mem::take(&mut *self.routes_per_edge.lock().unwrap());
+        routes_per_edge
+            .into_iter()
+            .filter(|(_, count)| *count >= min_route_count)
+            .map(|(edge, _)| edge)
+            .collect()
+    }
+}
+
+/// Keeps the tables owned by the payer in sync with the alt provider, tables created with the cli
+/// included, and when `auto_extend` is set, adds the accounts of frequently routed edges
+/// that are in none of the configured or managed tables, up to `max_tables` tables
+/// and `max_lamports` spent since startup
+pub fn spawn_alt_manager_job(
+    config: &AltManagerConfig,
+    auto_extend: bool,
+    configured_tables: Vec<String>,
+    rpc_client: RpcClient,
+    routing: Arc<Routing>,
+    chain_data: AccountProviderView,
+    alt_provider: Arc<RpcAltProvider>,
+    mut exit: broadcast::Receiver<()>,
+) -> anyhow::Result<JoinHandle<()>> {
+    let payer = Keypair::read_from_file(&config.payer_keypair_path)
+        .map_err(|e| anyhow!("failed to read alt manager payer keypair: {}", e))?;
+    let interval_secs = config.interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS);
+    let min_route_count = config.min_route_count.unwrap_or(DEFAULT_MIN_ROUTE_COUNT);
+    let max_tables = config.max_tables.unwrap_or(DEFAULT_MAX_TABLES);
+    let max_lamports = config.max_lamports.unwrap_or(DEFAULT_MAX_LAMPORTS);
+    let configured_tables = configured_tables
+        .iter()
+        .map(|x| Pubkey::from_str(x))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    info!(
+        authority = payer.pubkey().to_string(),
+        auto_extend, max_tables, max_lamports, "Starting alt manager"
+    );
+
+    let job = tokio_spawn("alt_manager", async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // set from the payer balance on the first auto extend run
+        let mut limits: Option<AltLimits> = None;
+
+        loop {
+            tokio::select! {
+                _ = exit.recv() => {
+                    info!("shutting down alt manager task");
+                    break;
+                }
+                _ = interval.tick() => {
+                    let mut managed = match alt_manager::fetch_managed_tables(&rpc_client, &payer.pubkey()).await {
+                        Ok(managed) => managed,
+                        Err(e) => {
+                            warn!("Failed to fetch managed lookup tables: {:?}", e);
+                            continue;
+                        }
+                    };
+                    for table in &managed {
+                        alt_provider.update_managed_alt(table.clone());
+                    }
+
+                    if !auto_extend {
+                        continue;
+                    }
+
+                    let accounts = frequently_routed_accounts(&routing, &chain_data, &alt_provider, min_route_count);
+                    let mut known_tables = vec![];
+                    for table in &configured_tables {
+                        if let Ok(table) = alt_provider.get_alt(*table).await {
+                            known_tables.push(table);
+                        }
+                    }
+                    let missing = alt_manager::missing_accounts(&known_tables, accounts);
+                    if missing.is_empty() {
+                        continue;
+                    }
+
+                    if limits.is_none() {
+                        match rpc_client.get_balance(&payer.pubkey()).await {
+                            Ok(balance) => limits = Some(AltLimits {
+                                max_tables,
+                                min_payer_balance: Some(balance.saturating_sub(max_lamports)),
+                            }),
+                            Err(e) => {
+                                warn!("Failed to fetch alt manager payer balance: {:?}", e);
+                                continue;
+                            }
+                        }
+                    }
+                    let limits = limits.as_ref().unwrap();
+
+                    let result = alt_manager::add_accounts(&rpc_client, &payer, &mut managed, missing, limits).await;
+                    // tables may have been extended before a failure, they are updated either way
+                    for table in &managed {
+                        alt_provider.update_managed_alt(table.clone());
+                    }
+                    match result {
+                        Ok(touched) => info!(tables = touched.len(), "Lookup tables extended"),
+                        Err(e) => warn!("Failed to extend lookup tables: {:?}", e),
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(job)
+}
+
+fn frequently_routed_accounts(
+    routing: &Routing,
+    chain_data: &AccountProviderView,
+    alt_provider: &RpcAltProvider,
+    min_route_count: u64,
+) -> Vec<Pubkey> {
+    let Some(edge_usage) = &alt_provider.edge_usage else {
+        return vec![];
+    };
+    let frequent = edge_usage.take_frequent(min_route_count);
+    if frequent.is_empty() {
+        return vec![];
+    }
+
+    routing
+        .edges()
+        .iter()
+        .filter(|edge| frequent.contains(&edge.unique_id()))
+        .filter_map(|edge| match edge_accounts(edge, chain_data) {
+            Ok(accounts) => Some(accounts),
+            Err(e) => {
+                debug!("Failed to list accounts of {}: {:?}", edge.desc(), e);
+                None
+            }
+        })
+        .flatten()
+        .unique()
+        .collect()
+}
+
+/// Accounts of the swap instruction that are the same whatever the wallet,
+/// the user token accounts are left out
+fn edge_accounts(edge: &Edge, chain_data: &AccountProviderView) -> anyhow::Result<Vec<Pubkey>> {
+    let accounts_for = |wallet: &Pubkey| -> anyhow::Result<Vec<Pubkey>> {
+        let ix = edge.build_swap_ix(chain_data, wallet, 1_000_000, 0, 10_000)?;
+        Ok(ix.instruction.accounts.iter().map(|x| x.pubkey).collect())
+    };
+
+    let other_wallet_accounts: HashSet<Pubkey> =
+        accounts_for(&Pubkey::new_unique())?.into_iter().collect();
+    Ok(accounts_for(&Pubkey::new_unique())?
+        .into_iter()
+        .filter(|x| other_wallet_accounts.contains(x))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_take_frequent_edges_and_reset_counters() {
+        let usage = EdgeUsage::default();
+        let edge_1 = (Pubkey::new_unique(), Pubkey::new_unique());
+        let edge_2 = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        usage.record(&HashSet::from([edge_1, edge_2]));
+        usage.record(&HashSet::from([edge_1]));
+
+        assert_eq!(HashSet::from([edge_1]), usage.take_frequent(2));
+        assert!(usage.take_frequent(1).is_empty());
+    }
+}
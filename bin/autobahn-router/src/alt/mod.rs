@@ -1 +1,2 @@
+pub mod alt_manager;
 pub mod alt_optimizer;
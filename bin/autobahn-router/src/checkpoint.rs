@@ -0,0 +1,380 @@
+use crate::edge::EdgeState;
+use crate::hot_mints::HotMintsCache;
+use crate::prelude::*;
+use crate::routing::{PersistedPathCacheEntry, Routing};
+use crate::util::tokio_spawn;
+use mango_feeds_connector::chain_data::{AccountData, SlotData, SlotStatus};
+use router_config_lib::CheckpointConfig;
+use router_lib::chain_data::ChainDataArcRw;
+use router_lib::dex::SwapMode;
+use router_lib::price_feeds::price_cache::PriceCache;
+use solana_sdk::account::{Account, AccountSharedData};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Bumped when the content of the checkpoint changes, older files are ignored
+const CHECKPOINT_VERSION: u32 = 2;
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+const DEFAULT_MAX_AGE_SECS: u64 = 600;
+/// Accounts copied per chain data read lock, so that writers are not blocked for the whole copy
+const ACCOUNTS_PER_LOCK: usize = 1_000;
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+struct Checkpoint {
+    version: u32,
+    timestamp_millis: u64,
+    slot: u64,
+    rooted_slot: u64,
+    accounts: Vec<CheckpointAccount>,
+    // by edge unique id
+    edges: Vec<((Pubkey, Pubkey), EdgeState)>,
+    // most recent first
+    hot_mints: Vec<Pubkey>,
+    prices: Vec<(Pubkey, f64)>,
+    paths: Vec<PersistedPathCacheEntry>,
+}
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+struct CheckpointAccount {
+    pubkey: Pubkey,
+    slot: u64,
+    write_version: u64,
+    account: Account,
+}
+
+/// What is saved to and restored from the checkpoint
+#[derive(Clone)]
+pub struct WarmState {
+    pub chain_data: ChainDataArcRw,
+    pub routing: Arc<Routing>,
+    pub hot_mints: Arc<RwLock<HotMintsCache>>,
+    pub price_cache: PriceCache,
+}
+
+/// Load the checkpoint, if there is a recent enough one, before geyser and the edge updaters start.
+/// Accounts are inserted with their original slot, the rooted slot of the checkpoint is marked
+/// as rooted and the newer account slots as processed on top of it, so any newer write from the
+/// snapshots or the geyser stream replaces them.
+/// Returns true when the warm state was restored.
+pub fn restore(config: &CheckpointConfig, state: &WarmState) -> anyhow::Result<bool> {
+    if !std::path::Path::new(&config.path).exists() {
+        info!("No checkpoint found at {}, cold start", config.path);
+        return Ok(false);
+    }
+
+    let started_at = Instant::now();
+    let checkpoint = read(&config.path)?;
+    if checkpoint.version != CHECKPOINT_VERSION {
+        warn!(
+            "Ignoring checkpoint with version {}, expected {}",
+            checkpoint.version, CHECKPOINT_VERSION
+        );
+        return Ok(false);
+    }
+    let age_millis = millis_since_epoch().saturating_sub(checkpoint.timestamp_millis);
+    let max_age_secs = config.max_age_secs.unwrap_or(DEFAULT_MAX_AGE_SECS);
+    if age_millis > max_age_secs * 1000 {
+        warn!(
+            "Ignoring checkpoint from {}s ago, max age is {}s",
+            age_millis / 1000,
+            max_age_secs
+        );
+        return Ok(false);
+    }
+
+    let account_count = checkpoint.accounts.len();
+    {
+        let mut chain_data = state.chain_data.write().unwrap();
+        chain_data.update_slot(SlotData {
+            slot: checkpoint.rooted_slot,
+            parent: None,
+            status: SlotStatus::Rooted,
+            chain: 0,
+        });
+        // not rooted yet when the checkpoint was written, chained so their writes stay visible
+        let processed_slots = checkpoint
+            .accounts
+            .iter()
+            .map(|x| x.slot)
+            .chain([checkpoint.slot])
+            .filter(|slot| *slot > checkpoint.rooted_slot)
+            .sorted()
+            .dedup();
+        let mut parent = checkpoint.rooted_slot;
+        for slot in processed_slots {
+            chain_data.update_slot(SlotData {
+                slot,
+                parent: Some(parent),
+                status: SlotStatus::Processed,
+                chain: 0,
+            });
+            parent = slot;
+        }
+        for account in checkpoint.accounts {
+            chain_data.update_account(
+                account.pubkey,
+                AccountData {
+                    slot: account.slot,
+                    write_version: account.write_version,
+                    account: AccountSharedData::from(account.account),
+                },
+            );
+        }
+    }
+
+    let mut edge_states: HashMap<_, _> = checkpoint.edges.into_iter().collect();
+    let mut edge_count = 0;
+    for edge in state.routing.edges() {
+        let Some(mut restored) = edge_states.remove(&edge.unique_id()) else {
+            continue;
+        };
+        let mut edge_state = edge.state.write().unwrap();
+        // not persisted, set again by the next update
+        restored.input_transfer_fee = edge_state.input_transfer_fee;
+        restored.output_transfer_fee = edge_state.output_transfer_fee;
+        *edge_state = restored;
+        edge_count += 1;
+    }
+
+    {
+        let mut hot_mints = state.hot_mints.write().unwrap();
+        for mint in checkpoint.hot_mints.iter().rev() {
+            hot_mints.add(*mint);
+        }
+    }
+
+    for (mint, price) in &checkpoint.prices {
+        state.price_cache.insert_if_missing(*mint, *price);
+    }
+
+    let hot_mints = state.hot_mints.read().unwrap().get();
+    for swap_mode in [SwapMode::ExactIn, SwapMode::ExactOut] {
+        state
+            .routing
+            .prepare_pruned_edges_and_cleanup_cache(&hot_mints, swap_mode);
+    }
+    let path_count = state.routing.import_path_cache(checkpoint.paths);
+
+    info!(
+        slot = checkpoint.slot,
+        rooted_slot = checkpoint.rooted_slot,
+        age_secs = age_millis / 1000,
+        accounts = account_count,
+        edges = edge_count,
+        hot_mints = checkpoint.hot_mints.len(),
+        prices = checkpoint.prices.len(),
+        cached_paths = path_count,
+        elapsed_ms = started_at.elapsed().as_millis(),
+        "Restored warm state from checkpoint"
+    );
+
+    Ok(true)
+}
+
+/// Writes a checkpoint every `interval_secs` and a last one on exit
+pub fn spawn_checkpoint_job(
+    config: &CheckpointConfig,
+    state: WarmState,
+    mut exit: broadcast::Receiver<()>,
+) -> JoinHandle<()> {
+    let path = config.path.clone();
+    let interval_secs = config.interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS);
+
+    tokio_spawn("checkpoint", async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        interval.tick().await;
+
+        loop {
+            let exiting = tokio::select! {
+                _ = exit.recv() => true,
+                _ = interval.tick() => false,
+            };
+
+            let path = path.clone();
+            let state = state.clone();
+            match tokio::task::spawn_blocking(move || write(&path, &state)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => error!("Failed to write checkpoint: {:?}", e),
+                Err(e) => error!("Checkpoint writer panicked: {:?}", e),
+            }
+
+            if exiting {
+                info!("checkpoint job exited");
+                break;
+            }
+        }
+    })
+}
+
+fn write(path: &str, state: &WarmState) -> anyhow::Result<()> {
+    let started_at = Instant::now();
+
+    let (slot, rooted_slot, pubkeys) = {
+        let chain_data = state.chain_data.read().unwrap();
+        let pubkeys = chain_data.iter_accounts().map(|(pk, _)| *pk).collect_vec();
+        (
+            chain_data.newest_processed_slot(),
+            chain_data.newest_rooted_slot(),
+            pubkeys,
+        )
+    };
+
+    // accounts written meanwhile have a newer slot, restored as processed
+    let mut accounts = Vec::with_capacity(pubkeys.len());
+    for chunk in pubkeys.chunks(ACCOUNTS_PER_LOCK) {
+        let chain_data = state.chain_data.read().unwrap();
+        accounts.extend(chunk.iter().filter_map(|pubkey| {
+            let data = chain_data.account(pubkey).ok()?;
+            Some(CheckpointAccount {
+                pubkey: *pubkey,
+                slot: data.slot,
+                write_version: data.write_version,
+                account: Account::from(data.account.clone()),
+            })
+        }));
+    }
+
+    let checkpoint = Checkpoint {
+        version: CHECKPOINT_VERSION,
+        timestamp_millis: millis_since_epoch(),
+        slot,
+        rooted_slot,
+        accounts,
+        edges: state
+            .routing
+            .edges()
+            .iter()
+            .map(|edge| (edge.unique_id(), edge.state.read().unwrap().clone()))
+            .collect(),
+        hot_mints: state.hot_mints.read().unwrap().latest(),
+        prices: state.price_cache.prices(),
+        paths: state.routing.export_path_cache(),
+    };
+
+    // written next to the previous checkpoint and renamed, a crash never leaves a partial file
+    let tmp_path = format!("{}.tmp", path);
+    {
+        let file = BufWriter::new(File::create(&tmp_path).context("create checkpoint file")?);
+        let mut writer = lz4::EncoderBuilder::new().build(file)?;
+        bincode::serialize_into(&mut writer, &checkpoint)?;
+        let (mut file, result) = writer.finish();
+        result?;
+        file.flush()?;
+    }
+    std::fs::rename(&tmp_path, path).context("rename checkpoint file")?;
+
+    info!(
+        slot,
+        rooted_slot,
+        accounts = checkpoint.accounts.len(),
+        edges = checkpoint.edges.len(),
+        cached_paths = checkpoint.paths.len(),
+        elapsed_ms = started_at.elapsed().as_millis(),
+        "Checkpoint written to {}",
+        path
+    );
+
+    Ok(())
+}
+
+fn read(path: &str) -> anyhow::Result<Checkpoint> {
+    let file = BufReader::new(File::open(path).context("open checkpoint file")?);
+    let mut reader = lz4::Decoder::new(file)?;
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    let checkpoint = bincode::deserialize(&data).context("decode checkpoint")?;
+    Ok(checkpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use router_config_lib::Config;
+    use solana_sdk::account::ReadableAccount;
+
+    fn make_state() -> WarmState {
+        let (_exit_sender, exit) = broadcast::channel(1);
+        let (_price_sender, prices) = broadcast::channel(1);
+        WarmState {
+            chain_data: ChainDataArcRw::new(Default::default()),
+            routing: Arc::new(Routing::new(&Config::default(), vec![100], vec![])),
+            hot_mints: Arc::new(RwLock::new(HotMintsCache::new(&None))),
+            price_cache: PriceCache::new(exit, prices).0,
+        }
+    }
+
+    fn account(data: u8) -> AccountSharedData {
+        AccountSharedData::from(Account {
+            lamports: 1,
+            data: vec![data; 8],
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn should_restore_written_checkpoint() {
+        let (rooted, processed) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = Pubkey::new_unique();
+
+        let source = make_state();
+        {
+            let mut chain_data = source.chain_data.write().unwrap();
+            chain_data.update_slot(SlotData {
+                slot: 100,
+                parent: None,
+                status: SlotStatus::Rooted,
+                chain: 0,
+            });
+            chain_data.update_slot(SlotData {
+                slot: 105,
+                parent: Some(100),
+                status: SlotStatus::Processed,
+                chain: 0,
+            });
+            for (pubkey, slot, data) in [(rooted, 90, 1), (processed, 105, 2)] {
+                chain_data.update_account(
+                    pubkey,
+                    AccountData {
+                        slot,
+                        write_version: 1,
+                        account: account(data),
+                    },
+                );
+            }
+        }
+        source.hot_mints.write().unwrap().add(mint);
+        source.price_cache.insert_if_missing(mint, 4.2);
+
+        let path = std::env::temp_dir()
+            .join(format!("checkpoint-{}.bin", Pubkey::new_unique()))
+            .to_string_lossy()
+            .to_string();
+        write(&path, &source).unwrap();
+
+        let restored = make_state();
+        let config = CheckpointConfig {
+            path: path.clone(),
+            interval_secs: None,
+            max_age_secs: None,
+        };
+        assert!(restore(&config, &restored).unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        let chain_data = restored.chain_data.read().unwrap();
+        // slot 105 was only processed, it must not be restored as rooted
+        assert_eq!(chain_data.newest_rooted_slot(), 100);
+        assert_eq!(chain_data.newest_processed_slot(), 105);
+        for (pubkey, slot, data) in [(rooted, 90, 1), (processed, 105, 2)] {
+            let restored_account = chain_data.account(&pubkey).unwrap();
+            assert_eq!(restored_account.slot, slot);
+            assert_eq!(restored_account.account.data(), &[data; 8]);
+        }
+        assert_eq!(restored.hot_mints.read().unwrap().latest(), vec![mint]);
+        assert_eq!(restored.price_cache.price_ui(mint), Some(4.2));
+    }
+}
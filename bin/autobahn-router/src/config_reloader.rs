@@ -0,0 +1,103 @@
+use crate::routing::{Routing, RoutingSettings};
+use crate::util::tokio_spawn;
+use router_config_lib::{Config, ConfigChanges, RoutingConfig};
+use std::sync::{Arc, Mutex};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+pub fn path_warming_amounts(config: &RoutingConfig) -> Vec<u64> {
+    config
+        .path_warming_amounts
+        .clone()
+        .unwrap_or(vec![100, 1000])
+}
+
+/// Read the configuration file again on demand and publish it to the running jobs
+pub struct ConfigReloader {
+    path: String,
+    /// config the router was started with, used to report changes that still need a restart
+    startup: Config,
+    current: Mutex<Config>,
+    sender: watch::Sender<Config>,
+}
+
+impl ConfigReloader {
+    pub fn new(path: String, config: Config) -> Self {
+        let (sender, _) = watch::channel(config.clone());
+        Self {
+            path,
+            startup: config.clone(),
+            current: Mutex::new(config),
+            sender,
+        }
+    }
+
+    /// Jobs only read the settings that can be changed live from the published config
+    pub fn subscribe(&self) -> watch::Receiver<Config> {
+        self.sender.subscribe()
+    }
+
+    pub fn reload(&self) -> anyhow::Result<ConfigChanges> {
+        let new = Config::load(&self.path)?;
+
+        let mut current = self.current.lock().unwrap();
+        let changes = ConfigChanges {
+            live: current.changes(&new).live,
+            restart_required: self.startup.changes(&new).restart_required,
+        };
+
+        if !changes.restart_required.is_empty() {
+            warn!(
+                fields = changes.restart_required.join(", "),
+                "Config reload - some changes need a restart to be applied"
+            );
+        }
+        if !changes.live.is_empty() {
+            info!(
+                fields = changes.live.join(", "),
+                "Config reload - applying changes"
+            );
+            *current = new.clone();
+            self.sender.send_replace(new);
+        }
+
+        Ok(changes)
+    }
+}
+
+/// Reload config on SIGHUP, and apply routing settings whatever triggered the reload
+pub fn spawn_config_reload_job(
+    reloader: Arc<ConfigReloader>,
+    routing: Arc<Routing>,
+    mut exit: broadcast::Receiver<()>,
+) -> JoinHandle<()> {
+    let mut config_updates = reloader.subscribe();
+
+    tokio_spawn("config_reload", async move {
+        let mut sighup = signal(SignalKind::hangup()).expect("SIGHUP handler");
+
+        loop {
+            tokio::select! {
+                _ = exit.recv() => {
+                    info!("shutting down config_reload task");
+                    break;
+                }
+                _ = sighup.recv() => {
+                    info!("Received SIGHUP, reloading config..");
+                    if let Err(e) = reloader.reload() {
+                        error!("Failed to reload config: {:?}", e);
+                    }
+                }
+                Ok(_) = config_updates.changed() => {
+                    let routing_config = config_updates.borrow_and_update().routing.clone();
+                    routing.update_settings(RoutingSettings::new(
+                        &routing_config,
+                        path_warming_amounts(&routing_config),
+                    ));
+                }
+            }
+        }
+    })
+}
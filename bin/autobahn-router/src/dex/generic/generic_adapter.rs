@@ -93,5 +93,7 @@ pub async fn build_dex_internal(
         } else {
             DexSubscriptionMode::Disabled
         },
+        interface: dex,
+        mints: if take_all_mints { None } else { Some(mints) },
     })
 }
@@ -7,6 +7,7 @@ use router_lib::dex::{
 };
 use router_lib::price_feeds::price_cache::PriceCache;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
 use std::cmp::min;
 use std::fmt::Formatter;
 use std::time::Duration;
@@ -24,6 +25,15 @@ pub struct EdgeState {
     pub cooldown_event: u64,
     /// When will the edge become available again ?
     pub cooldown_until: Option<u64>,
+
+    /// Manually disabled by an operator until then (not lifted by account updates)
+    pub banned_until: Option<u64>,
+
+    /// Token-2022 transfer fees of the input and output mints
+    #[serde(skip)]
+    pub input_transfer_fee: Option<TransferFeeConfig>,
+    #[serde(skip)]
+    pub output_transfer_fee: Option<TransferFeeConfig>,
 }
 
 pub struct Edge {
@@ -118,8 +128,23 @@ impl Edge {
         chain_data: &AccountProviderView,
         in_amount: u64,
     ) -> anyhow::Result<Quote> {
-        self.dex
-            .quote(&self.id, &prepared_quote, chain_data, in_amount)
+        let (input_transfer_fee, output_transfer_fee) = self.transfer_fees();
+        if input_transfer_fee.is_none() && output_transfer_fee.is_none() {
+            return self
+                .dex
+                .quote(&self.id, &prepared_quote, chain_data, in_amount);
+        }
+
+        // The pool receives the input minus its transfer fee,
+        // and the wallet receives the output minus its transfer fee
+        let epoch = chain_data.clock()?.epoch;
+        let input_fee = transfer_fee(&input_transfer_fee, epoch, in_amount)?;
+        let mut quote =
+            self.dex
+                .quote(&self.id, &prepared_quote, chain_data, in_amount - input_fee)?;
+        quote.in_amount = quote.in_amount.saturating_add(input_fee);
+        quote.out_amount -= transfer_fee(&output_transfer_fee, epoch, quote.out_amount)?;
+        Ok(quote)
     }
 
     pub fn supports_exact_out(&self) -> bool {
@@ -132,8 +157,32 @@ impl Edge {
         chain_data: &AccountProviderView,
         out_amount: u64,
     ) -> anyhow::Result<Quote> {
-        self.dex
-            .quote_exact_out(&self.id, &prepared_quote, chain_data, out_amount)
+        let (input_transfer_fee, output_transfer_fee) = self.transfer_fees();
+        if input_transfer_fee.is_none() && output_transfer_fee.is_none() {
+            return self
+                .dex
+                .quote_exact_out(&self.id, &prepared_quote, chain_data, out_amount);
+        }
+
+        // The pool has to send more for the wallet to receive out_amount,
+        // and the wallet has to send more for the pool to receive the quoted input
+        let epoch = chain_data.clock()?.epoch;
+        let output_fee = inverse_transfer_fee(&output_transfer_fee, epoch, out_amount)?;
+        let mut quote = self.dex.quote_exact_out(
+            &self.id,
+            &prepared_quote,
+            chain_data,
+            out_amount.saturating_add(output_fee),
+        )?;
+        quote.out_amount = quote.out_amount.saturating_sub(output_fee);
+        let input_fee = inverse_transfer_fee(&input_transfer_fee, epoch, quote.in_amount)?;
+        quote.in_amount = quote.in_amount.saturating_add(input_fee);
+        Ok(quote)
+    }
+
+    fn transfer_fees(&self) -> (Option<TransferFeeConfig>, Option<TransferFeeConfig>) {
+        let state = self.state.read().unwrap();
+        (state.input_transfer_fee, state.output_transfer_fee)
     }
 
     pub fn update_internal(
@@ -238,6 +287,12 @@ impl Edge {
     ) {
         trace!(edge = self.desc(), "updating");
 
+        {
+            let mut state = self.state.write().unwrap();
+            state.input_transfer_fee = token_cache.transfer_fee(&self.input_mint);
+            state.output_transfer_fee = token_cache.transfer_fee(&self.output_mint);
+        }
+
         let Ok(decimals) = token_cache.token(self.input_mint).map(|x| x.decimals) else {
             let mut state = self.state.write().unwrap();
             trace!("no decimals for {}", self.input_mint);
@@ -255,6 +310,30 @@ impl Edge {
     }
 }
 
+/// Fee withheld when transferring amount
+fn transfer_fee(
+    config: &Option<TransferFeeConfig>,
+    epoch: u64,
+    amount: u64,
+) -> anyhow::Result<u64> {
+    config
+        .map_or(Some(0), |x| x.calculate_epoch_fee(epoch, amount))
+        .context("transfer fee overflow")
+}
+
+/// Fee to add so that post_fee_amount is received after the transfer
+fn inverse_transfer_fee(
+    config: &Option<TransferFeeConfig>,
+    epoch: u64,
+    post_fee_amount: u64,
+) -> anyhow::Result<u64> {
+    config
+        .map_or(Some(0), |x| {
+            x.calculate_inverse_epoch_fee(epoch, post_fee_amount)
+        })
+        .context("transfer fee overflow")
+}
+
 impl EdgeState {
     /// Returns the price (in native/native) and ln(price) most applicable for the in amount
     /// Returns None if invalid
@@ -293,6 +372,10 @@ impl EdgeState {
             return false;
         }
 
+        if self.is_banned() {
+            return false;
+        }
+
         if self.cooldown_until.is_some() {
             // Do not check time here !
             // We will reset "cooldown until" on first account update coming after cooldown
@@ -304,6 +387,19 @@ impl EdgeState {
         true
     }
 
+    pub fn is_banned(&self) -> bool {
+        self.banned_until
+            .is_some_and(|until| millis_since_epoch() < until)
+    }
+
+    pub fn ban(&mut self, until: u64) {
+        self.banned_until = Some(until);
+    }
+
+    pub fn unban(&mut self) {
+        self.banned_until = None;
+    }
+
     pub fn reset_cooldown(&mut self) {
         self.cooldown_event += 0;
         self.cooldown_until = None;
@@ -323,3 +419,248 @@ impl EdgeState {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test::{MockDexIdentifier, MockDexInterface};
+    use anchor_lang::{AccountSerialize, Discriminator, Id};
+    use anchor_spl::token::Token;
+    use dex_raydium_cp::RaydiumCpDex;
+    use mango_feeds_connector::chain_data::AccountData;
+    use raydium_cp_swap::states::{AmmConfig, PoolState};
+    use router_lib::dex::AccountProvider;
+    use solana_sdk::account::AccountSharedData;
+    use solana_sdk::clock::Clock;
+    use solana_sdk::program_pack::Pack;
+    use solana_sdk::sysvar;
+    use spl_token_2022::state::{Account, AccountState};
+
+    /// Serves the given accounts, and a default clock for any other address
+    #[derive(Default)]
+    struct TestAccountProvider {
+        accounts: HashMap<Pubkey, AccountSharedData>,
+    }
+
+    impl AccountProvider for TestAccountProvider {
+        fn account(&self, address: &Pubkey) -> anyhow::Result<AccountData> {
+            let account = match self.accounts.get(address) {
+                Some(account) => account.clone(),
+                None => AccountSharedData::new_data(1, &Clock::default(), &sysvar::id())?,
+            };
+            Ok(AccountData {
+                slot: 0,
+                write_version: 0,
+                account,
+            })
+        }
+
+        fn newest_processed_slot(&self) -> u64 {
+            0
+        }
+    }
+
+    fn one_percent_fee() -> Option<TransferFeeConfig> {
+        let mut config = TransferFeeConfig::default();
+        config.newer_transfer_fee.transfer_fee_basis_points = 100.into();
+        config.newer_transfer_fee.maximum_fee = u64::MAX.into();
+        Some(config)
+    }
+
+    fn make_edge(
+        input_transfer_fee: Option<TransferFeeConfig>,
+        output_transfer_fee: Option<TransferFeeConfig>,
+    ) -> Edge {
+        let (input_mint, output_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        Edge {
+            input_mint,
+            output_mint,
+            dex: Arc::new(MockDexInterface::default()),
+            id: Arc::new(MockDexIdentifier {
+                key: Pubkey::new_unique(),
+                input_mint,
+                output_mint,
+                price: 1.0,
+                depth: None,
+            }),
+            accounts_needed: 10,
+            state: RwLock::new(EdgeState {
+                input_transfer_fee,
+                output_transfer_fee,
+                ..EdgeState::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn should_compute_transfer_fees() {
+        assert_eq!(transfer_fee(&None, 0, 1000).unwrap(), 0);
+        assert_eq!(inverse_transfer_fee(&None, 0, 1000).unwrap(), 0);
+
+        let config = one_percent_fee();
+        assert_eq!(transfer_fee(&config, 0, 1000).unwrap(), 10);
+        assert_eq!(inverse_transfer_fee(&config, 0, 990).unwrap(), 10);
+
+        // sending post amount + inverse fee always delivers at least the post amount
+        for post_fee_amount in [1, 99, 990, 1000, 123_456] {
+            let fee = inverse_transfer_fee(&config, 0, post_fee_amount).unwrap();
+            let pre_fee_amount = post_fee_amount + fee;
+            let received = pre_fee_amount - transfer_fee(&config, 0, pre_fee_amount).unwrap();
+            assert!(received >= post_fee_amount);
+        }
+    }
+
+    #[test]
+    fn should_quote_net_of_transfer_fees() {
+        let chain_data = Arc::new(TestAccountProvider::default()) as AccountProviderView;
+
+        let edge = make_edge(None, None);
+        let prepared = edge.prepare(&chain_data).unwrap();
+        let quote = edge.quote(&prepared, &chain_data, 1000).unwrap();
+        assert_eq!((quote.in_amount, quote.out_amount), (1000, 1000));
+
+        // pool receives 990, wallet receives 990
+        let edge = make_edge(one_percent_fee(), None);
+        let quote = edge.quote(&prepared, &chain_data, 1000).unwrap();
+        assert_eq!((quote.in_amount, quote.out_amount), (1000, 990));
+
+        // pool receives 990 and sends 990, wallet receives 990 - 10
+        let edge = make_edge(one_percent_fee(), one_percent_fee());
+        let quote = edge.quote(&prepared, &chain_data, 1000).unwrap();
+        assert_eq!((quote.in_amount, quote.out_amount), (1000, 980));
+    }
+
+    #[test]
+    fn should_quote_exact_out_gross_of_transfer_fees() {
+        let chain_data = Arc::new(TestAccountProvider::default()) as AccountProviderView;
+
+        // pool sends 1000 for the wallet to receive 990
+        let edge = make_edge(None, one_percent_fee());
+        let prepared = edge.prepare(&chain_data).unwrap();
+        let quote = edge.quote_exact_out(&prepared, &chain_data, 990).unwrap();
+        assert_eq!((quote.in_amount, quote.out_amount), (1000, 990));
+
+        // wallet sends enough for the pool to receive 1000
+        let edge = make_edge(one_percent_fee(), one_percent_fee());
+        let quote = edge.quote_exact_out(&prepared, &chain_data, 990).unwrap();
+        assert_eq!(quote.out_amount, 990);
+        let input_fee = transfer_fee(&one_percent_fee(), 0, quote.in_amount).unwrap();
+        assert!(quote.in_amount - input_fee >= 1000);
+        assert!(quote.in_amount <= 1011);
+    }
+
+    fn token_account(mint: Pubkey, amount: u64) -> AccountSharedData {
+        let mut data = vec![0; Account::LEN];
+        let account = Account {
+            mint,
+            amount,
+            state: AccountState::Initialized,
+            ..Account::default()
+        };
+        Account::pack(account, &mut data).unwrap();
+        AccountSharedData::create(1, data, Token::id(), false, 0)
+    }
+
+    /// Raydium CP edge over a 1:1 pool, with the given transfer fees on its mints
+    fn make_raydium_cp_edge(
+        input_transfer_fee: Option<TransferFeeConfig>,
+        output_transfer_fee: Option<TransferFeeConfig>,
+    ) -> (Edge, AccountProviderView) {
+        let pool_pk = Pubkey::new_unique();
+        let mut pool: PoolState = bytemuck::Zeroable::zeroed();
+        pool.amm_config = Pubkey::new_unique();
+        pool.token_0_vault = Pubkey::new_unique();
+        pool.token_1_vault = Pubkey::new_unique();
+        pool.token_0_mint = Pubkey::new_unique();
+        pool.token_1_mint = Pubkey::new_unique();
+        pool.token_0_program = Token::id();
+        pool.token_1_program = Token::id();
+
+        let mut pool_data = PoolState::DISCRIMINATOR.to_vec();
+        pool_data.extend_from_slice(bytemuck::bytes_of(&pool));
+        pool_data.resize(PoolState::LEN, 0);
+
+        let mut config_data = vec![];
+        AmmConfig {
+            trade_fee_rate: 2500,
+            ..AmmConfig::default()
+        }
+        .try_serialize(&mut config_data)
+        .unwrap();
+
+        let mut accounts = HashMap::new();
+        accounts.insert(
+            pool_pk,
+            AccountSharedData::create(1, pool_data, Pubkey::new_unique(), false, 0),
+        );
+        accounts.insert(
+            pool.amm_config,
+            AccountSharedData::create(1, config_data, Pubkey::new_unique(), false, 0),
+        );
+        accounts.insert(
+            pool.token_0_vault,
+            token_account(pool.token_0_mint, 1_000_000_000),
+        );
+        accounts.insert(
+            pool.token_1_vault,
+            token_account(pool.token_1_mint, 1_000_000_000),
+        );
+        let chain_data = Arc::new(TestAccountProvider { accounts }) as AccountProviderView;
+
+        let dex = Arc::new(RaydiumCpDex {
+            edges: HashMap::new(),
+            needed_accounts: HashSet::new(),
+        });
+        let id = dex
+            .discover_edges(&pool_pk, &chain_data)
+            .unwrap()
+            .remove(&pool_pk)
+            .unwrap()
+            .into_iter()
+            .find(|id| id.input_mint() == pool.token_0_mint)
+            .unwrap();
+
+        let edge = Edge {
+            input_mint: id.input_mint(),
+            output_mint: id.output_mint(),
+            accounts_needed: id.accounts_needed(),
+            dex,
+            id,
+            state: RwLock::new(EdgeState {
+                input_transfer_fee,
+                output_transfer_fee,
+                ..EdgeState::default()
+            }),
+        };
+        (edge, chain_data)
+    }
+
+    #[test]
+    fn should_deduct_transfer_fees_once_for_raydium_cp() {
+        let (edge, chain_data) = make_raydium_cp_edge(one_percent_fee(), one_percent_fee());
+        let prepared = edge.prepare(&chain_data).unwrap();
+
+        // the pool quotes on what it receives, the router takes off both transfer fees
+        let pool_quote = edge
+            .dex
+            .quote(&edge.id, &prepared, &chain_data, 990_000)
+            .unwrap();
+        let quote = edge.quote(&prepared, &chain_data, 1_000_000).unwrap();
+        let output_fee = transfer_fee(&one_percent_fee(), 0, pool_quote.out_amount).unwrap();
+        assert_eq!(quote.in_amount, 1_000_000);
+        assert_eq!(quote.out_amount, pool_quote.out_amount - output_fee);
+
+        // the pool sends enough for the wallet to receive the requested amount
+        let output_fee = inverse_transfer_fee(&one_percent_fee(), 0, 990_000).unwrap();
+        let pool_quote = edge
+            .dex
+            .quote_exact_out(&edge.id, &prepared, &chain_data, 990_000 + output_fee)
+            .unwrap();
+        let quote = edge
+            .quote_exact_out(&prepared, &chain_data, 990_000)
+            .unwrap();
+        let input_fee = inverse_transfer_fee(&one_percent_fee(), 0, pool_quote.in_amount).unwrap();
+        assert_eq!(quote.out_amount, 990_000);
+        assert_eq!(quote.in_amount, pool_quote.in_amount + input_fee);
+    }
+}
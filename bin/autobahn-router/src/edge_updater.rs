@@ -1,23 +1,34 @@
+use crate::config_reloader::path_warming_amounts;
 use crate::edge::Edge;
+use crate::hot_mints::HotMintsCache;
 use crate::metrics;
+use crate::routing::Routing;
 use crate::token_cache::TokenCache;
 use crate::util::tokio_spawn;
 use anchor_spl::token::spl_token;
 use itertools::Itertools;
+use rayon::prelude::*;
+use rayon::ThreadPool;
 use router_config_lib::Config;
 use router_feed_lib::get_program_account::FeedMetadata;
-use router_lib::dex::{AccountProviderView, DexSubscriptionMode};
+use router_lib::dex::{AccountProviderView, DexEdgeIdentifier, DexInterface, DexSubscriptionMode};
 use router_lib::price_feeds::price_cache::PriceCache;
 use router_lib::price_feeds::price_feed::PriceUpdate;
 use solana_program::pubkey::Pubkey;
 use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
-use tokio::sync::broadcast;
 use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, watch};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
+// edges repriced between two checks of the time budget
+const EDGES_PER_THREAD_PER_WAVE: usize = 4;
+const PRIORITY_EDGES_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+// programs own many non pool accounts (positions, tick arrays...), the set starts over when full
+const MAX_NOT_A_POOL_ACCOUNTS: usize = 100_000;
+
 #[derive(Clone)]
 pub struct Dex {
     pub name: String,
@@ -26,6 +37,10 @@ pub struct Dex {
     /// in case the program has too many accounts it could overload the rpc subscription
     /// it can be easier to subscribe to the program id directly
     pub subscription_mode: DexSubscriptionMode,
+    /// used to discover edges of pools created after startup
+    pub interface: Arc<dyn DexInterface>,
+    /// both mints of an edge must be in there for the edge to be used, None to take all mints
+    pub mints: Option<HashSet<Pubkey>>,
 }
 
 impl Dex {
@@ -41,6 +56,61 @@ impl Dex {
             .collect();
         edges
     }
+
+    pub fn programs(&self) -> HashSet<Pubkey> {
+        match &self.subscription_mode {
+            DexSubscriptionMode::Programs(p) => p.clone(),
+            DexSubscriptionMode::Mixed(m) => m.programs.clone(),
+            DexSubscriptionMode::Accounts(_) | DexSubscriptionMode::Disabled => HashSet::new(),
+        }
+    }
+
+    /// Register edges returned by DexInterface::discover_edges, returns the ones that were not known yet
+    pub fn add_discovered_edges(
+        &mut self,
+        discovered: HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>,
+    ) -> Vec<Arc<Edge>> {
+        let mut new_edges = HashMap::<(Pubkey, Pubkey), Arc<Edge>>::new();
+
+        for (pk, ids) in discovered {
+            for id in ids {
+                let unique_id = (id.key(), id.input_mint());
+                let is_known = self
+                    .edges_per_pk
+                    .get(&id.key())
+                    .is_some_and(|edges| edges.iter().any(|e| e.unique_id() == unique_id));
+                let is_wanted = self.mints.as_ref().map_or(true, |mints| {
+                    mints.contains(&id.input_mint()) && mints.contains(&id.output_mint())
+                });
+                if is_known || !is_wanted {
+                    continue;
+                }
+
+                let edge = new_edges.entry(unique_id).or_insert_with(|| {
+                    Arc::new(Edge {
+                        input_mint: id.input_mint(),
+                        output_mint: id.output_mint(),
+                        accounts_needed: id.accounts_needed(),
+                        dex: self.interface.clone(),
+                        id: id.clone(),
+                        state: Default::default(),
+                    })
+                });
+                let edges = self.edges_per_pk.entry(pk).or_default();
+                if !edges.iter().any(|e| Arc::ptr_eq(e, edge)) {
+                    edges.push(edge.clone());
+                }
+            }
+        }
+
+        new_edges.into_values().collect()
+    }
+}
+
+struct DirtyEdge {
+    edge: Arc<Edge>,
+    /// first invalidation since the last refresh
+    since: Instant,
 }
 
 #[derive(Default)]
@@ -53,8 +123,13 @@ struct EdgeUpdaterState {
     pub received_account: HashSet<Pubkey>,
     pub dirty_programs: HashSet<Pubkey>,
     pub dirty_token_accounts_for_owners: bool,
-    pub dirty_edges: HashMap<(Pubkey, Pubkey), Arc<Edge>>,
+    pub dirty_edges: HashMap<(Pubkey, Pubkey), DirtyEdge>,
     pub edges_per_mint: HashMap<Pubkey, Vec<Arc<Edge>>>,
+    /// edges of hot mints and cached paths, repriced first
+    pub priority_edges: HashSet<(Pubkey, Pubkey)>,
+    pub priority_edges_refreshed_at: Option<Instant>,
+    /// program owned accounts that were checked and are not a pool
+    pub not_a_pool: HashSet<Pubkey>,
 }
 
 struct EdgeUpdater {
@@ -62,8 +137,15 @@ struct EdgeUpdater {
     chain_data: AccountProviderView,
     token_cache: TokenCache,
     price_cache: PriceCache,
+    routing: Arc<Routing>,
+    hot_mints: Arc<RwLock<HotMintsCache>>,
+    worker_pool: Arc<ThreadPool>,
     ready_sender: async_channel::Sender<()>,
     register_mint_sender: async_channel::Sender<Pubkey>,
+    new_edges_sender: async_channel::Sender<Vec<Arc<Edge>>>,
+    repriced_edges_sender: broadcast::Sender<Vec<(Pubkey, Pubkey)>>,
+    discover_new_pools: bool,
+    dex_programs: HashSet<Pubkey>,
     state: EdgeUpdaterState,
     config: Config,
     path_warming_amounts: Vec<u64>,
@@ -75,13 +157,19 @@ pub fn spawn_updater_job(
     chain_data: AccountProviderView,
     token_cache: TokenCache,
     price_cache: PriceCache,
+    routing: Arc<Routing>,
+    hot_mints: Arc<RwLock<HotMintsCache>>,
+    worker_pool: Arc<ThreadPool>,
     path_warming_amounts: Vec<u64>,
     register_mint_sender: async_channel::Sender<Pubkey>,
+    new_edges_sender: async_channel::Sender<Vec<Arc<Edge>>>,
+    repriced_edges_sender: broadcast::Sender<Vec<(Pubkey, Pubkey)>>,
     ready_sender: async_channel::Sender<()>,
     mut slot_updates: broadcast::Receiver<u64>,
     mut account_updates: broadcast::Receiver<(Pubkey, Pubkey, u64)>,
     mut metadata_updates: broadcast::Receiver<FeedMetadata>,
     mut price_updates: broadcast::Receiver<PriceUpdate>,
+    mut config_updates: watch::Receiver<Config>,
     mut exit: broadcast::Receiver<()>,
 ) -> Option<JoinHandle<()>> {
     let dex = dex.clone();
@@ -134,11 +222,18 @@ pub fn spawn_updater_job(
     let init_timeout = Instant::now() + Duration::from_secs(init_timeout_in_seconds);
     let listener_job = tokio_spawn(format!("edge_updater_{}", dex.name).as_str(), async move {
         let mut updater = EdgeUpdater {
+            discover_new_pools: config.routing.discover_new_pools.unwrap_or(false),
+            dex_programs: dex.programs(),
             dex,
             chain_data,
             token_cache,
             price_cache,
+            routing,
+            hot_mints,
+            worker_pool,
             register_mint_sender,
+            new_edges_sender,
+            repriced_edges_sender,
             ready_sender,
             config,
             state: EdgeUpdaterState {
@@ -184,10 +279,14 @@ pub fn spawn_updater_job(
                 Ok(price_upd) = price_updates.recv() => {
                     if let Some(impacted_edges) = updater.state.edges_per_mint.get(&price_upd.mint) {
                         for edge in impacted_edges {
-                            updater.state.dirty_edges.insert(edge.unique_id(), edge.clone());
+                            mark_dirty(&mut updater.state.dirty_edges, edge);
                         }
                     };
                 },
+                Ok(_) = config_updates.changed() => {
+                    let config = config_updates.borrow_and_update().clone();
+                    updater.on_config_update(&config);
+                },
                 _ = refresh_one_interval.tick() => {
                     if !updater.state.is_ready && init_timeout < Instant::now() {
                         error!("Failed to init '{}' before timeout", updater.dex.name);
@@ -281,6 +380,23 @@ impl EdgeUpdater {
         let _ = self.ready_sender.try_send(());
     }
 
+    fn on_config_update(&mut self, config: &Config) {
+        let amounts = path_warming_amounts(&config.routing);
+        if amounts == self.path_warming_amounts {
+            return;
+        }
+
+        // cached prices are computed for the path warming amounts
+        info!(
+            dex_name = self.dex.name,
+            "path warming amounts changed, refreshing all edges"
+        );
+        self.path_warming_amounts = amounts;
+        for edge in self.dex.edges() {
+            mark_dirty(&mut self.state.dirty_edges, &edge);
+        }
+    }
+
     fn on_metadata_update(&mut self, res: Result<FeedMetadata, RecvError>) {
         let state = &mut self.state;
         match res {
@@ -331,10 +447,14 @@ impl EdgeUpdater {
         if !self.do_update(&pk, &owner) {
             return true;
         }
+        if !self.dex.edges_per_pk.contains_key(&pk) {
+            self.discover_edges(&pk, &owner);
+        }
+
         let state = &mut self.state;
         if let Some(impacted_edges) = self.dex.edges_per_pk.get(&pk) {
             for edge in impacted_edges {
-                state.dirty_edges.insert(edge.unique_id(), edge.clone());
+                mark_dirty(&mut state.dirty_edges, edge);
             }
         };
 
@@ -348,6 +468,66 @@ impl EdgeUpdater {
         return true;
     }
 
+    fn discover_edges(&mut self, pk: &Pubkey, owner: &Pubkey) {
+        // before being ready, we are still receiving the snapshot of pools known at startup
+        if !self.discover_new_pools
+            || !self.state.is_ready
+            || !self.dex_programs.contains(owner)
+            || self.state.not_a_pool.contains(pk)
+        {
+            return;
+        }
+
+        let discovered = match self.dex.interface.discover_edges(pk, &self.chain_data) {
+            Ok(discovered) => discovered,
+            Err(e) => {
+                debug!(
+                    "Failed to discover edges from {} on {}: {:?}",
+                    pk, self.dex.name, e
+                );
+                HashMap::new()
+            }
+        };
+        if discovered.is_empty() {
+            if self.state.not_a_pool.len() >= MAX_NOT_A_POOL_ACCOUNTS {
+                self.state.not_a_pool.clear();
+            }
+            self.state.not_a_pool.insert(*pk);
+            return;
+        }
+
+        let new_edges = self.dex.add_discovered_edges(discovered);
+        if new_edges.is_empty() {
+            return;
+        }
+
+        info!(
+            dex_name = self.dex.name,
+            pool = pk.to_string(),
+            edges_count = new_edges.len(),
+            "discovered new pool"
+        );
+
+        let state = &mut self.state;
+        for edge in &new_edges {
+            state
+                .edges_per_mint
+                .entry(edge.input_mint)
+                .or_default()
+                .push(edge.clone());
+            state
+                .edges_per_mint
+                .entry(edge.output_mint)
+                .or_default()
+                .push(edge.clone());
+            mark_dirty(&mut state.dirty_edges, edge);
+        }
+
+        if self.new_edges_sender.try_send(new_edges).is_err() {
+            warn!("Failed to send discovered edges of {}", self.dex.name);
+        }
+    }
+
     fn check_readiness(&mut self) {
         let state = &mut self.state;
 
@@ -397,32 +577,55 @@ impl EdgeUpdater {
     }
 
     fn refresh_some(&mut self) {
-        let state = &mut self.state;
-        if state.dirty_edges.is_empty() || !state.is_ready {
+        self.report_dirty_edges();
+
+        if self.state.dirty_edges.is_empty() || !self.state.is_ready {
             return;
         }
 
         let started_at = Instant::now();
-        let mut refreshed_edges = vec![];
+        self.refresh_priority_edges();
+
+        // edges of hot mints and cached paths first, then the ones waiting for the longest
+        let state = &self.state;
+        let queue = state
+            .dirty_edges
+            .iter()
+            .sorted_by_key(|(unique_id, dirty)| {
+                (!state.priority_edges.contains(*unique_id), dirty.since)
+            })
+            .map(|(unique_id, dirty)| (*unique_id, dirty.edge.clone()))
+            .collect_vec();
 
-        for (unique_id, edge) in &state.dirty_edges {
-            edge.update(
-                &self.chain_data,
-                &self.token_cache,
-                &self.price_cache,
-                &self.path_warming_amounts,
-            );
-            refreshed_edges.push(unique_id.clone());
+        let mut refreshed_edges = vec![];
+        let wave_size = self.worker_pool.current_num_threads() * EDGES_PER_THREAD_PER_WAVE;
+        for wave in queue.chunks(wave_size) {
+            self.worker_pool.install(|| {
+                wave.par_iter().for_each(|(_, edge)| {
+                    edge.update(
+                        &self.chain_data,
+                        &self.token_cache,
+                        &self.price_cache,
+                        &self.path_warming_amounts,
+                    );
+                })
+            });
+            refreshed_edges.extend(wave.iter().map(|(unique_id, _)| *unique_id));
 
             // Do not process for too long or we could miss update in account write queue
             if started_at.elapsed() > Duration::from_millis(100) {
                 break;
             }
         }
+
+        let state = &mut self.state;
         for unique_id in &refreshed_edges {
             state.dirty_edges.remove(&unique_id);
         }
 
+        // only fails when no quote stream is listening
+        let _ = self.repriced_edges_sender.send(refreshed_edges.clone());
+
         state.latest_slot_processed = state.latest_slot_pending;
 
         if started_at.elapsed() > Duration::from_millis(100) {
@@ -434,4 +637,51 @@ impl EdgeUpdater {
             )
         }
     }
+
+    // hot mints and cached paths do not change much from one tick to the next
+    fn refresh_priority_edges(&mut self) {
+        let state = &mut self.state;
+        if state
+            .priority_edges_refreshed_at
+            .is_some_and(|at| at.elapsed() < PRIORITY_EDGES_REFRESH_INTERVAL)
+        {
+            return;
+        }
+
+        let hot_mints = self.hot_mints.read().unwrap().get();
+        let mut priority_edges = self.routing.cached_path_edges();
+        for mint in &hot_mints {
+            if let Some(edges) = state.edges_per_mint.get(mint) {
+                priority_edges.extend(edges.iter().map(|edge| edge.unique_id()));
+            }
+        }
+
+        state.priority_edges = priority_edges;
+        state.priority_edges_refreshed_at = Some(Instant::now());
+    }
+
+    fn report_dirty_edges(&self) {
+        let dirty_edges = &self.state.dirty_edges;
+        let oldest_age_ms = dirty_edges
+            .values()
+            .map(|dirty| dirty.since.elapsed().as_millis() as i64)
+            .max()
+            .unwrap_or(0);
+
+        metrics::EDGE_UPDATER_DIRTY_EDGES
+            .with_label_values(&[&self.dex.name])
+            .set(dirty_edges.len() as i64);
+        metrics::EDGE_UPDATER_OLDEST_DIRTY_EDGE_AGE_MS
+            .with_label_values(&[&self.dex.name])
+            .set(oldest_age_ms);
+    }
+}
+
+fn mark_dirty(dirty_edges: &mut HashMap<(Pubkey, Pubkey), DirtyEdge>, edge: &Arc<Edge>) {
+    dirty_edges
+        .entry(edge.unique_id())
+        .or_insert_with(|| DirtyEdge {
+            edge: edge.clone(),
+            since: Instant::now(),
+        });
 }
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use mango_feeds_connector::chain_data::SlotStatus;
+use router_feed_lib::account_write::AccountWrite;
+use solana_program::clock::Slot;
+use solana_program::pubkey::Pubkey;
+
+use crate::metrics;
+
+/// Follows the slot chain the same way ChainData does, to find the accounts
+/// whose live version changed when ChainData switches to a different fork
+#[derive(Default)]
+pub struct ForkTracker {
+    /// slot -> parent, for slots newer than the root
+    parents: HashMap<Slot, Slot>,
+    /// (pubkey, owner) of accounts written in slots newer than the root
+    writes: HashMap<Slot, HashSet<(Pubkey, Pubkey)>>,
+    head: Slot,
+    root: Slot,
+}
+
+impl ForkTracker {
+    pub fn on_account_write(&mut self, slot: Slot, pubkey: Pubkey, owner: Pubkey) {
+        if slot > self.root {
+            self.writes.entry(slot).or_default().insert((pubkey, owner));
+        }
+    }
+
+    /// Returns the accounts written on either side of the fork when the new
+    /// slot is not a descendant of the previous head
+    pub fn on_slot(
+        &mut self,
+        slot: Slot,
+        parent: Option<Slot>,
+        status: SlotStatus,
+    ) -> HashSet<(Pubkey, Pubkey)> {
+        if let Some(parent) = parent {
+            self.parents.insert(slot, parent);
+        }
+
+        if status == SlotStatus::Rooted && slot > self.root {
+            self.root = slot;
+            self.parents.retain(|&s, _| s > slot);
+            self.writes.retain(|&s, _| s > slot);
+        }
+
+        if slot <= self.head {
+            return HashSet::new();
+        }
+        let previous_head = self.head;
+        self.head = slot;
+
+        if previous_head <= self.root || !self.is_fork_switch(previous_head, slot) {
+            return HashSet::new();
+        }
+
+        metrics::CHAIN_DATA_FORK_SWITCHES.inc();
+        let old_chain = self.chain(previous_head);
+        let new_chain = self.chain(slot);
+        old_chain
+            .symmetric_difference(&new_chain)
+            .filter_map(|s| self.writes.get(s))
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    /// Only when the ancestry is known, a missing slot update is not a fork
+    fn is_fork_switch(&self, previous_head: Slot, new_head: Slot) -> bool {
+        let mut current = new_head;
+        while current > previous_head {
+            match self.parents.get(&current) {
+                Some(&parent) => current = parent,
+                None => return false,
+            }
+        }
+        current != previous_head
+    }
+
+    /// Slots from `slot` down to the root (excluded), as far as the parents are known
+    fn chain(&self, slot: Slot) -> HashSet<Slot> {
+        let mut chain = HashSet::new();
+        let mut current = slot;
+        while current > self.root && chain.insert(current) {
+            match self.parents.get(&current) {
+                Some(&parent) => current = parent,
+                None => break,
+            }
+        }
+        chain
+    }
+}
+
+/// Holds account writes back until their slot is confirmed, ChainData
+/// then discards the writes of slots that did not make it to the confirmed chain
+#[derive(Default)]
+pub struct ConfirmedWritesBuffer {
+    pending: BTreeMap<Slot, Vec<AccountWrite>>,
+    confirmed_slot: Slot,
+}
+
+impl ConfirmedWritesBuffer {
+    /// Returns the write if its slot is already confirmed
+    pub fn push(&mut self, write: AccountWrite) -> Option<AccountWrite> {
+        if write.slot <= self.confirmed_slot {
+            return Some(write);
+        }
+
+        self.pending.entry(write.slot).or_default().push(write);
+        metrics::CHAIN_DATA_PENDING_UNCONFIRMED_SLOTS.set(self.pending.len() as i64);
+        None
+    }
+
+    /// Returns the writes that can be applied now, in slot order
+    pub fn on_slot(&mut self, slot: Slot, status: SlotStatus) -> Vec<AccountWrite> {
+        if status == SlotStatus::Processed || slot <= self.confirmed_slot {
+            return vec![];
+        }
+        self.confirmed_slot = slot;
+
+        let still_pending = self.pending.split_off(&(slot + 1));
+        let released = std::mem::replace(&mut self.pending, still_pending);
+        metrics::CHAIN_DATA_PENDING_UNCONFIRMED_SLOTS.set(self.pending.len() as i64);
+
+        released.into_values().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(slot: Slot, pubkey: Pubkey) -> AccountWrite {
+        AccountWrite {
+            pubkey,
+            slot,
+            write_version: 1,
+            lamports: 1,
+            owner: Pubkey::default(),
+            executable: false,
+            rent_epoch: 0,
+            data: vec![],
+        }
+    }
+
+    #[test]
+    fn should_report_accounts_written_on_both_sides_of_a_fork() {
+        let mut tracker = ForkTracker::default();
+        let owner = Pubkey::new_unique();
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        tracker.on_slot(10, Some(9), SlotStatus::Rooted);
+        tracker.on_account_write(11, a, owner);
+        assert!(tracker
+            .on_slot(11, Some(10), SlotStatus::Processed)
+            .is_empty());
+        tracker.on_account_write(12, b, owner);
+        assert!(tracker
+            .on_slot(12, Some(11), SlotStatus::Processed)
+            .is_empty());
+
+        // 13 is built on 11, so the write of 12 is not live anymore
+        tracker.on_account_write(13, c, owner);
+        let changed = tracker.on_slot(13, Some(11), SlotStatus::Processed);
+        assert_eq!(changed, HashSet::from([(b, owner), (c, owner)]));
+
+        // 14 follows 13, no fork switch
+        assert!(tracker
+            .on_slot(14, Some(13), SlotStatus::Processed)
+            .is_empty());
+    }
+
+    #[test]
+    fn should_not_report_a_fork_when_ancestry_is_unknown() {
+        let mut tracker = ForkTracker::default();
+        let owner = Pubkey::new_unique();
+
+        tracker.on_slot(10, Some(9), SlotStatus::Rooted);
+        tracker.on_account_write(11, Pubkey::new_unique(), owner);
+        tracker.on_slot(11, Some(10), SlotStatus::Processed);
+
+        // the update for slot 12 was missed
+        assert!(tracker
+            .on_slot(13, Some(12), SlotStatus::Processed)
+            .is_empty());
+    }
+
+    #[test]
+    fn should_hold_writes_until_confirmed() {
+        let mut buffer = ConfirmedWritesBuffer::default();
+        let pubkey = Pubkey::new_unique();
+
+        assert!(buffer.push(write(11, pubkey)).is_none());
+        assert!(buffer.push(write(12, pubkey)).is_none());
+        assert!(buffer.on_slot(12, SlotStatus::Processed).is_empty());
+
+        let released = buffer.on_slot(11, SlotStatus::Confirmed);
+        assert_eq!(
+            released.iter().map(|w| w.slot).collect::<Vec<_>>(),
+            vec![11]
+        );
+
+        // late write for an already confirmed slot
+        assert!(buffer.push(write(10, pubkey)).is_some());
+
+        let released = buffer.on_slot(13, SlotStatus::Rooted);
+        assert_eq!(
+            released.iter().map(|w| w.slot).collect::<Vec<_>>(),
+            vec![12]
+        );
+    }
+}
@@ -60,6 +60,11 @@ impl HotMintsCache {
         return;
     }
 
+    /// Mints that are hot because of recent requests, most recent first
+    pub fn latest(&self) -> Vec<Pubkey> {
+        self.latest_ordered.iter().copied().collect()
+    }
+
     pub fn get(&self) -> HashSet<Pubkey> {
         self.latest_unordered
             .union(&self.always_hot)
@@ -1,16 +1,46 @@
 use crate::routing_types::{Route, RouteStep};
 use crate::swap::Swap;
+use crate::token_cache::TokenCache;
 use anchor_lang::Id;
-use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
 use anchor_spl::token::Token;
-use autobahn_executor::swap_ix::generate_swap_ix_data;
+use autobahn_executor::swap_ix::{
+    generate_charge_fees_ix_data, generate_split_swap_ix_data, generate_swap_ix_data, SplitSwapLeg,
+};
+use itertools::Itertools;
 use router_lib::dex::{AccountProviderView, SwapInstruction, SwapMode};
+use router_lib::referral::referrer_vault_address;
 use solana_program::instruction::Instruction;
 use solana_program::pubkey::Pubkey;
+use std::collections::HashSet;
 use std::str::FromStr;
 
 const CU_PER_HOP_DEFAULT: u32 = 80_000;
 const CU_BASE: u32 = 150_000;
+const CU_TOKEN_TRANSFER: u32 = 6_000;
+// `TransferChecked` instruction data is [12, amount: u64, decimals: u8]
+const TOKEN_TRANSFER_AMOUNT_OFFSET: u16 = 1;
+
+/// Caller provided tweaks to the generated instructions
+#[derive(Clone, Debug, Default)]
+pub struct SwapOptions {
+    /// Token account (of the output mint) receiving the whole output instead of the wallet ATA
+    pub destination_token_account: Option<Pubkey>,
+    /// Platform fee charged on the output after the swap
+    pub platform_fee: Option<PlatformFeeOptions>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PlatformFeeOptions {
+    /// Token account (of the output mint) receiving the platform share
+    pub fee_account: Pubkey,
+    /// Total fee in output token native units, quoted out amounts are net of it
+    pub amount: u64,
+    /// Referrer wallet, gets its share in its referral vault for the output mint
+    pub referrer: Option<Pubkey>,
+}
 
 pub trait SwapStepInstructionBuilder {
     fn build_ix(
@@ -21,6 +51,13 @@ pub trait SwapStepInstructionBuilder {
         swap_mode: SwapMode,
         other_amount: u64,
     ) -> anyhow::Result<SwapInstruction>; // TODO handle multi hop from same edge ?
+
+    /// Token program owning the mint, used to derive and create its ATAs
+    fn token_program(&self, _mint: &Pubkey) -> Pubkey {
+        Token::id()
+    }
+
+    fn decimals(&self, mint: &Pubkey) -> anyhow::Result<u8>;
 }
 
 pub trait SwapInstructionsBuilder {
@@ -33,11 +70,13 @@ pub trait SwapInstructionsBuilder {
         max_slippage_bps: i32,
         other_amount_threshold: u64,
         swap_mode: SwapMode,
+        options: &SwapOptions,
     ) -> anyhow::Result<Swap>;
 }
 
 pub struct SwapStepInstructionBuilderImpl {
     pub chain_data: AccountProviderView,
+    pub token_cache: TokenCache,
 }
 
 impl SwapStepInstructionBuilder for SwapStepInstructionBuilderImpl {
@@ -62,18 +101,33 @@ impl SwapStepInstructionBuilder for SwapStepInstructionBuilderImpl {
             max_slippage_bps,
         )
     }
+
+    fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.token_cache.token_program(mint)
+    }
+
+    fn decimals(&self, mint: &Pubkey) -> anyhow::Result<u8> {
+        Ok(self.token_cache.token(*mint)?.decimals)
+    }
 }
 
 pub struct SwapInstructionsBuilderImpl<T: SwapStepInstructionBuilder> {
     ix_builder: T,
     router_version: u8,
+    // Platform share of the fee when there is a referrer, the referrer gets the rest
+    platform_fee_percent_with_referrer: u8,
 }
 
 impl<T: SwapStepInstructionBuilder> SwapInstructionsBuilderImpl<T> {
-    pub fn new(ix_builder: T, router_version: u8) -> SwapInstructionsBuilderImpl<T> {
+    pub fn new(
+        ix_builder: T,
+        router_version: u8,
+        platform_fee_percent_with_referrer: u8,
+    ) -> SwapInstructionsBuilderImpl<T> {
         Self {
             ix_builder,
             router_version,
+            platform_fee_percent_with_referrer,
         }
     }
 }
@@ -88,11 +142,17 @@ impl<T: SwapStepInstructionBuilder> SwapInstructionsBuilder for SwapInstructions
         max_slippage_bps: i32,
         other_amount_threshold: u64,
         swap_mode: SwapMode,
+        options: &SwapOptions,
     ) -> anyhow::Result<Swap> {
-        if route.steps.len() == 0 {
+        if route.legs.is_empty() || route.legs.iter().any(|x| x.steps.is_empty()) {
             anyhow::bail!("Can't generate instructions for empty route");
         }
 
+        if options.destination_token_account.is_some() && options.platform_fee.is_some() {
+            // the whole output is moved by the executor, nothing would be left to pay the fee
+            anyhow::bail!("Platform fee can't be charged when using a destination token account");
+        }
+
         let mut setup_instructions = vec![];
         let mut cleanup_instructions = vec![];
 
@@ -101,7 +161,7 @@ impl<T: SwapStepInstructionBuilder> SwapInstructionsBuilder for SwapInstructions
             Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap();
 
         if auto_wrap_sol && route.input_mint == sol_mint {
-            Self::create_ata(&wallet_pk, &mut setup_instructions, &sol_mint);
+            Self::create_ata(&wallet_pk, &mut setup_instructions, &sol_mint, &Token::id());
             let wsol_account = get_associated_token_address(wallet_pk, &sol_mint);
 
             let in_amount = match swap_mode {
@@ -127,57 +187,159 @@ impl<T: SwapStepInstructionBuilder> SwapInstructionsBuilder for SwapInstructions
         //   .. and it prevent using the "overquote" heuristic
         let max_slippage_for_hop_bps = max_slippage_bps * 2;
 
-        let swap_instructions = route
-            .steps
-            .iter()
-            .map(|x| {
-                self.ix_builder.build_ix(
-                    wallet_pk,
-                    x,
-                    max_slippage_for_hop_bps,
-                    swap_mode,
-                    other_amount_threshold,
-                )
-            })
-            .collect::<anyhow::Result<Vec<SwapInstruction>>>()?;
+        // ExactOut max input is shared between legs pro-rata of their expected input
+        let leg_in_amounts = route.legs.iter().map(|x| x.in_amount).collect_vec();
+        let leg_max_in_amounts = split_pro_rata(other_amount_threshold, &leg_in_amounts);
 
         let mut cu_estimate = CU_BASE;
+        let mut created_atas = HashSet::new();
+        let mut legs = Vec::with_capacity(route.legs.len());
+
+        for (leg, leg_max_in_amount) in route.legs.iter().zip(leg_max_in_amounts) {
+            let other_amount = match swap_mode {
+                SwapMode::ExactIn => other_amount_threshold,
+                SwapMode::ExactOut => leg_max_in_amount,
+            };
 
-        for step in &swap_instructions {
-            if auto_create_out || (step.out_mint == sol_mint && auto_wrap_sol) {
-                Self::create_ata(&wallet_pk, &mut setup_instructions, &step.out_mint);
-                cu_estimate += 12_000;
+            let leg_instructions = leg
+                .steps
+                .iter()
+                .map(|x| {
+                    self.ix_builder.build_ix(
+                        wallet_pk,
+                        x,
+                        max_slippage_for_hop_bps,
+                        swap_mode,
+                        other_amount,
+                    )
+                })
+                .collect::<anyhow::Result<Vec<SwapInstruction>>>()?;
+
+            for step in &leg_instructions {
+                cu_estimate += step.cu_estimate.unwrap_or(CU_PER_HOP_DEFAULT);
+
+                // legs of a split route end with the same mint
+                if !created_atas.insert(step.out_mint) {
+                    continue;
+                }
+
+                if auto_create_out || (step.out_mint == sol_mint && auto_wrap_sol) {
+                    let token_program = self.ix_builder.token_program(&step.out_mint);
+                    Self::create_ata(
+                        &wallet_pk,
+                        &mut setup_instructions,
+                        &step.out_mint,
+                        &token_program,
+                    );
+                    cu_estimate += 12_000;
+                }
+
+                if step.out_mint == sol_mint && auto_wrap_sol {
+                    let wsol_account = get_associated_token_address(wallet_pk, &sol_mint);
+                    Self::close_wsol_ata(&wallet_pk, &mut cleanup_instructions, &wsol_account)?;
+                    cu_estimate += 12_000;
+                }
             }
 
-            if step.out_mint == sol_mint && auto_wrap_sol {
-                let wsol_account = get_associated_token_address(wallet_pk, &sol_mint);
-                Self::close_wsol_ata(&wallet_pk, &mut cleanup_instructions, &wsol_account)?;
-                cu_estimate += 12_000;
+            let (mut instructions, in_out): (Vec<_>, Vec<_>) = leg_instructions
+                .into_iter()
+                .map(|x| (x.instruction, (x.in_amount_offset, x.out_pubkey)))
+                .unzip();
+            let (mut in_amount_offsets, mut out_accounts): (Vec<_>, Vec<_>) =
+                in_out.into_iter().unzip();
+
+            // Forward the leg output as an extra hop, the executor overrides
+            //   the transferred amount with the previous hop output
+            if let Some(destination) = options.destination_token_account {
+                let leg_out_account = *out_accounts.last().unwrap();
+                instructions.push(spl_token_2022::instruction::transfer_checked(
+                    &self.ix_builder.token_program(&route.output_mint),
+                    &leg_out_account,
+                    &route.output_mint,
+                    &destination,
+                    wallet_pk,
+                    &[],
+                    0,
+                    self.ix_builder.decimals(&route.output_mint)?,
+                )?);
+                in_amount_offsets.push(TOKEN_TRANSFER_AMOUNT_OFFSET);
+                out_accounts.push(destination);
+                cu_estimate += CU_TOKEN_TRANSFER;
             }
 
-            cu_estimate += step.cu_estimate.unwrap_or(CU_PER_HOP_DEFAULT);
+            legs.push(SplitSwapLeg {
+                instructions,
+                in_amount_offsets,
+                out_accounts,
+            });
         }
 
-        let (instructions, in_out): (Vec<_>, Vec<_>) = swap_instructions
-            .into_iter()
-            .map(|x| (x.instruction, (x.in_amount_offset, x.out_pubkey)))
-            .unzip();
-        let (in_amount_offsets, out_account_pubkeys): (Vec<_>, Vec<_>) = in_out.into_iter().unzip();
-
+        // Quoted amounts are net of the platform fee, the swap itself must cover it
+        let fee_amount = options.platform_fee.as_ref().map(|x| x.amount).unwrap_or(0);
         let min_out_amount = match swap_mode {
             SwapMode::ExactIn => other_amount_threshold,
             SwapMode::ExactOut => route.out_amount,
-        };
+        }
+        .saturating_add(fee_amount);
 
-        let swap_instruction = generate_swap_ix_data(
-            min_out_amount,
-            instructions.as_slice(),
-            in_amount_offsets.as_slice(),
-            get_associated_token_address(&wallet_pk, &route.input_mint),
-            out_account_pubkeys.as_slice(),
-            exec_program_id,
-            self.router_version,
+        let in_account = get_associated_token_address_with_program_id(
+            wallet_pk,
+            &route.input_mint,
+            &self.ix_builder.token_program(&route.input_mint),
         );
+        let swap_instruction = if legs.len() > 1 {
+            generate_split_swap_ix_data(
+                min_out_amount,
+                legs.as_slice(),
+                in_account,
+                exec_program_id,
+                self.router_version,
+            )
+        } else {
+            let leg = legs.remove(0);
+            generate_swap_ix_data(
+                min_out_amount,
+                leg.instructions.as_slice(),
+                leg.in_amount_offsets.as_slice(),
+                in_account,
+                leg.out_accounts.as_slice(),
+                exec_program_id,
+                self.router_version,
+            )
+        };
+
+        if let Some(platform_fee) = &options.platform_fee {
+            let referrer_vault = platform_fee
+                .referrer
+                .map(|referrer| referrer_vault_address(&referrer, &route.output_mint).0);
+            let platform_fee_percent = if referrer_vault.is_some() {
+                self.platform_fee_percent_with_referrer
+            } else {
+                100
+            };
+
+            // must run before closing the wSOL account
+            let token_program = self.ix_builder.token_program(&route.output_mint);
+            cleanup_instructions.insert(
+                0,
+                generate_charge_fees_ix_data(
+                    platform_fee.amount,
+                    platform_fee_percent,
+                    token_program,
+                    route.output_mint,
+                    get_associated_token_address_with_program_id(
+                        wallet_pk,
+                        &route.output_mint,
+                        &token_program,
+                    ),
+                    platform_fee.fee_account,
+                    *wallet_pk,
+                    referrer_vault,
+                    exec_program_id,
+                ),
+            );
+            cu_estimate += CU_TOKEN_TRANSFER * 2;
+        }
 
         Ok(Swap {
             setup_instructions,
@@ -188,6 +350,25 @@ impl<T: SwapStepInstructionBuilder> SwapInstructionsBuilder for SwapInstructions
     }
 }
 
+/// Split `total` proportionally to `weights`, the last part takes the rounding leftovers
+fn split_pro_rata(total: u64, weights: &[u64]) -> Vec<u64> {
+    let weight_sum = weights.iter().map(|x| *x as u128).sum::<u128>();
+    let mut remaining = total;
+    weights
+        .iter()
+        .enumerate()
+        .map(|(i, weight)| {
+            let part = if i == weights.len() - 1 || weight_sum == 0 {
+                remaining
+            } else {
+                ((total as u128 * *weight as u128) / weight_sum) as u64
+            };
+            remaining = remaining.saturating_sub(part);
+            part
+        })
+        .collect()
+}
+
 impl<T: SwapStepInstructionBuilder> SwapInstructionsBuilderImpl<T> {
     fn close_wsol_ata(
         wallet_pk: &&Pubkey,
@@ -204,13 +385,18 @@ impl<T: SwapStepInstructionBuilder> SwapInstructionsBuilderImpl<T> {
         Ok(())
     }
 
-    fn create_ata(wallet_pk: &&Pubkey, setup_instructions: &mut Vec<Instruction>, mint: &Pubkey) {
+    fn create_ata(
+        wallet_pk: &&Pubkey,
+        setup_instructions: &mut Vec<Instruction>,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) {
         setup_instructions.push(
             spl_associated_token_account::instruction::create_associated_token_account_idempotent(
                 &wallet_pk,
                 &wallet_pk,
                 &mint,
-                &Token::id(),
+                token_program,
             ),
         );
     }
@@ -220,6 +406,7 @@ impl<T: SwapStepInstructionBuilder> SwapInstructionsBuilderImpl<T> {
 mod tests {
     use super::*;
     use crate::edge::Edge;
+    use crate::routing_types::RouteLeg;
     use crate::test_utils::*;
     use router_feed_lib::router_rpc_client::RouterRpcClient;
     use router_lib::dex::{
@@ -354,11 +541,15 @@ mod tests {
                 cu_estimate: None,
             })
         }
+
+        fn decimals(&self, _mint: &Pubkey) -> anyhow::Result<u8> {
+            Ok(6)
+        }
     }
 
     #[test]
     fn should_fail_if_there_is_no_step() {
-        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0);
+        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0, 50);
         let wallet = 0.to_pubkey();
 
         let ixs = builder.build_ixs(
@@ -369,7 +560,7 @@ mod tests {
                 in_amount: 1000,
                 out_amount: 2000,
                 price_impact_bps: 0,
-                steps: vec![],
+                legs: vec![],
                 slot: 0,
                 accounts: None,
             },
@@ -378,6 +569,7 @@ mod tests {
             0,
             0,
             SwapMode::ExactIn,
+            &Default::default(),
         );
 
         assert!(ixs.is_err());
@@ -385,7 +577,7 @@ mod tests {
 
     #[test]
     fn should_fail_if_there_is_no_step_exact_out() {
-        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0);
+        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0, 50);
         let wallet = 0.to_pubkey();
 
         let ixs = builder.build_ixs(
@@ -396,7 +588,7 @@ mod tests {
                 in_amount: 1000,
                 out_amount: 2000,
                 price_impact_bps: 0,
-                steps: vec![],
+                legs: vec![],
                 slot: 0,
                 accounts: None,
             },
@@ -405,6 +597,7 @@ mod tests {
             0,
             0,
             SwapMode::ExactOut,
+            &Default::default(),
         );
 
         assert!(ixs.is_err());
@@ -423,7 +616,7 @@ mod tests {
         expected_setup_len: usize,
         expected_cleanup_len: usize,
     ) {
-        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0);
+        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0, 50);
         let wallet = 0.to_pubkey();
         let sol = Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap();
 
@@ -447,19 +640,23 @@ mod tests {
                     price_impact_bps: 0,
                     slot: 0,
                     accounts: None,
-                    steps: vec![RouteStep {
-                        edge: Arc::new(Edge {
-                            input_mint: in_mint,
-                            output_mint: out_mint,
-                            dex: Arc::new(MockDex {}),
-                            id: Arc::new(MockId {}),
-                            accounts_needed: 1,
-                            state: Default::default(),
-                        }),
+                    legs: vec![RouteLeg {
                         in_amount: 1000,
                         out_amount: 2000,
-                        fee_amount: 0,
-                        fee_mint: Default::default(),
+                        steps: vec![RouteStep {
+                            edge: Arc::new(Edge {
+                                input_mint: in_mint,
+                                output_mint: out_mint,
+                                dex: Arc::new(MockDex {}),
+                                id: Arc::new(MockId {}),
+                                accounts_needed: 1,
+                                state: Default::default(),
+                            }),
+                            in_amount: 1000,
+                            out_amount: 2000,
+                            fee_amount: 0,
+                            fee_mint: Default::default(),
+                        }],
                     }],
                 },
                 true,
@@ -467,6 +664,7 @@ mod tests {
                 0,
                 0,
                 swap_mode,
+                &Default::default(),
             )
             .unwrap();
 
@@ -476,7 +674,7 @@ mod tests {
 
     #[test]
     fn should_build_ixs() {
-        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0);
+        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0, 50);
         let wallet = 0.to_pubkey();
 
         let ixs = builder
@@ -490,19 +688,23 @@ mod tests {
                     price_impact_bps: 0,
                     slot: 0,
                     accounts: None,
-                    steps: vec![RouteStep {
-                        edge: Arc::new(Edge {
-                            input_mint: 1.to_pubkey(),
-                            output_mint: 2.to_pubkey(),
-                            accounts_needed: 1,
-                            dex: Arc::new(MockDex {}),
-                            id: Arc::new(MockId {}),
-                            state: Default::default(),
-                        }),
+                    legs: vec![RouteLeg {
                         in_amount: 1000,
                         out_amount: 2000,
-                        fee_amount: 0,
-                        fee_mint: Default::default(),
+                        steps: vec![RouteStep {
+                            edge: Arc::new(Edge {
+                                input_mint: 1.to_pubkey(),
+                                output_mint: 2.to_pubkey(),
+                                accounts_needed: 1,
+                                dex: Arc::new(MockDex {}),
+                                id: Arc::new(MockId {}),
+                                state: Default::default(),
+                            }),
+                            in_amount: 1000,
+                            out_amount: 2000,
+                            fee_amount: 0,
+                            fee_mint: Default::default(),
+                        }],
                     }],
                 },
                 false,
@@ -510,6 +712,7 @@ mod tests {
                 0,
                 0,
                 SwapMode::ExactIn,
+                &Default::default(),
             )
             .unwrap();
 
@@ -519,7 +722,7 @@ mod tests {
 
     #[test]
     fn should_build_ixs_exact_out() {
-        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0);
+        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0, 50);
         let wallet = 0.to_pubkey();
 
         let ixs = builder
@@ -533,19 +736,23 @@ mod tests {
                     price_impact_bps: 0,
                     slot: 0,
                     accounts: None,
-                    steps: vec![RouteStep {
-                        edge: Arc::new(Edge {
-                            input_mint: 1.to_pubkey(),
-                            output_mint: 2.to_pubkey(),
-                            accounts_needed: 1,
-                            dex: Arc::new(MockDex {}),
-                            id: Arc::new(MockId {}),
-                            state: Default::default(),
-                        }),
+                    legs: vec![RouteLeg {
                         in_amount: 1000,
                         out_amount: 2000,
-                        fee_amount: 0,
-                        fee_mint: Default::default(),
+                        steps: vec![RouteStep {
+                            edge: Arc::new(Edge {
+                                input_mint: 1.to_pubkey(),
+                                output_mint: 2.to_pubkey(),
+                                accounts_needed: 1,
+                                dex: Arc::new(MockDex {}),
+                                id: Arc::new(MockId {}),
+                                state: Default::default(),
+                            }),
+                            in_amount: 1000,
+                            out_amount: 2000,
+                            fee_amount: 0,
+                            fee_mint: Default::default(),
+                        }],
                     }],
                 },
                 false,
@@ -553,10 +760,289 @@ mod tests {
                 0,
                 0,
                 SwapMode::ExactOut,
+                &Default::default(),
             )
             .unwrap();
 
         assert_eq!(0, ixs.setup_instructions.len());
         assert_eq!(0, ixs.cleanup_instructions.len());
     }
+
+    struct MockToken2022SwapStepInstructionBuilder {}
+
+    impl SwapStepInstructionBuilder for MockToken2022SwapStepInstructionBuilder {
+        fn build_ix(
+            &self,
+            wallet_pk: &Pubkey,
+            step: &RouteStep,
+            max_slippage_bps: i32,
+            swap_mode: SwapMode,
+            other_amount: u64,
+        ) -> anyhow::Result<SwapInstruction> {
+            MockSwapStepInstructionBuilder {}.build_ix(
+                wallet_pk,
+                step,
+                max_slippage_bps,
+                swap_mode,
+                other_amount,
+            )
+        }
+
+        fn token_program(&self, _mint: &Pubkey) -> Pubkey {
+            spl_token_2022::ID
+        }
+
+        fn decimals(&self, _mint: &Pubkey) -> anyhow::Result<u8> {
+            Ok(6)
+        }
+    }
+
+    #[test]
+    fn should_create_out_ata_with_mint_token_program() {
+        let builder =
+            SwapInstructionsBuilderImpl::new(MockToken2022SwapStepInstructionBuilder {}, 0, 50);
+        let wallet = 0.to_pubkey();
+
+        let ixs = builder
+            .build_ixs(
+                &wallet,
+                &Route {
+                    input_mint: 1.to_pubkey(),
+                    output_mint: 2.to_pubkey(),
+                    in_amount: 1000,
+                    out_amount: 2000,
+                    price_impact_bps: 0,
+                    slot: 0,
+                    accounts: None,
+                    legs: vec![RouteLeg {
+                        in_amount: 1000,
+                        out_amount: 2000,
+                        steps: vec![RouteStep {
+                            edge: Arc::new(Edge {
+                                input_mint: 1.to_pubkey(),
+                                output_mint: 2.to_pubkey(),
+                                accounts_needed: 1,
+                                dex: Arc::new(MockDex {}),
+                                id: Arc::new(MockId {}),
+                                state: Default::default(),
+                            }),
+                            in_amount: 1000,
+                            out_amount: 2000,
+                            fee_amount: 0,
+                            fee_mint: Default::default(),
+                        }],
+                    }],
+                },
+                false,
+                true,
+                0,
+                0,
+                SwapMode::ExactIn,
+                &Default::default(),
+            )
+            .unwrap();
+
+        assert_eq!(1, ixs.setup_instructions.len());
+        let create_ata = &ixs.setup_instructions[0];
+        assert_eq!(
+            create_ata.accounts[1].pubkey,
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &wallet,
+                &2.to_pubkey(),
+                &spl_token_2022::ID
+            )
+        );
+        assert_eq!(create_ata.accounts[5].pubkey, spl_token_2022::ID);
+    }
+
+    #[test]
+    fn should_build_split_swap_ix_for_multiple_legs() {
+        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0, 50);
+        let wallet = 0.to_pubkey();
+
+        let make_leg = |in_amount: u64, out_amount: u64| RouteLeg {
+            in_amount,
+            out_amount,
+            steps: vec![RouteStep {
+                edge: Arc::new(Edge {
+                    input_mint: 1.to_pubkey(),
+                    output_mint: 2.to_pubkey(),
+                    accounts_needed: 1,
+                    dex: Arc::new(MockDex {}),
+                    id: Arc::new(MockId {}),
+                    state: Default::default(),
+                }),
+                in_amount,
+                out_amount,
+                fee_amount: 0,
+                fee_mint: Default::default(),
+            }],
+        };
+
+        let ixs = builder
+            .build_ixs(
+                &wallet,
+                &Route {
+                    input_mint: 1.to_pubkey(),
+                    output_mint: 2.to_pubkey(),
+                    in_amount: 1000,
+                    out_amount: 2000,
+                    price_impact_bps: 0,
+                    slot: 0,
+                    accounts: None,
+                    legs: vec![make_leg(700, 1200), make_leg(300, 800)],
+                },
+                false,
+                true,
+                0,
+                1001,
+                SwapMode::ExactIn,
+                &Default::default(),
+            )
+            .unwrap();
+
+        let data = &ixs.swap_instruction.data;
+        assert_eq!(
+            autobahn_executor::Instructions::ExecuteSplitSwap as u8,
+            data[0]
+        );
+        // aggregate min out
+        assert_eq!(1001, u64::from_le_bytes(data[1..9].try_into().unwrap()));
+        // leg count and first leg hop count
+        assert_eq!(2, data[9]);
+        assert_eq!(1, data[10]);
+        // output ATA is created only once
+        assert_eq!(1, ixs.setup_instructions.len());
+    }
+
+    #[test]
+    fn should_forward_output_to_destination_token_account() {
+        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0, 50);
+        let wallet = 0.to_pubkey();
+        let destination = 3.to_pubkey();
+
+        let ixs = builder
+            .build_ixs(
+                &wallet,
+                &make_route(1.to_pubkey(), 2.to_pubkey()),
+                false,
+                false,
+                0,
+                0,
+                SwapMode::ExactIn,
+                &SwapOptions {
+                    destination_token_account: Some(destination),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // out account of the transfer hop + destination in the transfer accounts
+        let destination_metas = ixs
+            .swap_instruction
+            .accounts
+            .iter()
+            .filter(|x| x.pubkey == destination)
+            .count();
+        assert_eq!(2, destination_metas);
+        // number of hops
+        assert_eq!(2, ixs.swap_instruction.data[9]);
+    }
+
+    #[test]
+    fn should_charge_platform_fee_before_unwrapping_sol() {
+        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0, 30);
+        let wallet = 0.to_pubkey();
+        let sol = Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap();
+
+        let ixs = builder
+            .build_ixs(
+                &wallet,
+                &make_route(1.to_pubkey(), sol),
+                true,
+                false,
+                0,
+                0,
+                SwapMode::ExactIn,
+                &SwapOptions {
+                    platform_fee: Some(PlatformFeeOptions {
+                        fee_account: 4.to_pubkey(),
+                        amount: 1_000,
+                        referrer: Some(5.to_pubkey()),
+                    }),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(2, ixs.cleanup_instructions.len());
+        let fee_ix = &ixs.cleanup_instructions[0];
+        assert_eq!(autobahn_executor::id(), fee_ix.program_id);
+        assert_eq!(
+            autobahn_executor::Instructions::ChargeFeesV2 as u8,
+            fee_ix.data[0]
+        );
+        assert_eq!(
+            1_000,
+            u64::from_le_bytes(fee_ix.data[1..9].try_into().unwrap())
+        );
+        // configured platform share, the referrer gets the rest
+        assert_eq!(30, fee_ix.data[9]);
+    }
+
+    #[test]
+    fn should_fail_to_charge_fee_when_output_goes_to_destination() {
+        let builder = SwapInstructionsBuilderImpl::new(MockSwapStepInstructionBuilder {}, 0, 50);
+        let wallet = 0.to_pubkey();
+
+        let ixs = builder.build_ixs(
+            &wallet,
+            &make_route(1.to_pubkey(), 2.to_pubkey()),
+            false,
+            false,
+            0,
+            0,
+            SwapMode::ExactIn,
+            &SwapOptions {
+                destination_token_account: Some(3.to_pubkey()),
+                platform_fee: Some(PlatformFeeOptions {
+                    fee_account: 4.to_pubkey(),
+                    amount: 1_000,
+                    referrer: None,
+                }),
+            },
+        );
+
+        assert!(ixs.is_err());
+    }
+
+    fn make_route(input_mint: Pubkey, output_mint: Pubkey) -> Route {
+        Route {
+            input_mint,
+            output_mint,
+            in_amount: 1000,
+            out_amount: 2000,
+            price_impact_bps: 0,
+            slot: 0,
+            accounts: None,
+            legs: vec![RouteLeg {
+                in_amount: 1000,
+                out_amount: 2000,
+                steps: vec![RouteStep {
+                    edge: Arc::new(Edge {
+                        input_mint,
+                        output_mint,
+                        accounts_needed: 1,
+                        dex: Arc::new(MockDex {}),
+                        id: Arc::new(MockId {}),
+                        state: Default::default(),
+                    }),
+                    in_amount: 1000,
+                    out_amount: 2000,
+                    fee_amount: 0,
+                    fee_mint: Default::default(),
+                }],
+            }],
+        }
+    }
 }
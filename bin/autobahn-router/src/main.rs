@@ -1,17 +1,23 @@
+use crate::alt::alt_manager::{spawn_alt_manager_job, EdgeUsage};
+use crate::config_reloader::{path_warming_amounts, spawn_config_reload_job, ConfigReloader};
 use crate::edge_updater::{spawn_updater_job, Dex};
+use crate::fork_tracker::{ConfirmedWritesBuffer, ForkTracker};
 use crate::hot_mints::HotMintsCache;
 use crate::ix_builder::{SwapInstructionsBuilderImpl, SwapStepInstructionBuilderImpl};
 use crate::liquidity::{spawn_liquidity_updater_job, LiquidityProvider};
 use crate::path_warmer::spawn_path_warmer_job;
+use crate::pool_discovery::spawn_pool_discovery_job;
 use crate::prometheus_sync::PrometheusSync;
-use crate::routing::Routing;
+use crate::routing::{Routing, RoutingPriceGraph};
+use crate::server::admin_server::AdminServer;
 use crate::server::alt_provider::RpcAltProvider;
 use crate::server::hash_provider::RpcHashProvider;
 use crate::server::http_server::HttpServer;
 use crate::server::live_account_provider::LiveAccountProvider;
 use crate::server::route_provider::RoutingRouteProvider;
-use crate::source::mint_accounts_source::{request_mint_metadata, Token};
-use crate::token_cache::{Decimals, TokenCache};
+use crate::source::account_filter::AccountFilter;
+use crate::source::mint_accounts_source::request_mint_metadata;
+use crate::token_cache::{Decimals, TokenCache, TokenCacheAccountProvider};
 use crate::tx_watcher::spawn_tx_watcher_jobs;
 use crate::util::tokio_spawn;
 use dex_orca::OrcaDex;
@@ -30,11 +36,14 @@ use router_lib::dex::{
 };
 use router_lib::mango;
 use router_lib::price_feeds::composite::CompositePriceFeed;
+use router_lib::price_feeds::onchain::{OnChainPriceFeed, OnChainPriceSource};
+use router_lib::price_feeds::oracle::{OracleAccountSource, OraclePriceFeed};
 use router_lib::price_feeds::price_cache::PriceCache;
 use router_lib::price_feeds::price_feed::PriceFeed;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_client::RpcClient as BlockingRpcClient;
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::sysvar;
 use source::geyser;
 use std::env;
 use std::process::exit;
@@ -44,16 +53,20 @@ use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 
 mod alt;
+mod checkpoint;
+mod config_reloader;
 mod debug_tools;
 mod dex;
 pub mod edge;
 mod edge_updater;
+mod fork_tracker;
 mod hot_mints;
 pub mod ix_builder;
 mod liquidity;
 mod metrics;
 mod mock;
 mod path_warmer;
+mod pool_discovery;
 pub mod prelude;
 mod prometheus_sync;
 pub mod routing;
@@ -97,6 +110,7 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let config = Config::load(&args[1])?;
+    let config_reloader = Arc::new(ConfigReloader::new(args[1].clone(), config.clone()));
     let router_version = RouterVersion::OverestimateAmount;
 
     if config.metrics.output_http {
@@ -162,6 +176,7 @@ async fn main() -> anyhow::Result<()> {
         account_write_receiver,
         slot_receiver,
         account_update_sender.clone(),
+        source_config.confirmed_only.unwrap_or(false),
         exit_sender.subscribe(),
     );
 
@@ -216,11 +231,22 @@ async fn main() -> anyhow::Result<()> {
     let (price_cache, price_cache_job) =
         PriceCache::new(exit_sender.subscribe(), price_feed.receiver());
 
-    let path_warming_amounts = config
-        .routing
-        .path_warming_amounts
-        .clone()
-        .unwrap_or(vec![100, 1000]);
+    // oracle prices only go to the cache, edges are not refreshed on every oracle write
+    let mut oracle_price_feed = config
+        .price_feed
+        .oracles
+        .as_ref()
+        .is_some_and(|x| !x.is_empty())
+        .then(|| OraclePriceFeed::spawn(config.price_feed.clone(), exit_sender.subscribe()));
+    let oracle_price_cache_job = oracle_price_feed
+        .as_mut()
+        .map(|(feed, _)| price_cache.add_feed(exit_sender.subscribe(), feed.receiver()));
+    let oracle_accounts = oracle_price_feed
+        .as_ref()
+        .map(|(feed, _)| feed.oracle_accounts())
+        .unwrap_or_default();
+
+    let path_warming_amounts = path_warming_amounts(&config.routing);
 
     let mut orca_config = HashMap::new();
     orca_config.insert(
@@ -285,6 +311,22 @@ async fn main() -> anyhow::Result<()> {
             config.raydium.take_all_mints,
             &config.raydium.mints
         ),
+        dex::generic::build_dex!(
+            dex_raydium_clmm::RaydiumClmmDex::initialize(&mut router_rpc, HashMap::new(),).await?,
+            &mango_data,
+            config.raydium_clmm.enabled,
+            config.raydium_clmm.add_mango_tokens,
+            config.raydium_clmm.take_all_mints,
+            &config.raydium_clmm.mints
+        ),
+        dex::generic::build_dex!(
+            dex_meteora_dlmm::MeteoraDlmmDex::initialize(&mut router_rpc, HashMap::new(),).await?,
+            &mango_data,
+            config.meteora_dlmm.enabled,
+            config.meteora_dlmm.add_mango_tokens,
+            config.meteora_dlmm.take_all_mints,
+            &config.meteora_dlmm.mints
+        ),
         dex::generic::build_dex!(
             dex_openbook_v2::OpenbookV2Dex::initialize(&mut router_rpc, HashMap::new(),).await?,
             &mango_data,
@@ -293,6 +335,14 @@ async fn main() -> anyhow::Result<()> {
             config.openbook_v2.take_all_mints,
             &config.openbook_v2.mints
         ),
+        dex::generic::build_dex!(
+            dex_phoenix::PhoenixDex::initialize(&mut router_rpc, HashMap::new(),).await?,
+            &mango_data,
+            config.phoenix.enabled,
+            config.phoenix.add_mango_tokens,
+            config.phoenix.take_all_mints,
+            &config.phoenix.mints
+        ),
         dex::generic::build_dex!(
             dex_infinity::InfinityDex::initialize(&mut router_rpc, HashMap::new(),).await?,
             &mango_data,
@@ -331,10 +381,18 @@ async fn main() -> anyhow::Result<()> {
             number_of_accounts_per_gma,
         )
         .await;
-        let mut data: HashMap<Pubkey, token_cache::Decimals> = HashMap::new();
-        for (mint_pubkey, Token { mint, decimals }) in mint_metadata {
-            assert_eq!(mint_pubkey, mint);
-            data.insert(mint_pubkey, decimals as Decimals);
+        let mut data: HashMap<Pubkey, token_cache::Token> = HashMap::new();
+        for (mint_pubkey, token) in mint_metadata {
+            assert_eq!(mint_pubkey, token.mint);
+            data.insert(
+                mint_pubkey,
+                token_cache::Token {
+                    mint: token.mint,
+                    decimals: token.decimals as Decimals,
+                    program: token.program,
+                    transfer_fee: token.transfer_fee,
+                },
+            );
         }
         TokenCache::new(data)
     };
@@ -348,6 +406,57 @@ async fn main() -> anyhow::Result<()> {
     let chain_data_wrapper =
         Arc::new(ChainDataAccountProvider::new(chain_data.clone())) as AccountProviderView;
 
+    let oracle_price_feed_job = oracle_price_feed.map(|(mut feed, job)| {
+        feed.set_account_source(OracleAccountSource {
+            chain_data: chain_data_wrapper.clone(),
+            account_updates: account_update_sender.subscribe(),
+        });
+        job
+    });
+
+    let discover_new_pools = config.routing.discover_new_pools.unwrap_or(false);
+    let (new_edges_sender, new_edges_receiver) = async_channel::unbounded::<Vec<Arc<Edge>>>();
+    // edges refreshed by the updaters, used to push new quotes to websocket subscribers
+    let (repriced_edges_sender, _) = broadcast::channel::<Vec<(Pubkey, Pubkey)>>(10_000);
+
+    let routing = Arc::new(Routing::new(
+        &config,
+        path_warming_amounts.clone(),
+        edges.clone(),
+    ));
+
+    price_feed.set_onchain_source(OnChainPriceSource {
+        chain_data: chain_data_wrapper.clone(),
+        graph: Arc::new(RoutingPriceGraph {
+            routing: routing.clone(),
+            token_cache: token_cache.clone(),
+        }),
+    });
+
+    // restored before the updaters and geyser start, newer writes replace the restored accounts
+    let warm_state = checkpoint::WarmState {
+        chain_data: chain_data.clone(),
+        routing: routing.clone(),
+        hot_mints: hot_mints.clone(),
+        price_cache: price_cache.clone(),
+    };
+    let warm_start = match &config.checkpoint {
+        Some(checkpoint_config) => checkpoint::restore(checkpoint_config, &warm_state)
+            .unwrap_or_else(|e| {
+                warn!("Failed to restore checkpoint, cold start: {:?}", e);
+                false
+            }),
+        None => false,
+    };
+
+    // shared by the updaters of all dexes
+    let edge_updater_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(config.routing.edge_updater_threads.unwrap_or(0))
+            .thread_name(|i| format!("edge_updater_{i}"))
+            .build()?,
+    );
+
     let update_jobs = dexs
         .iter()
         .enumerate()
@@ -358,26 +467,42 @@ async fn main() -> anyhow::Result<()> {
                 chain_data_wrapper.clone(),
                 token_cache.clone(),
                 price_cache.clone(),
+                routing.clone(),
+                hot_mints.clone(),
+                edge_updater_pool.clone(),
                 path_warming_amounts.clone(),
                 price_feed.register_mint_sender(),
+                new_edges_sender.clone(),
+                repriced_edges_sender.clone(),
                 ready_channels[i].0.clone(),
                 rpc_slot_sender.subscribe(),
                 account_update_sender.subscribe(),
                 metadata_update_sender.subscribe(),
                 price_feed.receiver(),
+                config_reloader.subscribe(),
                 exit_sender.subscribe(),
             )
         })
         .collect_vec();
 
-    let routing = Arc::new(Routing::new(
-        &config,
-        path_warming_amounts.clone(),
-        edges.clone(),
-    ));
+    let config_reload_job = spawn_config_reload_job(
+        config_reloader.clone(),
+        routing.clone(),
+        exit_sender.subscribe(),
+    );
+    let pool_discovery_job = discover_new_pools.then(|| {
+        spawn_pool_discovery_job(
+            &source_config,
+            routing.clone(),
+            token_cache.clone(),
+            price_feed.register_mint_sender(),
+            new_edges_receiver,
+            exit_sender.subscribe(),
+        )
+    });
     let route_provider = Arc::new(RoutingRouteProvider {
         chain_data: chain_data_wrapper.clone(),
-        routing,
+        routing: routing.clone(),
         hot_mints: hot_mints.clone(),
         prices: price_cache.clone(),
         tokens: token_cache.clone(),
@@ -389,20 +514,55 @@ async fn main() -> anyhow::Result<()> {
         last_update: Default::default(),
     });
 
+    // routes are only counted when the alt manager consumes the counters
+    let alt_auto_extend = config
+        .alt_manager
+        .as_ref()
+        .is_some_and(|x| x.auto_extend.unwrap_or(false));
     let alt_provider = Arc::new(RpcAltProvider {
         rpc_client: build_rpc(&source_config),
         cache: Default::default(),
+        managed: Default::default(),
+        edge_usage: alt_auto_extend.then(EdgeUsage::default),
     });
+    let alt_manager_job = config
+        .alt_manager
+        .as_ref()
+        .map(|alt_manager_config| {
+            spawn_alt_manager_job(
+                alt_manager_config,
+                alt_auto_extend,
+                config.routing.lookup_tables.clone(),
+                build_rpc(&source_config),
+                routing.clone(),
+                chain_data_wrapper.clone(),
+                alt_provider.clone(),
+                exit_sender.subscribe(),
+            )
+        })
+        .transpose()?;
 
     let live_account_provider = Arc::new(LiveAccountProvider {
         rpc_client: build_blocking_rpc(&source_config),
     });
 
+    let platform_fee_percent_with_referrer = config
+        .server
+        .platform_fee_percent_with_referrer
+        .unwrap_or(50);
+    if platform_fee_percent_with_referrer > 100 {
+        bail!("server.platform_fee_percent_with_referrer must be at most 100");
+    }
     let ix_builder = Arc::new(SwapInstructionsBuilderImpl::new(
         SwapStepInstructionBuilderImpl {
-            chain_data: chain_data_wrapper.clone(),
+            chain_data: Arc::new(TokenCacheAccountProvider::new(
+                chain_data_wrapper.clone(),
+                token_cache.clone(),
+            )),
+            token_cache: token_cache.clone(),
         },
         router_version as u8,
+        platform_fee_percent_with_referrer,
     ));
 
     let liquidity_provider = Arc::new(RwLock::new(LiquidityProvider::new(
@@ -423,12 +583,22 @@ async fn main() -> anyhow::Result<()> {
         live_account_provider,
         liquidity_provider.clone(),
         ix_builder,
+        repriced_edges_sender,
         config.clone(),
         exit_sender.subscribe(),
     )
     .await?;
 
-    let filters = dexs
+    let admin_server_job = AdminServer::start(
+        routing.clone(),
+        config_reloader.clone(),
+        &config,
+        exit_sender.subscribe(),
+    )
+    .await?
+    .map(|x| x.join_handle);
+
+    let accounts = dexs
         .iter()
         .flat_map(|x| x.edges_per_pk.keys())
         .copied()
@@ -441,13 +611,40 @@ async fn main() -> anyhow::Result<()> {
                 })
                 .flatten(),
         )
+        // adapters read on-chain time from the Clock sysvar
+        .chain([sysvar::clock::ID])
+        .chain(oracle_accounts.iter().copied())
         .collect::<HashSet<_>>();
 
-    debug_tools::set_global_filters(&filters);
+    debug_tools::set_global_filters(&accounts);
+
+    let filters = if discover_new_pools {
+        AccountFilter {
+            accounts,
+            discovery_programs: dexs.iter().flat_map(|x| x.programs()).collect(),
+            discovery_token_owners: dexs
+                .iter()
+                .flat_map(|x| match &x.subscription_mode {
+                    DexSubscriptionMode::Mixed(m) => m.token_accounts_for_owner.clone(),
+                    _ => HashSet::new(),
+                })
+                .collect(),
+        }
+    } else {
+        AccountFilter {
+            accounts,
+            ..AccountFilter::default()
+        }
+    };
 
     info!(
-        "Will only react to account writes for {} account(s)",
-        filters.len()
+        "Will only react to account writes for {} account(s){}",
+        filters.accounts.len(),
+        if discover_new_pools {
+            " and accounts of subscribed programs"
+        } else {
+            ""
+        }
     );
 
     let subscribed_accounts = dexs
@@ -458,6 +655,8 @@ async fn main() -> anyhow::Result<()> {
             DexSubscriptionMode::Mixed(m) => m.accounts.clone().into_iter(),
             DexSubscriptionMode::Disabled => HashSet::new().into_iter(),
         })
+        .chain([sysvar::clock::ID])
+        .chain(oracle_accounts)
         .collect();
 
     let subscribed_programs = dexs
@@ -506,14 +705,14 @@ async fn main() -> anyhow::Result<()> {
 
     let mango_watcher_job = mango::mango_fetcher::spawn_mango_watcher(&mango_data, &config);
     let path_warmer_job = spawn_path_warmer_job(
-        &config.routing,
+        config_reloader.subscribe(),
         hot_mints.clone(),
         mango_data.clone(),
         route_provider.clone(),
         token_cache,
         price_cache,
-        path_warming_amounts,
         exit_flag.clone(),
+        warm_start,
     );
 
     let (ready_sender, ready_receiver) = async_channel::bounded::<()>(1);
@@ -535,21 +734,43 @@ async fn main() -> anyhow::Result<()> {
         tx_watcher_job,
         account_update_job,
         liquidity_job,
+        path_warmer_job,
+        config_reload_job,
     ]
     .into_iter()
     .chain(update_jobs.into_iter())
     .chain(mango_watcher_job.into_iter())
-    .chain(path_warmer_job.into_iter())
+    .chain(pool_discovery_job.into_iter())
+    .chain(alt_manager_job.into_iter())
+    .chain(oracle_price_feed_job.into_iter())
+    .chain(oracle_price_cache_job.into_iter())
+    .chain(admin_server_job.into_iter())
     .collect();
 
+    let mut checkpoint_job = None;
     loop {
         tokio::select!(
             _ = jobs.next() => {
+                if exit_flag.load(atomic::Ordering::Relaxed) {
+                    if let Some(job) = checkpoint_job.take() {
+                        // let the last checkpoint be written
+                        let _ = tokio::time::timeout(Duration::from_secs(60), job).await;
+                    }
+                }
                 error!("A critical job exited, aborting run..");
                 exit(-1);
             },
             Ok(_) = ready_receiver.recv() => {
                 info!("autobahn-router setup complete");
+                // not before every dex received its snapshot,
+                // a good checkpoint must not be replaced by a partial one
+                checkpoint_job = config.checkpoint.as_ref().map(|checkpoint_config| {
+                    checkpoint::spawn_checkpoint_job(
+                        checkpoint_config,
+                        warm_state.clone(),
+                        exit_sender.subscribe(),
+                    )
+                });
             },
         );
     }
@@ -561,6 +782,11 @@ fn build_price_feed(
     config: &Config,
     exit_sender: &broadcast::Sender<()>,
 ) -> (Box<dyn PriceFeed>, JoinHandle<()>) {
+    if config.price_feed.onchain_only.unwrap_or(false) {
+        let x = OnChainPriceFeed::start(config.price_feed.clone(), exit_sender.subscribe());
+        return (Box::new(x.0) as Box<dyn PriceFeed>, x.1);
+    }
+
     let x = CompositePriceFeed::start(config.price_feed.clone(), exit_sender.subscribe());
     (Box::new(x.0) as Box<dyn PriceFeed>, x.1)
 }
@@ -588,12 +814,15 @@ fn start_chaindata_updating(
     account_writes: async_channel::Receiver<AccountOrSnapshotUpdate>,
     slot_updates: async_channel::Receiver<SlotUpdate>,
     account_update_sender: broadcast::Sender<(Pubkey, Pubkey, u64)>,
+    confirmed_only: bool,
     mut exit: broadcast::Receiver<()>,
 ) -> JoinHandle<()> {
     use mango_feeds_connector::chain_data::SlotData;
 
     tokio_spawn("chain_data", async move {
         let mut most_recent_seen_slot = 0;
+        let mut fork_tracker = ForkTracker::default();
+        let mut confirmed_writes = confirmed_only.then(ConfirmedWritesBuffer::default);
 
         loop {
             tokio::select! {
@@ -609,14 +838,18 @@ fn start_chaindata_updating(
                     };
 
                     let mut writer = chain_data.write().unwrap();
-                    handle_updated_account(&mut most_recent_seen_slot, &mut writer, update, &account_update_sender);
+                    if let Some(update) = hold_until_confirmed(&mut confirmed_writes, update) {
+                        handle_updated_account(&mut most_recent_seen_slot, &mut writer, &mut fork_tracker, update, &account_update_sender);
+                    }
 
                     let mut batchsize: u32 = 0;
                     let started_at = Instant::now();
                     'batch_loop: while let Ok(update) = account_writes.try_recv() {
                         batchsize += 1;
 
-                        handle_updated_account(&mut most_recent_seen_slot, &mut writer, update, &account_update_sender);
+                        if let Some(update) = hold_until_confirmed(&mut confirmed_writes, update) {
+                            handle_updated_account(&mut most_recent_seen_slot, &mut writer, &mut fork_tracker, update, &account_update_sender);
+                        }
 
                         // budget for microbatch
                         if batchsize > 10 || started_at.elapsed() > Duration::from_micros(500) {
@@ -634,25 +867,52 @@ fn start_chaindata_updating(
                     debug!("chain_data updater got slot: {} ({:?}) -- channel sizes: {} {}", slot_update.slot, slot_update.status,
                     slot_updates.len(), account_writes.len());
 
-                    chain_data.write().unwrap().update_slot(SlotData {
+                    let mut writer = chain_data.write().unwrap();
+                    writer.update_slot(SlotData {
                         slot: slot_update.slot,
                         parent: slot_update.parent,
                         status: slot_update.status,
                         chain: 0,
                     });
 
-                    // TODO: slot updates can significantly affect state, do we need to track what needs to be updated
-                    // when switching to a different fork?
+                    if let Some(confirmed_writes) = &mut confirmed_writes {
+                        for account_write in confirmed_writes.on_slot(slot_update.slot, slot_update.status) {
+                            handle_updated_account(&mut most_recent_seen_slot, &mut writer, &mut fork_tracker, AccountOrSnapshotUpdate::AccountUpdate(account_write), &account_update_sender);
+                        }
+                    }
+
+                    // live version of the accounts written on the previous or the new fork changed,
+                    // edges using them need to be reloaded
+                    for (pubkey, owner) in fork_tracker.on_slot(slot_update.slot, slot_update.parent, slot_update.status) {
+                        // ignore failing sends when there are no receivers
+                        let _err = account_update_sender.send((pubkey, owner, slot_update.slot));
+                    }
                 }
-                // TODO: update Clock Sysvar
+                // Clock sysvar is subscribed like any other account and comes in with account_writes
             }
         }
     })
 }
 
+/// In confirmed only mode, account writes are applied once their slot is confirmed
+fn hold_until_confirmed(
+    confirmed_writes: &mut Option<ConfirmedWritesBuffer>,
+    update: AccountOrSnapshotUpdate,
+) -> Option<AccountOrSnapshotUpdate> {
+    match (confirmed_writes, update) {
+        (Some(confirmed_writes), AccountOrSnapshotUpdate::AccountUpdate(account_write)) => {
+            confirmed_writes
+                .push(account_write)
+                .map(AccountOrSnapshotUpdate::AccountUpdate)
+        }
+        (_, update) => Some(update),
+    }
+}
+
 fn handle_updated_account(
     most_recent_seen_slot: &mut u64,
     chain_data: &mut RwLockWriteGuard<ChainData>,
+    fork_tracker: &mut ForkTracker,
     update: AccountOrSnapshotUpdate,
     account_update_sender: &broadcast::Sender<(Pubkey, Pubkey, u64)>,
 ) {
@@ -663,9 +923,15 @@ fn handle_updated_account(
     fn one_update(
         most_recent_seen_slot: &mut u64,
         chain_data: &mut RwLockWriteGuard<ChainData>,
+        fork_tracker: &mut ForkTracker,
         account_update_sender: &broadcast::Sender<(Pubkey, Pubkey, u64)>,
         account_write: AccountWrite,
     ) {
+        fork_tracker.on_account_write(
+            account_write.slot,
+            account_write.pubkey,
+            account_write.owner,
+        );
         chain_data.update_account(
             account_write.pubkey,
             AccountData {
@@ -702,6 +968,7 @@ fn handle_updated_account(
         AccountOrSnapshotUpdate::AccountUpdate(account_write) => one_update(
             most_recent_seen_slot,
             chain_data,
+            fork_tracker,
             account_update_sender,
             account_write,
         ),
@@ -710,6 +977,7 @@ fn handle_updated_account(
                 one_update(
                     most_recent_seen_slot,
                     chain_data,
+                    fork_tracker,
                     account_update_sender,
                     account_write,
                 )
@@ -26,6 +26,10 @@ lazy_static::lazy_static! {
         register_int_gauge!("grpc_no_update_for_duration_ms", "Did not get any message from Geyser gPRC for this duration").unwrap();
     pub static ref GRPC_TO_EDGE_SLOT_LAG: IntGaugeVec =
         register_int_gauge_vec!(opts!("router_grpc_to_edge_slot_lag", "RPC Slot vs last slot used to update edges"), &["dex_name"]).unwrap();
+    pub static ref EDGE_UPDATER_DIRTY_EDGES: IntGaugeVec =
+        register_int_gauge_vec!(opts!("router_edge_updater_dirty_edges", "Edges waiting to be repriced"), &["dex_name"]).unwrap();
+    pub static ref EDGE_UPDATER_OLDEST_DIRTY_EDGE_AGE_MS: IntGaugeVec =
+        register_int_gauge_vec!(opts!("router_edge_updater_oldest_dirty_edge_age_ms", "Time since the oldest edge waiting to be repriced was invalidated"), &["dex_name"]).unwrap();
 
     pub static ref QUIC_ACCOUNT_WRITES: IntCounter =
         register_int_counter!("quic_account_writes", "Number of account updates via Geyser gRPC").unwrap();
@@ -43,6 +47,14 @@ lazy_static::lazy_static! {
         register_int_counter_vec!(opts!("quic_source_connection_retries", "gRPC source connection retries"), &["source_name"]).unwrap();
     pub static ref QUIC_NO_MESSAGE_FOR_DURATION_MS: IntGauge =
         register_int_gauge!("quic_no_update_for_duration_ms", "Did not get any message from Geyser gPRC for this duration").unwrap();
+    pub static ref GEYSER_SOURCE_SLOT_LAG: IntGaugeVec =
+        register_int_gauge_vec!(opts!("router_geyser_source_slot_lag", "Slots behind the freshest geyser source"), &["source_name"]).unwrap();
+    pub static ref GEYSER_SOURCE_DEMOTED: IntGaugeVec =
+        register_int_gauge_vec!(opts!("router_geyser_source_demoted", "Geyser source ignored because it is lagging"), &["source_name"]).unwrap();
+    pub static ref CHAIN_DATA_FORK_SWITCHES: IntCounter =
+        register_int_counter!("router_chain_data_fork_switches", "Number of times chain data switched to a different fork").unwrap();
+    pub static ref CHAIN_DATA_PENDING_UNCONFIRMED_SLOTS: IntGauge =
+        register_int_gauge!("router_chain_data_pending_unconfirmed_slots", "Slots with account writes held back until confirmed").unwrap();
 
     pub static ref HTTP_REQUEST_TIMING: HistogramVec =
         register_histogram_vec!(
@@ -14,6 +14,8 @@ pub mod test {
         pub input_mint: Pubkey,
         pub output_mint: Pubkey,
         pub price: f64,
+        /// Simulated pool depth (in input native units), None for infinite liquidity
+        pub depth: Option<f64>,
     }
 
     impl DexEdgeIdentifier for MockDexIdentifier {
@@ -42,7 +44,11 @@ pub mod test {
         }
     }
 
-    pub struct MockDexInterface {}
+    #[derive(Default)]
+    pub struct MockDexInterface {
+        /// dex name used by dex filters, "Mock" when not set
+        pub name: Option<String>,
+    }
 
     pub struct MockEdge {}
     impl DexEdge for MockEdge {
@@ -64,7 +70,7 @@ pub mod test {
         }
 
         fn name(&self) -> String {
-            todo!()
+            self.name.clone().unwrap_or("Mock".to_string())
         }
 
         fn subscription_mode(&self) -> DexSubscriptionMode {
@@ -95,7 +101,8 @@ pub mod test {
             in_amount: u64,
         ) -> anyhow::Result<Quote> {
             let id = id.as_any().downcast_ref::<MockDexIdentifier>().unwrap();
-            let out_amount = (id.price * in_amount as f64).round() as u64;
+            let impact = id.depth.map(|d| 1.0 + in_amount as f64 / d).unwrap_or(1.0);
+            let out_amount = (id.price * in_amount as f64 / impact).round() as u64;
 
             Ok(Quote {
                 in_amount,
@@ -129,7 +136,11 @@ pub mod test {
             out_amount: u64,
         ) -> anyhow::Result<Quote> {
             let id = id.as_any().downcast_ref::<MockDexIdentifier>().unwrap();
-            let in_amount = (out_amount as f64 / id.price).round() as u64;
+            let price = id.price - id.depth.map(|d| out_amount as f64 / d).unwrap_or(0.0);
+            if price <= 0.0 {
+                anyhow::bail!("not enough liquidity");
+            }
+            let in_amount = (out_amount as f64 / price).round() as u64;
 
             Ok(Quote {
                 in_amount,
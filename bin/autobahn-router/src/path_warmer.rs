@@ -7,76 +7,113 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 
+use tokio::sync::watch;
 use tokio::task::JoinHandle;
 use tracing::log::trace;
 use tracing::{debug, info, warn};
 
+use crate::config_reloader::path_warming_amounts;
 use crate::debug_tools;
 use crate::hot_mints::HotMintsCache;
 use crate::server::route_provider::RouteProvider;
 use crate::token_cache::TokenCache;
-use router_config_lib::{PathWarmingMode, RoutingConfig};
+use router_config_lib::{Config, PathWarmingMode, RoutingConfig};
 use router_lib::mango::mango_fetcher::MangoMetadata;
 use router_lib::price_feeds::price_cache::PriceCache;
 
+struct PathWarmingSettings {
+    mode: PathWarmingMode,
+    configured_mints: Vec<Pubkey>,
+    interval_secs: u64,
+    max_accounts: Vec<usize>,
+    amounts: Vec<u64>,
+}
+
+impl PathWarmingSettings {
+    fn new(config: &RoutingConfig, mango_metadata: &Option<MangoMetadata>) -> Self {
+        let mut mode = config
+            .path_warming_mode
+            .clone()
+            .unwrap_or(PathWarmingMode::ConfiguredMints);
+        let configured_mints = config
+            .path_warming_for_mints
+            .clone()
+            .unwrap_or(vec![])
+            .iter()
+            .filter_map(|x| match Pubkey::from_str(x) {
+                Ok(mint) => Some(mint),
+                Err(_) => {
+                    warn!("Invalid mint in path warming config: {}", x);
+                    None
+                }
+            })
+            .collect_vec();
+
+        match mode {
+            PathWarmingMode::ConfiguredMints if configured_mints.is_empty() => {
+                warn!("No configured tokens => no path warming");
+                mode = PathWarmingMode::None;
+            }
+            PathWarmingMode::MangoMints if mango_metadata.is_none() => {
+                warn!("Mango tokens unavailable => no path warming");
+                mode = PathWarmingMode::None;
+            }
+            _ => {}
+        };
+
+        PathWarmingSettings {
+            mode,
+            configured_mints,
+            interval_secs: config.path_warming_interval_secs.unwrap_or(10),
+            max_accounts: config
+                .path_warming_max_accounts
+                .clone()
+                .unwrap_or(vec![10_usize, 15, 20, 25, 30, 40]),
+            amounts: path_warming_amounts(config),
+        }
+    }
+}
+
+/// Settings are read again from `config_updates` between two warmup loops.
+/// When the warm state was restored from a checkpoint, warming starts right away.
 pub fn spawn_path_warmer_job<T>(
-    config: &RoutingConfig,
+    mut config_updates: watch::Receiver<Config>,
     hot_mints_cache: Arc<RwLock<HotMintsCache>>,
     mango_metadata: Option<MangoMetadata>,
     route_provider: Arc<T>,
     token_cache: TokenCache,
     price_cache: PriceCache,
-    path_warming_amounts: Vec<u64>,
     exit_flag: Arc<AtomicBool>,
-) -> Option<JoinHandle<()>>
+    warm_start: bool,
+) -> JoinHandle<()>
 where
     T: RouteProvider + Send + Sync + 'static,
 {
-    let mode = config
-        .path_warming_mode
-        .clone()
-        .unwrap_or(PathWarmingMode::ConfiguredMints);
-    let configured_mints = config
-        .path_warming_for_mints
-        .clone()
-        .unwrap_or(vec![])
-        .iter()
-        .map(|x| Pubkey::from_str(x).expect("Invalid mint in path warming config"))
-        .collect_vec();
-
-    match mode {
-        PathWarmingMode::None => return None,
-        PathWarmingMode::ConfiguredMints => {
-            if configured_mints.is_empty() {
-                warn!("No configured tokens => no path warming");
-                return None;
-            }
-        }
-        PathWarmingMode::MangoMints => {
-            if mango_metadata.is_none() {
-                warn!("Mango tokens unavailable => no path warming");
-                return None;
-            }
-        }
-        PathWarmingMode::HotMints => {}
-        PathWarmingMode::All => {}
-    };
-
     let sol_mint = Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap();
-    let config = config.clone();
     let start = Instant::now();
-    let job = tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(
-            config.path_warming_interval_secs.unwrap_or(10),
-        ));
-        let config_max_accounts = config
-            .path_warming_max_accounts
-            .unwrap_or(vec![10_usize, 15, 20, 25, 30, 40]);
+    tokio::spawn(async move {
+        let mut settings =
+            PathWarmingSettings::new(&config_updates.borrow_and_update().routing, &mango_metadata);
+        let mut interval = tokio::time::interval(Duration::from_secs(settings.interval_secs));
         interval.tick().await;
 
         loop {
             interval.tick().await;
-            if start.elapsed() < Duration::from_secs(60) {
+
+            if config_updates.has_changed().unwrap_or(false) {
+                let new_settings = PathWarmingSettings::new(
+                    &config_updates.borrow_and_update().routing,
+                    &mango_metadata,
+                );
+                if new_settings.interval_secs != settings.interval_secs {
+                    interval =
+                        tokio::time::interval(Duration::from_secs(new_settings.interval_secs));
+                    interval.tick().await;
+                }
+                settings = new_settings;
+            }
+
+            if !warm_start && start.elapsed() < Duration::from_secs(60) {
                 // do not start right away as not everything is ready yet
                 continue;
             }
@@ -86,14 +123,14 @@ where
 
             let hot_mints = hot_mints_cache.read().unwrap().get();
             let mints = match generate_mints(
-                &mode,
-                &configured_mints,
+                &settings.mode,
+                &settings.configured_mints,
                 &hot_mints,
                 &all_mints,
                 &mango_metadata,
             ) {
                 Some(value) => value,
-                None => break,
+                None => continue,
             };
 
             debug!("Running a path warmup loop for {} mints", mints.len());
@@ -131,11 +168,11 @@ where
 
                 trace!("Warming up {}", debug_tools::name(&from_mint),);
 
-                for amount_ui in &path_warming_amounts {
+                for amount_ui in &settings.amounts {
                     let amount_native =
                         ((*amount_ui as f64 / price_ui) * multiplier).round() as u64;
 
-                    for max_accounts in &config_max_accounts {
+                    for max_accounts in &settings.max_accounts {
                         match route_provider.prepare_cache_for_input_mint(
                             *from_mint,
                             amount_native,
@@ -166,9 +203,7 @@ where
                 mints.len()
             );
         }
-    });
-
-    Some(job)
+    })
 }
 
 fn generate_mints(
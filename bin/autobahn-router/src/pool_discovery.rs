@@ -0,0 +1,90 @@
+use crate::edge::Edge;
+use crate::routing::Routing;
+use crate::source::mint_accounts_source::request_mint_metadata;
+use crate::token_cache::{Token, TokenCache};
+use crate::util::tokio_spawn;
+use router_config_lib::AccountDataSourceConfig;
+use solana_program::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Add edges discovered by the edge updaters to the routing graph,
+/// after fetching decimals of the new mints and registering them for price updates
+pub fn spawn_pool_discovery_job(
+    source_config: &AccountDataSourceConfig,
+    routing: Arc<Routing>,
+    token_cache: TokenCache,
+    register_mint_sender: async_channel::Sender<Pubkey>,
+    new_edges_receiver: async_channel::Receiver<Vec<Arc<Edge>>>,
+    mut exit: broadcast::Receiver<()>,
+) -> JoinHandle<()> {
+    let rpc_http_url = source_config.rpc_http_url.clone();
+    let number_of_accounts_per_gma = source_config.number_of_accounts_per_gma.unwrap_or(100);
+
+    tokio_spawn("pool_discovery", async move {
+        loop {
+            tokio::select! {
+                _ = exit.recv() => {
+                    info!("shutting down pool_discovery task");
+                    break;
+                }
+                res = new_edges_receiver.recv() => {
+                    let Ok(mut new_edges) = res else {
+                        warn!("new edges channel closed");
+                        break;
+                    };
+                    while let Ok(more) = new_edges_receiver.try_recv() {
+                        new_edges.extend(more);
+                    }
+
+                    register_mints(
+                        &rpc_http_url,
+                        number_of_accounts_per_gma,
+                        &token_cache,
+                        &register_mint_sender,
+                        &new_edges,
+                    )
+                    .await;
+                    routing.add_edges(new_edges);
+                }
+            }
+        }
+    })
+}
+
+async fn register_mints(
+    rpc_http_url: &str,
+    number_of_accounts_per_gma: usize,
+    token_cache: &TokenCache,
+    register_mint_sender: &async_channel::Sender<Pubkey>,
+    new_edges: &[Arc<Edge>],
+) {
+    let unknown_mints = new_edges
+        .iter()
+        .flat_map(|x| [x.input_mint, x.output_mint])
+        .filter(|x| !token_cache.contains(x))
+        .collect::<HashSet<_>>();
+    if unknown_mints.is_empty() {
+        return;
+    }
+
+    let mint_metadata =
+        request_mint_metadata(rpc_http_url, &unknown_mints, number_of_accounts_per_gma).await;
+    for (mint, token) in mint_metadata {
+        token_cache.insert(Token {
+            mint,
+            decimals: token.decimals,
+            program: token.program,
+            transfer_fee: token.transfer_fee,
+        });
+    }
+
+    for mint in unknown_mints {
+        if register_mint_sender.try_send(mint).is_err() {
+            warn!("Failed to register mint '{}' for price update", mint);
+        }
+    }
+}
@@ -2,11 +2,13 @@ use crate::debug_tools;
 use crate::metrics;
 use crate::prelude::*;
 use crate::routing_objectpool::RoutingObjectPools;
+use crate::token_cache::TokenCache;
 use mango_feeds_connector::chain_data::AccountData;
 use ordered_float::NotNan;
-use router_config_lib::Config;
+use router_config_lib::{Config, RoutingConfig};
 use router_lib::dex::SwapMode;
-use router_lib::dex::{AccountProviderView, DexEdge};
+use router_lib::dex::{AccountProviderView, DexEdge, DexEdgeIdentifier, DexInterface};
+use router_lib::price_feeds::onchain::PriceGraphProvider;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::time::{Duration, Instant};
 use std::u64;
@@ -25,6 +27,8 @@ pub enum RoutingError {
     NoPathBetweenMintPair(Pubkey, Pubkey),
     #[error("could not compute out amount")]
     CouldNotComputeOut,
+    #[error("unknown dex {0}")]
+    UnknownDex(String),
 }
 
 fn best_price_paths_depth_search<F>(
@@ -364,8 +368,28 @@ struct PathDiscoveryCacheEntry {
     edges: Vec<Vec<EdgeIndex>>,
 }
 
+/// PathDiscoveryCacheEntry referring to mints and edges by pubkey instead of graph indexes,
+/// so that it stays meaningful for another graph (after a restart)
+#[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+pub struct PersistedPathCacheEntry {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub swap_mode: SwapMode,
+    pub dex_filter: DexFilter,
+    pub timestamp_millis: u64,
+    pub in_amount: f64,
+    pub max_account: usize,
+    pub paths: Vec<Vec<(Pubkey, Pubkey)>>,
+}
+
+// bounds the pruned edges kept for dex filtered requests
+const MAX_DEX_FILTER_PRUNED_EDGES: usize = 16;
+
+// paths discovered with a dex filter only use edges of the allowed dexes, so the filter is part of the key
+type PathDiscoveryCacheKey = (MintNodeIndex, MintNodeIndex, SwapMode, DexFilter);
+
 struct PathDiscoveryCache {
-    cache: HashMap<(MintNodeIndex, MintNodeIndex, SwapMode), Vec<PathDiscoveryCacheEntry>>,
+    cache: HashMap<PathDiscoveryCacheKey, Vec<PathDiscoveryCacheEntry>>,
     last_expire_timestamp_millis: u64,
     max_age_millis: u64,
 }
@@ -397,6 +421,7 @@ impl PathDiscoveryCache {
         from: MintNodeIndex,
         to: MintNodeIndex,
         swap_mode: SwapMode,
+        dex_filter: &DexFilter,
         in_amount: u64,
         max_accounts: usize,
         timestamp_millis: u64,
@@ -409,7 +434,10 @@ impl PathDiscoveryCache {
         );
 
         let max_accounts_bucket = Self::compute_account_bucket(max_accounts);
-        let entry = self.cache.entry((from, to, swap_mode)).or_default();
+        let entry = self
+            .cache
+            .entry((from, to, swap_mode, dex_filter.clone()))
+            .or_default();
 
         // try to reduce memory footprint...
         for path in &mut edges {
@@ -447,12 +475,16 @@ impl PathDiscoveryCache {
         from: MintNodeIndex,
         to: MintNodeIndex,
         swap_mode: SwapMode,
+        dex_filter: &DexFilter,
         in_amount: u64,
         max_accounts: usize,
     ) -> (Option<&Vec<Vec<EdgeIndex>>>, Option<&Vec<Vec<EdgeIndex>>>) {
         let in_amount = in_amount as f64;
         let max_accounts_bucket = Self::compute_account_bucket(max_accounts);
-        let Some(entries) = self.cache.get_mut(&(from, to, swap_mode)) else {
+        let Some(entries) = self
+            .cache
+            .get_mut(&(from, to, swap_mode, dex_filter.clone()))
+        else {
             // cache miss
             metrics::PATH_DISCOVERY_CACHE_MISSES.inc();
             return (None, None);
@@ -478,18 +510,26 @@ impl PathDiscoveryCache {
         (lower, upper)
     }
 
-    fn invalidate(&mut self, from: MintNodeIndex, to: MintNodeIndex, max_accounts: usize) {
+    fn invalidate(
+        &mut self,
+        from: MintNodeIndex,
+        to: MintNodeIndex,
+        dex_filter: &DexFilter,
+        max_accounts: usize,
+    ) {
         let max_accounts_bucket = Self::compute_account_bucket(max_accounts);
-        let Some(entries) = self.cache.get_mut(&(from, to, SwapMode::ExactIn)) else {
-            return;
-        };
-
-        entries.retain(|x| x.max_account != max_accounts_bucket);
+        for swap_mode in [SwapMode::ExactIn, SwapMode::ExactOut] {
+            let key = (from, to, swap_mode, dex_filter.clone());
+            if let Some(entries) = self.cache.get_mut(&key) {
+                entries.retain(|x| x.max_account != max_accounts_bucket);
+            }
+        }
+    }
 
-        let Some(entries) = self.cache.get_mut(&(from, to, SwapMode::ExactOut)) else {
-            return;
-        };
-        entries.retain(|x| x.max_account != max_accounts_bucket);
+    // drop everything touching these mints so that new edges are taken into account
+    fn invalidate_mints(&mut self, mints: &HashSet<MintNodeIndex>) {
+        self.cache
+            .retain(|(from, to, _, _), _| !mints.contains(from) && !mints.contains(to));
     }
 
     fn compute_account_bucket(max_accounts: usize) -> usize {
@@ -497,43 +537,37 @@ impl PathDiscoveryCache {
     }
 }
 
-/// global singleton to manage routing
-#[allow(dead_code)]
-pub struct Routing {
+struct BuiltRouteLeg {
+    leg: RouteLeg,
+    slot: u64,
+    price_impact_bps: u64,
+}
+
+/// Edges and mints known by the router
+/// Only ever grows (new pools are appended) so indexes stay valid from one version to the next
+struct RoutingGraph {
     // indexed by EdgeIndex
     edges: Vec<Arc<Edge>>,
 
     // indexed by MintNodeIndex
     mints: MintVec<Pubkey>,
 
-    // memory pools for best_price_paths_depth_search
-    objectpools: RoutingObjectPools,
+    // memory pools for best_price_paths_depth_search (sized by mint count)
+    objectpools: Arc<RoutingObjectPools>,
 
     // Preparation for pathfinding
     // mint pubkey -> NodeIndex
     mint_to_index: HashMap<Pubkey, MintNodeIndex>,
 
-    path_discovery_cache: RwLock<PathDiscoveryCache>,
-
-    // Keep pruned edges for a while (speed up searching)
-    // first for exact in and second of exact out
-    pruned_out_edges_per_mint_index_exact_in: RwLock<(Instant, MintVec<Vec<EdgeWithNodes>>)>,
-    pruned_out_edges_per_mint_index_exact_out: RwLock<(Instant, MintVec<Vec<EdgeWithNodes>>)>,
-    path_warming_amounts: Vec<u64>,
-
-    // Optim & Heuristics
-    overquote: f64,
-    max_path_length: usize,
-    retain_path_count: usize,
-    max_edge_per_pair: usize,
-    max_edge_per_cold_pair: usize,
+    // lowercase names of the dexes with edges, the only ones a dex filter can name
+    dex_names: HashSet<String>,
 }
 
-impl Routing {
-    pub fn new(
-        configuration: &Config,
-        path_warming_amounts: Vec<u64>,
+impl RoutingGraph {
+    fn new(
         edges: Vec<Arc<Edge>>,
+        retain_path_count: usize,
+        previous: Option<&RoutingGraph>,
     ) -> Self {
         let mints: MintVec<Pubkey> = edges
             .iter()
@@ -546,21 +580,151 @@ impl Routing {
             .enumerate()
             .map(|(i, mint_pubkey)| (*mint_pubkey, i.into()))
             .collect();
+        let dex_names = edges.iter().map(|e| e.dex.name().to_lowercase()).collect();
 
-        info!(
-            "Setup routing algorithm with {} edges and {} distinct mints",
-            edges.len(),
-            mints.len()
-        );
-
-        let mint_count = mints.len();
-        let retain_path_count = configuration.routing.retain_path_count.unwrap_or(10);
+        let objectpools = match previous {
+            Some(previous) if previous.mints.len() == mints.len() => previous.objectpools.clone(),
+            _ => Arc::new(RoutingObjectPools::new(mints.len(), retain_path_count)),
+        };
 
         Self {
             edges,
             mints,
-            objectpools: RoutingObjectPools::new(mint_count, retain_path_count),
+            objectpools,
             mint_to_index,
+            dex_names,
+        }
+    }
+
+    // called per request
+    #[tracing::instrument(skip_all, level = "trace")]
+    fn lookup_edge_index_paths<'a>(
+        &self,
+        paths: impl Iterator<Item = &'a Vec<EdgeIndex>>,
+    ) -> Vec<Vec<Arc<Edge>>> {
+        // cached paths may come from a newer graph than this one
+        paths
+            .filter_map(|path| {
+                path.iter()
+                    .map(|&edge_index| self.edges.get(edge_index.idx()).cloned())
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect_vec()
+    }
+
+    fn edge_info(&self, edge_index: EdgeIndex, _now_ms: u64, in_amount: u64) -> Option<EdgeInfo> {
+        let edge = &self.edges[edge_index.idx()];
+        let price = edge
+            .state
+            .read()
+            .unwrap()
+            .cached_price_for(in_amount)
+            .map(|(price, _ln_price)| price)?;
+
+        Some(EdgeInfo {
+            price,
+            accounts: edge.accounts_needed,
+        })
+    }
+
+    fn edge_info_exact_out(
+        &self,
+        edge_index: EdgeIndex,
+        _now_ms: u64,
+        amount: u64,
+    ) -> Option<EdgeInfo> {
+        let edge = &self.edges[edge_index.idx()];
+        let price = edge
+            .state
+            .read()
+            .unwrap()
+            .cached_price_exact_out_for(amount)
+            .map(|(price, _ln_price)| price)?;
+        Some(EdgeInfo {
+            price,
+            accounts: edge.accounts_needed,
+        })
+    }
+}
+
+/// global singleton to manage routing
+#[allow(dead_code)]
+pub struct Routing {
+    graph: RwLock<Arc<RoutingGraph>>,
+
+    path_discovery_cache: RwLock<PathDiscoveryCache>,
+
+    // Keep pruned edges for a while (speed up searching)
+    // first for exact in and second of exact out
+    // along with the graph they were computed from
+    pruned_out_edges_per_mint_index_exact_in:
+        RwLock<(Instant, MintVec<Vec<EdgeWithNodes>>, Arc<RoutingGraph>)>,
+    pruned_out_edges_per_mint_index_exact_out:
+        RwLock<(Instant, MintVec<Vec<EdgeWithNodes>>, Arc<RoutingGraph>)>,
+    // same for requests restricting dexes, computed on first use and
+    // dropped when the unfiltered ones are refreshed
+    // (at most MAX_DEX_FILTER_PRUNED_EDGES, the oldest is evicted first)
+    pruned_out_edges_per_dex_filter: RwLock<
+        HashMap<
+            (SwapMode, DexFilter),
+            (Instant, Arc<MintVec<Vec<EdgeWithNodes>>>, Arc<RoutingGraph>),
+        >,
+    >,
+
+    // dexes banned by an operator (name -> banned until, unix millis),
+    // applied to the edges added after the ban too
+    banned_dexes: RwLock<HashMap<String, u64>>,
+
+    // sizes the object pools, can't change at runtime
+    retain_path_count: usize,
+    settings: RwLock<Arc<RoutingSettings>>,
+}
+
+/// Optim & Heuristics, can be changed on a running router
+#[derive(Clone, Debug)]
+pub struct RoutingSettings {
+    pub path_warming_amounts: Vec<u64>,
+    pub overquote: f64,
+    pub max_path_length: usize,
+    pub max_edge_per_pair: usize,
+    pub max_edge_per_cold_pair: usize,
+    pub max_split_paths: usize,
+    pub split_chunk_count: usize,
+    pub split_min_improvement_bps: u64,
+}
+
+impl RoutingSettings {
+    pub fn new(configuration: &RoutingConfig, path_warming_amounts: Vec<u64>) -> Self {
+        Self {
+            path_warming_amounts,
+            overquote: configuration.overquote.unwrap_or(0.20),
+            max_path_length: configuration.max_path_length.unwrap_or(4),
+            max_edge_per_pair: configuration.max_edge_per_pair.unwrap_or(8),
+            max_edge_per_cold_pair: configuration.max_edge_per_cold_pair.unwrap_or(3),
+            max_split_paths: configuration.max_split_paths.unwrap_or(1),
+            split_chunk_count: configuration.split_chunk_count.unwrap_or(10),
+            split_min_improvement_bps: configuration.split_min_improvement_bps.unwrap_or(5),
+        }
+    }
+}
+
+impl Routing {
+    pub fn new(
+        configuration: &Config,
+        path_warming_amounts: Vec<u64>,
+        edges: Vec<Arc<Edge>>,
+    ) -> Self {
+        let retain_path_count = configuration.routing.retain_path_count.unwrap_or(10);
+        let graph = Arc::new(RoutingGraph::new(edges, retain_path_count, None));
+
+        info!(
+            "Setup routing algorithm with {} edges and {} distinct mints",
+            graph.edges.len(),
+            graph.mints.len()
+        );
+
+        Self {
+            graph: RwLock::new(graph.clone()),
             path_discovery_cache: RwLock::new(PathDiscoveryCache {
                 cache: Default::default(),
                 last_expire_timestamp_millis: 0,
@@ -569,18 +733,199 @@ impl Routing {
             pruned_out_edges_per_mint_index_exact_in: RwLock::new((
                 Instant::now() - Duration::from_secs(3600 * 24),
                 MintVec::new_from_prototype(0, vec![]),
+                graph.clone(),
             )),
             pruned_out_edges_per_mint_index_exact_out: RwLock::new((
                 Instant::now() - Duration::from_secs(3600 * 24),
                 MintVec::new_from_prototype(0, vec![]),
+                graph,
             )),
-            path_warming_amounts,
-            overquote: configuration.routing.overquote.unwrap_or(0.20),
-            max_path_length: configuration.routing.max_path_length.unwrap_or(4),
+            pruned_out_edges_per_dex_filter: Default::default(),
+            banned_dexes: Default::default(),
             retain_path_count,
-            max_edge_per_pair: configuration.routing.max_edge_per_pair.unwrap_or(8),
-            max_edge_per_cold_pair: configuration.routing.max_edge_per_cold_pair.unwrap_or(3),
+            settings: RwLock::new(Arc::new(RoutingSettings::new(
+                &configuration.routing,
+                path_warming_amounts,
+            ))),
+        }
+    }
+
+    fn graph(&self) -> Arc<RoutingGraph> {
+        self.graph.read().unwrap().clone()
+    }
+
+    /// Every edge known by the router, including the ones added at runtime
+    pub fn edges(&self) -> Vec<Arc<Edge>> {
+        self.graph().edges.clone()
+    }
+
+    /// Bans a dex until the given time (unix millis) or lifts its ban, for the edges added
+    /// from now on; the edges already in the graph have to be updated by the caller
+    pub fn set_dex_ban(&self, dex: &str, until: Option<u64>) {
+        let mut banned_dexes = self.banned_dexes.write().unwrap();
+        match until {
+            Some(until) => banned_dexes.insert(dex.to_string(), until),
+            None => banned_dexes.remove(dex),
+        };
+    }
+
+    /// Edges used by the paths in the discovery cache
+    pub fn cached_path_edges(&self) -> HashSet<(Pubkey, Pubkey)> {
+        let graph = self.graph();
+        let cache = self.path_discovery_cache.read().unwrap();
+        cache
+            .cache
+            .values()
+            .flatten()
+            .flat_map(|entry| entry.edges.iter().flatten())
+            .filter_map(|edge_index| graph.edges.get(edge_index.idx()))
+            .map(|edge| edge.unique_id())
+            .collect()
+    }
+
+    /// Content of the path discovery cache, in a form that can be imported by another router
+    pub fn export_path_cache(&self) -> Vec<PersistedPathCacheEntry> {
+        let graph = self.graph();
+        let cache = self.path_discovery_cache.read().unwrap();
+        cache
+            .cache
+            .iter()
+            .flat_map(|((from, to, swap_mode, dex_filter), entries)| {
+                let graph = &graph;
+                entries.iter().map(move |entry| PersistedPathCacheEntry {
+                    from: graph.mints[*from],
+                    to: graph.mints[*to],
+                    swap_mode: *swap_mode,
+                    dex_filter: dex_filter.clone(),
+                    timestamp_millis: entry.timestamp_millis,
+                    in_amount: entry.in_amount,
+                    max_account: entry.max_account,
+                    paths: entry
+                        .edges
+                        .iter()
+                        .map(|path| {
+                            path.iter()
+                                .map(|edge_index| graph.edges[edge_index.idx()].unique_id())
+                                .collect()
+                        })
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// Add exported entries to the path discovery cache, paths using unknown edges are dropped.
+    /// Entries are expected in the exported order. Returns the number of entries added.
+    pub fn import_path_cache(&self, entries: Vec<PersistedPathCacheEntry>) -> usize {
+        let graph = self.graph();
+        let edge_indexes: HashMap<(Pubkey, Pubkey), EdgeIndex> = graph
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(i, edge)| (edge.unique_id(), i.into()))
+            .collect();
+
+        let mut cache = self.path_discovery_cache.write().unwrap();
+        let mut imported = 0;
+        for entry in entries {
+            let (Some(from), Some(to)) = (
+                graph.mint_to_index.get(&entry.from),
+                graph.mint_to_index.get(&entry.to),
+            ) else {
+                continue;
+            };
+            let edges = entry
+                .paths
+                .iter()
+                .filter_map(|path| {
+                    path.iter()
+                        .map(|id| edge_indexes.get(id).copied())
+                        .collect::<Option<Vec<_>>>()
+                })
+                .collect_vec();
+            if edges.is_empty() {
+                continue;
+            }
+
+            cache
+                .cache
+                .entry((*from, *to, entry.swap_mode, entry.dex_filter))
+                .or_default()
+                .push(PathDiscoveryCacheEntry {
+                    timestamp_millis: entry.timestamp_millis,
+                    in_amount: entry.in_amount,
+                    max_account: entry.max_account,
+                    edges,
+                });
+            imported += 1;
+        }
+
+        imported
+    }
+
+    pub fn settings(&self) -> Arc<RoutingSettings> {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Apply new heuristics, cached paths are dropped as they may not be valid anymore
+    pub fn update_settings(&self, settings: RoutingSettings) {
+        info!(?settings, "Updating routing settings");
+        *self.settings.write().unwrap() = Arc::new(settings);
+        self.path_discovery_cache.write().unwrap().cache.clear();
+    }
+
+    /// Make edges of pools created after startup routable, unknown mints are added to the graph.
+    /// Pruned edges are refreshed on the next request.
+    pub fn add_edges(&self, new_edges: Vec<Arc<Edge>>) {
+        let mut writer = self.graph.write().unwrap();
+
+        let known = writer
+            .edges
+            .iter()
+            .map(|x| x.unique_id())
+            .collect::<HashSet<_>>();
+        let new_edges = new_edges
+            .into_iter()
+            .filter(|x| !known.contains(&x.unique_id()))
+            .unique_by(|x| x.unique_id())
+            .collect_vec();
+        if new_edges.is_empty() {
+            return;
+        }
+
+        let banned_dexes = self.banned_dexes.read().unwrap();
+        for edge in new_edges.iter() {
+            if let Some(until) = banned_dexes.get(&edge.dex.name()) {
+                edge.state.write().unwrap().ban(*until);
+            }
         }
+        drop(banned_dexes);
+
+        let edges = writer
+            .edges
+            .iter()
+            .chain(new_edges.iter())
+            .cloned()
+            .collect_vec();
+        let graph = RoutingGraph::new(edges, self.retain_path_count, Some(&writer));
+
+        info!(
+            new_edges = new_edges.len(),
+            new_mints = graph.mints.len() - writer.mints.len(),
+            "Added edges to routing graph"
+        );
+
+        let touched_mints = new_edges
+            .iter()
+            .flat_map(|x| [x.input_mint, x.output_mint])
+            .map(|x| graph.mint_to_index[&x])
+            .collect::<HashSet<_>>();
+        self.path_discovery_cache
+            .write()
+            .unwrap()
+            .invalidate_mints(&touched_mints);
+
+        *writer = Arc::new(graph);
     }
 
     /// This should never do anything if path_warming is enabled
@@ -600,7 +945,9 @@ impl Routing {
                 .unwrap(),
         };
 
-        let need_refresh = reader.1.len() == 0 || reader.0.elapsed() > Duration::from_secs(60 * 15);
+        let need_refresh = reader.1.len() == 0
+            || reader.0.elapsed() > Duration::from_secs(60 * 15)
+            || !Arc::ptr_eq(&reader.2, &self.graph());
         drop(reader);
         if need_refresh {
             self.prepare_pruned_edges_and_cleanup_cache(hot_mints, swap_mode);
@@ -614,17 +961,20 @@ impl Routing {
         hot_mints: &HashSet<Pubkey>,
         swap_mode: SwapMode,
     ) {
+        let settings = self.settings();
         debug!("prepare_pruned_edges_and_cleanup_cache started");
         self.path_discovery_cache.write().unwrap().expire_old();
 
+        let graph = self.graph();
         let (valid_edge_count, out_edges_per_mint_index) = Self::select_best_pools(
             &hot_mints,
-            self.max_edge_per_pair,
-            self.max_edge_per_cold_pair,
-            &self.path_warming_amounts,
-            &self.edges,
-            &self.mint_to_index,
+            settings.max_edge_per_pair,
+            settings.max_edge_per_cold_pair,
+            &settings.path_warming_amounts,
+            &graph.edges,
+            &graph.mint_to_index,
             swap_mode,
+            &DexFilter::default(),
         );
 
         let mut writer = match swap_mode {
@@ -644,10 +994,62 @@ impl Routing {
             debug!("Failed to use clone_into out_edges_per_mint_index, falling back to slower assignment");
             (*writer).1 = out_edges_per_mint_index;
         }
+        (*writer).2 = graph;
+        drop(writer);
+
+        self.pruned_out_edges_per_dex_filter
+            .write()
+            .unwrap()
+            .retain(|(mode, _), _| *mode != swap_mode);
 
         debug!("prepare_pruned_edges_and_cleanup_cache done");
     }
 
+    fn pruned_out_edges_for_dex_filter(
+        &self,
+        graph: &Arc<RoutingGraph>,
+        hot_mints: &HashSet<Pubkey>,
+        swap_mode: SwapMode,
+        dex_filter: &DexFilter,
+    ) -> Arc<MintVec<Vec<EdgeWithNodes>>> {
+        let key = (swap_mode, dex_filter.clone());
+        if let Some((_, out_edges, pruned_graph)) = self
+            .pruned_out_edges_per_dex_filter
+            .read()
+            .unwrap()
+            .get(&key)
+        {
+            if Arc::ptr_eq(pruned_graph, graph) {
+                return out_edges.clone();
+            }
+        }
+
+        let settings = self.settings();
+        let (_, out_edges) = Self::select_best_pools(
+            hot_mints,
+            settings.max_edge_per_pair,
+            settings.max_edge_per_cold_pair,
+            &settings.path_warming_amounts,
+            &graph.edges,
+            &graph.mint_to_index,
+            swap_mode,
+            dex_filter,
+        );
+        let out_edges = Arc::new(out_edges);
+        let mut writer = self.pruned_out_edges_per_dex_filter.write().unwrap();
+        if writer.len() >= MAX_DEX_FILTER_PRUNED_EDGES && !writer.contains_key(&key) {
+            let oldest = writer
+                .iter()
+                .min_by_key(|(_, (computed_at, _, _))| *computed_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                writer.remove(&oldest);
+            }
+        }
+        writer.insert(key, (Instant::now(), out_edges.clone(), graph.clone()));
+        out_edges
+    }
+
     fn compute_price_impact(edge: &Arc<Edge>) -> Option<f64> {
         let state = edge.state.read().unwrap();
         if !state.is_valid() || state.cached_prices.len() < 2 {
@@ -685,6 +1087,7 @@ impl Routing {
         all_edges: &Vec<Arc<Edge>>,
         mint_to_index: &HashMap<Pubkey, MintNodeIndex>,
         swap_mode: SwapMode,
+        dex_filter: &DexFilter,
     ) -> (i32, MintVec<Vec<EdgeWithNodes>>) {
         let mut result = HashSet::new();
 
@@ -696,10 +1099,13 @@ impl Routing {
                 if swap_mode == SwapMode::ExactOut && !edge.supports_exact_out() {
                     continue;
                 }
+                if !dex_filter.accepts(&edge.dex.name()) {
+                    continue;
+                }
 
                 let edge_index: EdgeIndex = edge_index.into();
                 let state = edge.state.read().unwrap();
-                if !state.is_valid() || state.cached_prices.len() < i {
+                if !state.is_valid() || state.cached_prices.len() <= i {
                     continue;
                 }
                 let price = state.cached_prices[i].1;
@@ -875,55 +1281,8 @@ impl Routing {
         }
     }
 
-    // called per request
-    #[tracing::instrument(skip_all, level = "trace")]
-    fn lookup_edge_index_paths<'a>(
-        &self,
-        paths: impl Iterator<Item = &'a Vec<EdgeIndex>>,
-    ) -> Vec<Vec<Arc<Edge>>> {
-        paths
-            .map(|path| {
-                path.iter()
-                    .map(|&edge_index| self.edges[edge_index.idx()].clone())
-                    .collect_vec()
-            })
-            .collect_vec()
-    }
-
-    fn edge_info(&self, edge_index: EdgeIndex, _now_ms: u64, in_amount: u64) -> Option<EdgeInfo> {
-        let edge = &self.edges[edge_index.idx()];
-        let price = edge
-            .state
-            .read()
-            .unwrap()
-            .cached_price_for(in_amount)
-            .map(|(price, _ln_price)| price)?;
-
-        Some(EdgeInfo {
-            price,
-            accounts: edge.accounts_needed,
-        })
-    }
-
-    fn edge_info_exact_out(
-        &self,
-        edge_index: EdgeIndex,
-        _now_ms: u64,
-        amount: u64,
-    ) -> Option<EdgeInfo> {
-        let edge = &self.edges[edge_index.idx()];
-        let price = edge
-            .state
-            .read()
-            .unwrap()
-            .cached_price_exact_out_for(amount)
-            .map(|(price, _ln_price)| price)?;
-        Some(EdgeInfo {
-            price,
-            accounts: edge.accounts_needed,
-        })
-    }
-
+    /// Path warming, paths are only cached for requests without a dex filter,
+    /// filtered requests fill their own cache entries on their first quote
     pub fn prepare_cache_for_input_mint<F>(
         &self,
         input_mint: &Pubkey,
@@ -934,58 +1293,77 @@ impl Routing {
     where
         F: Fn(&Pubkey, &Pubkey) -> bool,
     {
+        let settings = self.settings();
         // signer + autobahn-executor program + token program + source token account (others are part of the edges)
         // + ATA program + system program + mint
         let min_accounts_needed = 7;
 
-        let Some(&input_index) = self.mint_to_index.get(input_mint) else {
-            bail!("unsupported input mint {input_mint}"); // TODO
-        };
-
         let timestamp = millis_since_epoch();
 
-        let new_paths_by_out_node = self.generate_best_paths(
-            in_amount,
-            timestamp,
-            max_accounts,
-            min_accounts_needed,
-            input_index,
-            &self
+        let (graph, new_paths_by_out_node) = {
+            let pruned = self
                 .pruned_out_edges_per_mint_index_exact_in
                 .read()
-                .unwrap()
-                .1,
-            &HashSet::new(),
-            false,
-            self.max_path_length,
-        )?;
+                .unwrap();
+            let graph = pruned.2.clone();
+            let Some(&input_index) = graph.mint_to_index.get(input_mint) else {
+                bail!("unsupported input mint {input_mint}"); // TODO
+            };
 
-        let new_paths_by_out_node_exact_out = self.generate_best_paths_exact_out(
-            in_amount,
-            timestamp,
-            max_accounts,
-            min_accounts_needed,
-            input_index,
-            &self
+            let new_paths_by_out_node = self.generate_best_paths(
+                &graph,
+                in_amount,
+                timestamp,
+                max_accounts,
+                min_accounts_needed,
+                input_index,
+                &pruned.1,
+                &HashSet::new(),
+                false,
+                settings.max_path_length,
+            )?;
+            (graph, new_paths_by_out_node)
+        };
+
+        let (graph_exact_out, new_paths_by_out_node_exact_out) = {
+            let pruned = self
                 .pruned_out_edges_per_mint_index_exact_out
                 .read()
-                .unwrap()
-                .1,
-            &HashSet::new(),
-            false,
-            self.max_path_length,
-        )?;
+                .unwrap();
+            let graph = pruned.2.clone();
+            let Some(&input_index) = graph.mint_to_index.get(input_mint) else {
+                bail!("unsupported input mint {input_mint}"); // TODO
+            };
+
+            let new_paths_by_out_node = self.generate_best_paths_exact_out(
+                &graph,
+                in_amount,
+                timestamp,
+                max_accounts,
+                min_accounts_needed,
+                input_index,
+                &pruned.1,
+                &HashSet::new(),
+                false,
+                settings.max_path_length,
+            )?;
+            (graph, new_paths_by_out_node)
+        };
+
+        // mint indexes are the same in every graph version
+        let input_index = graph.mint_to_index[input_mint];
 
         let mut writer = self.path_discovery_cache.write().unwrap();
         for (out_index, new_paths) in new_paths_by_out_node.into_iter().enumerate() {
             let out_index: MintNodeIndex = out_index.into();
-            if !filter(&input_mint, &self.mints[out_index]) {
+            if !filter(&input_mint, &graph.mints[out_index]) {
                 continue;
             }
             writer.insert(
                 input_index,
                 out_index,
                 SwapMode::ExactIn,
+                &DexFilter::default(),
                 in_amount,
                 max_accounts,
                 timestamp,
@@ -995,13 +1373,14 @@ impl Routing {
 
         for (out_index, new_paths) in new_paths_by_out_node_exact_out.into_iter().enumerate() {
             let out_index: MintNodeIndex = out_index.into();
-            if !filter(&input_mint, &self.mints[out_index]) {
+            if !filter(&input_mint, &graph_exact_out.mints[out_index]) {
                 continue;
             }
             writer.insert(
                 input_index,
                 out_index,
                 SwapMode::ExactOut,
+                &DexFilter::default(),
                 in_amount,
                 max_accounts,
                 timestamp,
@@ -1219,16 +1598,19 @@ impl Routing {
         hot_mints: &HashSet<Pubkey>,
         max_path_length: Option<usize>,
         swap_mode: SwapMode,
+        dex_filter: &DexFilter,
+        only_direct_routes: bool,
     ) -> anyhow::Result<Route> {
+        let settings = self.settings();
         self.prepare_pruned_edges_if_not_initialized(hot_mints, swap_mode);
 
         // First try with one less hop that maximal authorized as it's way quicker (20-30%)
         // If we can't find anything, will retry with +1
-        let max_path_length = max_path_length.unwrap_or((self.max_path_length - 1).max(1));
+        let max_path_length = max_path_length.unwrap_or((settings.max_path_length - 1).max(1));
 
         // overestimate requested `in_amount` so that quote is not too tight for exact in,
         // exact out we do not overquote here but we will overquote when route is built
-        let amount = (original_amount as f64 * (1.0 + self.overquote)).round() as u64;
+        let amount = (original_amount as f64 * (1.0 + settings.overquote)).round() as u64;
 
         // signer + autobahn-executor program + token program + source token account (others are part of the edges)
         // + ATA program + system program + mint
@@ -1239,10 +1621,34 @@ impl Routing {
         // 2. Path evaluation/optimization: do actual path quoting, try to find a multi-path route
         // 3. Output generation
 
-        let Some(&input_index) = self.mint_to_index.get(input_mint) else {
+        let pruned = match swap_mode {
+            SwapMode::ExactIn => self
+                .pruned_out_edges_per_mint_index_exact_in
+                .read()
+                .unwrap(),
+            SwapMode::ExactOut => self
+                .pruned_out_edges_per_mint_index_exact_out
+                .read()
+                .unwrap(),
+        };
+        let graph = pruned.2.clone();
+        if let Some(dex) = dex_filter.unknown_dex(&graph.dex_names) {
+            bail!(RoutingError::UnknownDex(dex.clone()));
+        }
+        // Excluded dexes must not take the per pair slots, filtered requests are pruned separately
+        let filtered_out_edges;
+        let out_edges_per_node = if dex_filter.is_all() {
+            &pruned.1
+        } else {
+            filtered_out_edges =
+                self.pruned_out_edges_for_dex_filter(&graph, hot_mints, swap_mode, dex_filter);
+            filtered_out_edges.as_ref()
+        };
+
+        let Some(&input_index) = graph.mint_to_index.get(input_mint) else {
             bail!(RoutingError::UnsupportedInputMint(input_mint.clone()));
         };
-        let Some(&output_index) = self.mint_to_index.get(output_mint) else {
+        let Some(&output_index) = graph.mint_to_index.get(output_mint) else {
             bail!(RoutingError::UnsupportedOutputMint(output_mint.clone()));
         };
 
@@ -1255,16 +1661,26 @@ impl Routing {
 
         // Path discovery: find candidate paths for the pair.
         // Prefer cached paths where possible.
-        let cached_paths_opt = {
+        // Single pool swaps only need the direct paths, added below
+        let cached_paths_opt = if only_direct_routes {
+            None
+        } else {
             let mut cache = self.path_discovery_cache.write().unwrap();
-            let cached = cache.get(input_index, output_index, swap_mode, amount, max_accounts);
+            let cached = cache.get(
+                input_index,
+                output_index,
+                swap_mode,
+                dex_filter,
+                amount,
+                max_accounts,
+            );
 
             let p1 = cached
                 .0
-                .map(|paths| self.lookup_edge_index_paths(paths.iter()));
+                .map(|paths| graph.lookup_edge_index_paths(paths.iter()));
             let p2 = cached
                 .1
-                .map(|paths| self.lookup_edge_index_paths(paths.iter()));
+                .map(|paths| graph.lookup_edge_index_paths(paths.iter()));
 
             if (p1.is_none() && p2.is_none()) || ignore_cache {
                 None
@@ -1279,34 +1695,24 @@ impl Routing {
         };
 
         let timestamp = millis_since_epoch();
-        let pruned = match swap_mode {
-            SwapMode::ExactIn => self
-                .pruned_out_edges_per_mint_index_exact_in
-                .read()
-                .unwrap(),
-            SwapMode::ExactOut => self
-                .pruned_out_edges_per_mint_index_exact_out
-                .read()
-                .unwrap(),
-        };
-        let out_edges_per_node = &pruned.1;
 
-        let mut paths;
+        let mut paths = vec![];
         let mut used_cached_paths = false;
         if let Some(cached_paths) = cached_paths_opt {
             paths = cached_paths;
             used_cached_paths = true;
-        } else {
+        } else if !only_direct_routes {
             let avoid_cold_mints = !ignore_cache;
             let hot_mints = hot_mints
                 .iter()
-                .filter_map(|x| self.mint_to_index.get(x))
+                .filter_map(|x| graph.mint_to_index.get(x))
                 .copied()
                 .collect();
 
             let (out_paths, new_paths_by_out_node) = match swap_mode {
                 SwapMode::ExactIn => {
                     let new_paths_by_out_node = self.generate_best_paths(
+                        &graph,
                         amount,
                         timestamp,
                         max_accounts,
@@ -1318,12 +1724,13 @@ impl Routing {
                         max_path_length,
                     )?;
                     (
-                        self.lookup_edge_index_paths(new_paths_by_out_node[output_index].iter()),
+                        graph.lookup_edge_index_paths(new_paths_by_out_node[output_index].iter()),
                         new_paths_by_out_node,
                     )
                 }
                 SwapMode::ExactOut => {
                     let new_paths_by_out_node = self.generate_best_paths_exact_out(
+                        &graph,
                         amount,
                         timestamp,
                         max_accounts,
@@ -1335,7 +1742,7 @@ impl Routing {
                         max_path_length,
                     )?;
                     (
-                        self.lookup_edge_index_paths(new_paths_by_out_node[input_index].iter()),
+                        graph.lookup_edge_index_paths(new_paths_by_out_node[input_index].iter()),
                         new_paths_by_out_node,
                     )
                 }
@@ -1348,6 +1755,7 @@ impl Routing {
                     input_index,
                     out_index,
                     swap_mode,
+                    dex_filter,
                     amount,
                     max_accounts,
                     millis_since_epoch(),
@@ -1358,15 +1766,18 @@ impl Routing {
 
         // Path discovery: add all direct paths
         // note: currently this could mean some path exist twice
-        self.add_direct_paths(input_index, output_index, out_edges_per_node, &mut paths);
+        Self::add_direct_paths(
+            &graph,
+            input_index,
+            output_index,
+            out_edges_per_node,
+            &mut paths,
+        );
 
         // Do not keep that locked - deadlock with recursion and also may impact performance
         drop(pruned);
 
         // Path evaluation
-        // TODO: this could evaluate pairs of paths to see if a split makes sense,
-        // needs to take care of shared steps though.
-
         let mut snapshot = HashMap::new();
 
         let path_output_fn = match swap_mode {
@@ -1402,97 +1813,52 @@ impl Routing {
             }
         }
 
+        // Split evaluation: spreading the amount over independent paths can beat the best single path
+        // (not for single pool swaps)
+        let split = if only_direct_routes {
+            None
+        } else {
+            self.find_best_split(
+                chain_data,
+                &mut snapshot,
+                &path_and_output,
+                amount,
+                max_accounts,
+                min_accounts_needed,
+                swap_mode,
+            )
+        };
+        if let Some(split) = split {
+            if let Some(route) = self.build_split_route(
+                chain_data,
+                &mut snapshot,
+                input_mint,
+                output_mint,
+                &path_and_output,
+                &split,
+                original_amount,
+                amount,
+                swap_mode,
+            )? {
+                return Ok(route);
+            }
+        }
+
         // Build the output
 
         for (out_path, routing_result, _) in path_and_output {
-            let (route_steps, context_slot) = match swap_mode {
-                // Restore requested `in_amount` for route building here
-                SwapMode::ExactIn => Self::build_route_steps(
-                    chain_data,
-                    &mut snapshot,
-                    Self::prepare,
-                    &out_path,
-                    original_amount,
-                )?,
-                SwapMode::ExactOut => Self::build_route_steps_exact_out(
-                    chain_data,
-                    &mut snapshot,
-                    Self::prepare,
-                    &out_path,
-                    original_amount,
-                )?,
-            };
-
-            let actual_in_amount = route_steps.first().unwrap().in_amount;
-            let actual_out_amount = route_steps.last().unwrap().out_amount;
-
-            let overquote_in_amount = match swap_mode {
-                SwapMode::ExactIn => amount,
-                SwapMode::ExactOut => routing_result,
-            };
-
-            let out_amount_for_small_amount = Self::compute_out_amount_from_path(
+            let Some(built) = self.build_route_leg(
                 chain_data,
                 &mut snapshot,
                 &out_path,
-                1_000,
-                false,
+                original_amount,
+                amount,
+                routing_result,
+                swap_mode,
             )?
-            .unwrap_or_default()
-            .0;
-
-            let out_amount_for_request = actual_out_amount;
-            let expected_ratio = 1_000.0 / out_amount_for_small_amount as f64;
-            let actual_ratio = actual_in_amount as f64 / actual_out_amount as f64;
-
-            let price_impact = expected_ratio / actual_ratio * 10_000.0 - 10_000.0;
-            let price_impact_bps = price_impact.round() as u64;
-
-            trace!(
-                price_impact_bps,
-                out_amount_for_small_amount,
-                out_amount_for_request,
-                expected_ratio,
-                actual_ratio,
-                "price impact"
-            );
-
-            let adjusted_out_amount = match swap_mode {
-                SwapMode::ExactIn => ((actual_in_amount as f64 / overquote_in_amount as f64)
-                    * routing_result as f64)
-                    .floor() as u64,
-                SwapMode::ExactOut => original_amount,
-            };
-
-            let adjusted_in_amount = match swap_mode {
-                SwapMode::ExactIn => actual_in_amount,
-                SwapMode::ExactOut => ((actual_out_amount as f64 / amount as f64)
-                    * routing_result as f64)
-                    .ceil() as u64,
-            };
-
-            let out_amount_for_overquoted_amount = match swap_mode {
-                SwapMode::ExactIn => routing_result,
-                SwapMode::ExactOut => amount,
-            };
-
-            if (swap_mode == SwapMode::ExactOut
-                && (actual_in_amount == u64::MAX || actual_out_amount == 0))
-                || (swap_mode == SwapMode::ExactIn && adjusted_out_amount == 0)
-            {
+            else {
                 continue;
-            }
-
-            if self.overquote > 0.0 {
-                debug!(
-                    actual_in_amount,
-                    actual_out_amount,
-                    overquote_in_amount,
-                    out_amount_for_overquoted_amount,
-                    adjusted_out_amount,
-                    "adjusted amount"
-                );
-            }
+            };
 
             // If enabled,for debug purpose, recompute route while capturing accessed chain accounts
             // Can be used when executing the swap to check if accounts have changed
@@ -1503,11 +1869,11 @@ impl Routing {
             return Ok(Route {
                 input_mint: *input_mint,
                 output_mint: *output_mint,
-                in_amount: adjusted_in_amount,
-                out_amount: adjusted_out_amount,
-                steps: route_steps,
-                slot: context_slot,
-                price_impact_bps,
+                in_amount: built.leg.in_amount,
+                out_amount: built.leg.out_amount,
+                legs: vec![built.leg],
+                slot: built.slot,
+                price_impact_bps: built.price_impact_bps,
                 accounts,
             });
         }
@@ -1526,9 +1892,327 @@ impl Routing {
             input_index,
             output_index,
             used_cached_paths,
+            dex_filter,
+            only_direct_routes,
         )
     }
 
+    #[tracing::instrument(skip_all, level = "trace")]
+    fn build_route_leg(
+        &self,
+        chain_data: &AccountProviderView,
+        snapshot: &mut HashMap<(Pubkey, Pubkey), Option<Arc<dyn DexEdge>>>,
+        out_path: &Vec<Arc<Edge>>,
+        original_amount: u64,
+        amount: u64,
+        routing_result: u64,
+        swap_mode: SwapMode,
+    ) -> anyhow::Result<Option<BuiltRouteLeg>> {
+        let settings = self.settings();
+        let (route_steps, context_slot) = match swap_mode {
+            // Restore requested `in_amount` for route building here
+            SwapMode::ExactIn => Self::build_route_steps(
+                chain_data,
+                snapshot,
+                Self::prepare,
+                out_path,
+                original_amount,
+            )?,
+            SwapMode::ExactOut => Self::build_route_steps_exact_out(
+                chain_data,
+                snapshot,
+                Self::prepare,
+                out_path,
+                original_amount,
+            )?,
+        };
+
+        let actual_in_amount = route_steps.first().unwrap().in_amount;
+        let actual_out_amount = route_steps.last().unwrap().out_amount;
+
+        let overquote_in_amount = match swap_mode {
+            SwapMode::ExactIn => amount,
+            SwapMode::ExactOut => routing_result,
+        };
+
+        let out_amount_for_small_amount =
+            Self::compute_out_amount_from_path(chain_data, snapshot, out_path, 1_000, false)?
+                .unwrap_or_default()
+                .0;
+
+        let out_amount_for_request = actual_out_amount;
+        let expected_ratio = 1_000.0 / out_amount_for_small_amount as f64;
+        let actual_ratio = actual_in_amount as f64 / actual_out_amount as f64;
+
+        let price_impact = expected_ratio / actual_ratio * 10_000.0 - 10_000.0;
+        let price_impact_bps = price_impact.round() as u64;
+
+        trace!(
+            price_impact_bps,
+            out_amount_for_small_amount,
+            out_amount_for_request,
+            expected_ratio,
+            actual_ratio,
+            "price impact"
+        );
+
+        let adjusted_out_amount = match swap_mode {
+            SwapMode::ExactIn => ((actual_in_amount as f64 / overquote_in_amount as f64)
+                * routing_result as f64)
+                .floor() as u64,
+            SwapMode::ExactOut => original_amount,
+        };
+
+        let adjusted_in_amount = match swap_mode {
+            SwapMode::ExactIn => actual_in_amount,
+            SwapMode::ExactOut => {
+                ((actual_out_amount as f64 / amount as f64) * routing_result as f64).ceil() as u64
+            }
+        };
+
+        let out_amount_for_overquoted_amount = match swap_mode {
+            SwapMode::ExactIn => routing_result,
+            SwapMode::ExactOut => amount,
+        };
+
+        if (swap_mode == SwapMode::ExactOut
+            && (actual_in_amount == u64::MAX || actual_out_amount == 0))
+            || (swap_mode == SwapMode::ExactIn && adjusted_out_amount == 0)
+        {
+            return Ok(None);
+        }
+
+        if settings.overquote > 0.0 {
+            debug!(
+                actual_in_amount,
+                actual_out_amount,
+                overquote_in_amount,
+                out_amount_for_overquoted_amount,
+                adjusted_out_amount,
+                "adjusted amount"
+            );
+        }
+
+        Ok(Some(BuiltRouteLeg {
+            leg: RouteLeg {
+                in_amount: adjusted_in_amount,
+                out_amount: adjusted_out_amount,
+                steps: route_steps,
+            },
+            slot: context_slot,
+            price_impact_bps,
+        }))
+    }
+
+    /// Greedily hand out chunks of `amount` to the candidate path with the best marginal result.
+    /// Returns (index in `path_and_output`, allocated amount, routing result) for every used path,
+    /// or None if splitting is not worth it.
+    fn find_best_split(
+        &self,
+        chain_data: &AccountProviderView,
+        snapshot: &mut HashMap<(Pubkey, Pubkey), Option<Arc<dyn DexEdge>>>,
+        path_and_output: &[(Vec<Arc<Edge>>, u64, u64)],
+        amount: u64,
+        max_accounts: usize,
+        min_accounts_needed: usize,
+        swap_mode: SwapMode,
+    ) -> Option<Vec<(usize, u64, u64)>> {
+        let settings = self.settings();
+        if settings.max_split_paths < 2 || path_and_output.len() < 2 {
+            return None;
+        }
+
+        // Legs sharing a pool would not be quoted independently, only combine disjoint paths
+        let mut candidates = vec![];
+        let mut used_pools = HashSet::new();
+        let mut accounts = min_accounts_needed;
+        for (i, (path, _, _)) in path_and_output.iter().enumerate() {
+            if candidates.len() >= settings.max_split_paths {
+                break;
+            }
+            if path.iter().any(|edge| used_pools.contains(&edge.key())) {
+                continue;
+            }
+            let path_accounts = path.iter().map(|edge| edge.accounts_needed).sum::<usize>();
+            if accounts + path_accounts > max_accounts {
+                continue;
+            }
+            accounts += path_accounts;
+            used_pools.extend(path.iter().map(|edge| edge.key()));
+            candidates.push(i);
+        }
+
+        if candidates.len() < 2 {
+            return None;
+        }
+
+        let path_output_fn = match swap_mode {
+            SwapMode::ExactIn => Self::compute_out_amount_from_path,
+            SwapMode::ExactOut => Self::compute_in_amount_from_path,
+        };
+
+        let chunk_count = settings.split_chunk_count.max(2) as u64;
+        let chunk_size = amount / chunk_count;
+        if chunk_size == 0 {
+            return None;
+        }
+
+        let mut allocated = vec![0u64; candidates.len()];
+        let mut results = vec![0u64; candidates.len()];
+
+        for chunk_index in 0..chunk_count {
+            let chunk = if chunk_index == chunk_count - 1 {
+                amount - chunk_size * (chunk_count - 1)
+            } else {
+                chunk_size
+            };
+
+            // (candidate index, result, marginal result)
+            let mut best: Option<(usize, u64, u64)> = None;
+            for (candidate_index, &path_index) in candidates.iter().enumerate() {
+                let Some((result, _)) = path_output_fn(
+                    chain_data,
+                    snapshot,
+                    &path_and_output[path_index].0,
+                    allocated[candidate_index] + chunk,
+                    false,
+                )
+                .ok()
+                .flatten() else {
+                    continue;
+                };
+                if result == 0 || result == u64::MAX {
+                    continue;
+                }
+
+                let marginal = result.saturating_sub(results[candidate_index]);
+                let is_better = match best {
+                    None => true,
+                    Some((_, _, best_marginal)) => match swap_mode {
+                        SwapMode::ExactIn => marginal > best_marginal,
+                        SwapMode::ExactOut => marginal < best_marginal,
+                    },
+                };
+                if is_better {
+                    best = Some((candidate_index, result, marginal));
+                }
+            }
+
+            let (candidate_index, result, _) = best?;
+            allocated[candidate_index] += chunk;
+            results[candidate_index] = result;
+        }
+
+        let split = candidates
+            .into_iter()
+            .enumerate()
+            .filter(|(candidate_index, _)| allocated[*candidate_index] > 0)
+            .map(|(candidate_index, path_index)| {
+                (
+                    path_index,
+                    allocated[candidate_index],
+                    results[candidate_index],
+                )
+            })
+            .collect_vec();
+
+        if split.len() < 2 {
+            return None;
+        }
+
+        let split_result = split
+            .iter()
+            .map(|(_, _, result)| *result as f64)
+            .sum::<f64>();
+        let single_result = path_and_output[0].1 as f64;
+        let min_improvement = settings.split_min_improvement_bps as f64 / 10_000.0;
+        let is_better = match swap_mode {
+            SwapMode::ExactIn => split_result > single_result * (1.0 + min_improvement),
+            SwapMode::ExactOut => split_result < single_result * (1.0 - min_improvement),
+        };
+
+        debug!(
+            legs = split.len(),
+            split_result, single_result, is_better, "split evaluation"
+        );
+
+        is_better.then_some(split)
+    }
+
+    fn build_split_route(
+        &self,
+        chain_data: &AccountProviderView,
+        snapshot: &mut HashMap<(Pubkey, Pubkey), Option<Arc<dyn DexEdge>>>,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        path_and_output: &[(Vec<Arc<Edge>>, u64, u64)],
+        split: &[(usize, u64, u64)],
+        original_amount: u64,
+        amount: u64,
+        swap_mode: SwapMode,
+    ) -> anyhow::Result<Option<Route>> {
+        let mut legs = Vec::with_capacity(split.len());
+        let mut accounts: Option<HashMap<_, _>> = None;
+        let mut context_slot = 0;
+        let mut weighted_price_impact = 0u128;
+        let mut remaining_amount = original_amount;
+
+        for (i, (path_index, leg_amount, leg_result)) in split.iter().enumerate() {
+            // Restore requested amount for route building, last leg takes the rounding leftovers
+            let leg_original_amount = if i == split.len() - 1 {
+                remaining_amount
+            } else {
+                ((*leg_amount as u128 * original_amount as u128) / amount as u128) as u64
+            };
+            remaining_amount = remaining_amount.saturating_sub(leg_original_amount);
+
+            let out_path = &path_and_output[*path_index].0;
+            let Some(built) = self.build_route_leg(
+                chain_data,
+                snapshot,
+                out_path,
+                leg_original_amount,
+                *leg_amount,
+                *leg_result,
+                swap_mode,
+            )?
+            else {
+                return Ok(None);
+            };
+
+            if let Ok(leg_accounts) =
+                self.capture_accounts(chain_data, out_path, leg_original_amount)
+            {
+                accounts
+                    .get_or_insert_with(HashMap::new)
+                    .extend(leg_accounts);
+            }
+
+            context_slot = context_slot.max(built.slot);
+            weighted_price_impact += built.price_impact_bps as u128 * built.leg.in_amount as u128;
+            legs.push(built.leg);
+        }
+
+        let in_amount = legs.iter().map(|leg| leg.in_amount).sum::<u64>();
+        let out_amount = legs.iter().map(|leg| leg.out_amount).sum::<u64>();
+        let price_impact_bps = if in_amount > 0 {
+            (weighted_price_impact / in_amount as u128) as u64
+        } else {
+            0
+        };
+
+        Ok(Some(Route {
+            input_mint: *input_mint,
+            output_mint: *output_mint,
+            in_amount,
+            out_amount,
+            price_impact_bps,
+            legs,
+            slot: context_slot,
+            accounts,
+        }))
+    }
+
     fn find_route_with_relaxed_constraints_or_fail(
         &self,
         chain_data: &AccountProviderView,
@@ -1543,15 +2227,19 @@ impl Routing {
         input_index: MintNodeIndex,
         output_index: MintNodeIndex,
         used_cached_paths: bool,
+        dex_filter: &DexFilter,
+        only_direct_routes: bool,
     ) -> anyhow::Result<Route> {
+        let settings = self.settings();
         // It is possible for cache path to became invalid after some account write or failed tx (cooldown)
         // If we used cache but can't find any valid path, try again without the cache
-        let can_try_one_more_hop = max_path_length != self.max_path_length;
-        if !ignore_cache && (used_cached_paths || can_try_one_more_hop) {
+        // Direct paths do not depend on the cache nor on the path length
+        let can_try_one_more_hop = max_path_length != settings.max_path_length;
+        if !only_direct_routes && !ignore_cache && (used_cached_paths || can_try_one_more_hop) {
             if used_cached_paths {
                 debug!("Invalid cached path, retrying without cache");
                 let mut cache = self.path_discovery_cache.write().unwrap();
-                cache.invalidate(input_index, output_index, max_accounts);
+                cache.invalidate(input_index, output_index, dex_filter, max_accounts);
             } else {
                 debug!("No path within boundaries, retrying with +1 hop");
             }
@@ -1563,8 +2251,10 @@ impl Routing {
                 max_accounts,
                 used_cached_paths,
                 hot_mints,
-                Some(self.max_path_length),
+                Some(settings.max_path_length),
                 swap_mode,
+                dex_filter,
+                only_direct_routes,
             );
         }
 
@@ -1701,7 +2391,7 @@ impl Routing {
     }
 
     fn add_direct_paths(
-        &self,
+        graph: &RoutingGraph,
         input_index: MintNodeIndex,
         output_index: MintNodeIndex,
         out_edges_per_node: &MintVec<Vec<EdgeWithNodes>>,
@@ -1711,7 +2401,7 @@ impl Routing {
             if target_and_edge.target_node != output_index {
                 continue;
             }
-            let edge = &self.edges[target_and_edge.edge.idx()];
+            let edge = &graph.edges[target_and_edge.edge.idx()];
             let state = edge.state.read().unwrap();
             if !state.is_valid() {
                 continue;
@@ -1730,6 +2420,7 @@ impl Routing {
     #[tracing::instrument(skip_all, level = "trace")]
     fn generate_best_paths(
         &self,
+        graph: &RoutingGraph,
         in_amount: u64,
         now_ms: u64,
         max_accounts: usize,
@@ -1742,7 +2433,8 @@ impl Routing {
     ) -> anyhow::Result<MintVec<Vec<Vec<EdgeIndex>>>> {
         // non-pooled version
         // let mut best_by_node_prealloc = vec![vec![0f64; 3]; 8 * out_edges_per_node.len()];
-        let mut best_by_node_prealloc = self.objectpools.get_best_by_node(out_edges_per_node.len());
+        let mut best_by_node_prealloc =
+            graph.objectpools.get_best_by_node(out_edges_per_node.len());
 
         // non-pooled version
         // let mut best_paths_by_node_prealloc: MintVec<Vec<(NotNan<f64>, Vec<EdgeWithNodes>)>> =
@@ -1750,7 +2442,7 @@ impl Routing {
         //         out_edges_per_node.len(),
         //         vec![(NotNan::new(f64::NEG_INFINITY).unwrap(), vec![]); self.retain_path_count],
         //     );
-        let mut best_paths_by_node_prealloc = self
+        let mut best_paths_by_node_prealloc = graph
             .objectpools
             .get_best_paths_by_node(out_edges_per_node.len(), self.retain_path_count);
 
@@ -1762,7 +2454,7 @@ impl Routing {
             out_edges_per_node,
             &mut best_paths_by_node_prealloc,
             &mut best_by_node_prealloc,
-            |edge_index, in_amount| self.edge_info(edge_index, now_ms, in_amount),
+            |edge_index, in_amount| graph.edge_info(edge_index, now_ms, in_amount),
             hot_mints,
             avoid_cold_mints,
             SwapMode::ExactIn,
@@ -1792,6 +2484,7 @@ impl Routing {
     #[tracing::instrument(skip_all, level = "trace")]
     fn generate_best_paths_exact_out(
         &self,
+        graph: &RoutingGraph,
         out_amount: u64,
         now_ms: u64,
         max_accounts: usize,
@@ -1803,9 +2496,10 @@ impl Routing {
         max_path_length: usize,
     ) -> anyhow::Result<MintVec<Vec<Vec<EdgeIndex>>>> {
         // similar to generate_best_paths justg changing function to calculate edge info and setting is_exact_out to true
-        let mut best_by_node_prealloc = self.objectpools.get_best_by_node(out_edges_per_node.len());
+        let mut best_by_node_prealloc =
+            graph.objectpools.get_best_by_node(out_edges_per_node.len());
 
-        let mut best_paths_by_node_prealloc = self
+        let mut best_paths_by_node_prealloc = graph
             .objectpools
             .get_best_paths_by_node_exact_out(out_edges_per_node.len(), self.retain_path_count);
 
@@ -1817,7 +2511,7 @@ impl Routing {
             out_edges_per_node,
             &mut best_paths_by_node_prealloc,
             &mut best_by_node_prealloc,
-            |edge_index, out_amount: u64| self.edge_info_exact_out(edge_index, now_ms, out_amount),
+            |edge_index, out_amount: u64| graph.edge_info_exact_out(edge_index, now_ms, out_amount),
             hot_mints,
             avoid_cold_mints,
             SwapMode::ExactOut,
@@ -1848,7 +2542,7 @@ impl Routing {
         output_mint: Pubkey,
         amm_key: Pubkey,
     ) -> anyhow::Result<Arc<Edge>> {
-        if let Some(result) = self.edges.iter().find(|x| {
+        if let Some(result) = self.graph().edges.iter().find(|x| {
             x.input_mint == input_mint && x.output_mint == output_mint && x.key() == amm_key
         }) {
             Ok(result.clone())
@@ -1865,7 +2559,7 @@ impl Routing {
 
         let mut seen_out = HashSet::new();
         let mut seen_in = HashSet::new();
-        for edge in &self.edges {
+        for edge in &self.graph().edges {
             if edge.output_mint == *output_mint {
                 Self::print_some_edges(&mut seen_out, edge);
             }
@@ -1899,6 +2593,27 @@ impl Routing {
     }
 }
 
+/// Live routing graph for the on-chain price feed, so that discovered pools
+/// and their mints get prices too
+pub struct RoutingPriceGraph {
+    pub routing: Arc<Routing>,
+    pub token_cache: TokenCache,
+}
+
+impl PriceGraphProvider for RoutingPriceGraph {
+    fn edges(&self) -> Vec<(Arc<dyn DexInterface>, Arc<dyn DexEdgeIdentifier>)> {
+        self.routing
+            .edges()
+            .iter()
+            .map(|e| (e.dex.clone(), e.id.clone()))
+            .collect()
+    }
+
+    fn decimals(&self) -> HashMap<Pubkey, u8> {
+        self.token_cache.decimals()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2579,7 +3294,7 @@ mod tests {
         let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
             Default::default(),
         ))) as AccountProviderView;
-        let dex = Arc::new(MockDexInterface {}) as Arc<dyn DexInterface>;
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
         let edges = vec![
             Arc::new(make_edge(
                 &dex,
@@ -2660,12 +3375,15 @@ mod tests {
                 &Default::default(),
                 None,
                 SwapMode::ExactIn,
+                &DexFilter::default(),
+                false,
             )
             .unwrap();
 
-        assert_eq!(2, path.steps.len());
-        assert_eq!(pool_2, path.steps[0].edge.id.key());
-        assert_eq!(pool_3, path.steps[1].edge.id.key());
+        assert_eq!(1, path.legs.len());
+        assert_eq!(2, path.legs[0].steps.len());
+        assert_eq!(pool_2, path.legs[0].steps[0].edge.id.key());
+        assert_eq!(pool_3, path.legs[0].steps[1].edge.id.key());
         assert_eq!(7580808080, path.out_amount);
         assert_eq_f64!(
             1_000_000_000.0 * 0.1501 * 1.0 / 0.0198,
@@ -2687,7 +3405,7 @@ mod tests {
         let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
             Default::default(),
         ))) as AccountProviderView;
-        let dex = Arc::new(MockDexInterface {}) as Arc<dyn DexInterface>;
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
         let edges = vec![
             Arc::new(make_edge(
                 &dex,
@@ -2768,12 +3486,15 @@ mod tests {
                 &Default::default(),
                 None,
                 SwapMode::ExactOut,
+                &DexFilter::default(),
+                false,
             )
             .unwrap();
 
-        assert_eq!(2, path.steps.len());
-        assert_eq!(pool_2, path.steps[0].edge.id.key());
-        assert_eq!(pool_3, path.steps[1].edge.id.key());
+        assert_eq!(1, path.legs.len());
+        assert_eq!(2, path.legs[0].steps.len());
+        assert_eq!(pool_2, path.legs[0].steps[0].edge.id.key());
+        assert_eq!(pool_3, path.legs[0].steps[1].edge.id.key());
         assert_eq!(131_912_059, path.in_amount);
         assert_eq!(1_000_000_000, path.out_amount);
         assert_eq_f64!(
@@ -2783,7 +3504,641 @@ mod tests {
         );
     }
 
-    fn make_edge(
+    #[test]
+    fn should_split_route_between_shallow_pools() {
+        let usdc = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+        let pool_1 = Pubkey::new_unique();
+        let pool_2 = Pubkey::new_unique();
+
+        let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
+            Default::default(),
+        ))) as AccountProviderView;
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
+        let edges = [pool_1, pool_2]
+            .iter()
+            .map(|pool| {
+                Arc::new(make_edge_with_depth(
+                    &dex,
+                    pool,
+                    &sol,
+                    &usdc,
+                    &chain_data,
+                    9,
+                    150.0,
+                    0.15,
+                    Some(100_000_000_000.0),
+                ))
+            })
+            .collect_vec();
+
+        let mut config = Config::default();
+        config.routing.max_split_paths = Some(2);
+        let routing = Routing::new(&config, vec![100, 1000], edges);
+
+        let route = routing
+            .find_best_route(
+                &chain_data,
+                &sol,
+                &usdc,
+                100_000_000_000,
+                40,
+                true,
+                &Default::default(),
+                None,
+                SwapMode::ExactIn,
+                &DexFilter::default(),
+                false,
+            )
+            .unwrap();
+
+        assert_eq!(2, route.legs.len());
+        assert_ne!(
+            route.legs[0].steps[0].edge.id.key(),
+            route.legs[1].steps[0].edge.id.key()
+        );
+        assert_eq!(100_000_000_000, route.in_amount);
+        assert_eq!(vec![50, 50], route.leg_percents());
+
+        // best single pool would only give 100 * 0.15 / 2.2 (overquoted) * (100 / 120)
+        let single_pool_out_amount = 0.15 * 120_000_000_000.0 / 2.2 * (100.0 / 120.0);
+        assert!(route.out_amount as f64 > single_pool_out_amount * 1.3);
+    }
+
+    #[test]
+    fn should_find_route_through_edges_added_at_runtime() {
+        let usdc = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+        let bonk = Pubkey::new_unique();
+        let pool_1 = Pubkey::new_unique();
+        let pool_2 = Pubkey::new_unique();
+
+        let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
+            Default::default(),
+        ))) as AccountProviderView;
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
+        let edges = vec![Arc::new(make_edge(
+            &dex,
+            &pool_1,
+            &sol,
+            &usdc,
+            &chain_data,
+            9,
+            150.0,
+            0.15,
+        ))];
+
+        let routing = Routing::new(&Config::default(), vec![100, 1000], edges.clone());
+
+        let find_route = |output_mint: &Pubkey| {
+            routing.find_best_route(
+                &chain_data,
+                &sol,
+                output_mint,
+                1_000_000_000,
+                40,
+                true,
+                &Default::default(),
+                None,
+                SwapMode::ExactIn,
+                &DexFilter::default(),
+                false,
+            )
+        };
+
+        assert!(find_route(&usdc).is_ok());
+        assert!(find_route(&bonk).is_err());
+
+        let new_edge = Arc::new(make_edge(
+            &dex,
+            &pool_2,
+            &usdc,
+            &bonk,
+            &chain_data,
+            6,
+            1.0,
+            50_000.0,
+        ));
+        // already known edges are ignored
+        routing.add_edges(vec![new_edge.clone(), edges[0].clone()]);
+
+        let route = find_route(&bonk).unwrap();
+        assert_eq!(1, route.legs.len());
+        assert_eq!(2, route.legs[0].steps.len());
+        assert_eq!(pool_1, route.legs[0].steps[0].edge.id.key());
+        assert_eq!(pool_2, route.legs[0].steps[1].edge.id.key());
+        assert_eq!(2, routing.graph().edges.len());
+    }
+
+    #[test]
+    fn should_ban_edges_added_after_a_dex_ban() {
+        let usdc = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+
+        let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
+            Default::default(),
+        ))) as AccountProviderView;
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
+        let other_dex = Arc::new(MockDexInterface {
+            name: Some("Other".to_string()),
+        }) as Arc<dyn DexInterface>;
+        let new_edge = |dex: &Arc<dyn DexInterface>| {
+            Arc::new(make_edge(
+                dex,
+                &Pubkey::new_unique(),
+                &sol,
+                &usdc,
+                &chain_data,
+                9,
+                150.0,
+                0.15,
+            ))
+        };
+
+        let routing = Routing::new(&Config::default(), vec![100, 1000], vec![new_edge(&dex)]);
+        routing.set_dex_ban("Mock", Some(u64::MAX));
+
+        let (banned, other) = (new_edge(&dex), new_edge(&other_dex));
+        routing.add_edges(vec![banned.clone(), other.clone()]);
+        assert!(banned.state.read().unwrap().is_banned());
+        assert!(!other.state.read().unwrap().is_banned());
+
+        routing.set_dex_ban("Mock", None);
+        let unbanned = new_edge(&dex);
+        routing.add_edges(vec![unbanned.clone()]);
+        assert!(!unbanned.state.read().unwrap().is_banned());
+    }
+
+    #[test]
+    fn should_import_exported_path_cache_into_another_graph() {
+        let usdc = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+        let bonk = Pubkey::new_unique();
+        let pool_1 = Pubkey::new_unique();
+        let pool_2 = Pubkey::new_unique();
+        let pool_3 = Pubkey::new_unique();
+
+        let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
+            Default::default(),
+        ))) as AccountProviderView;
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
+        let sol_usdc = Arc::new(make_edge(
+            &dex,
+            &pool_1,
+            &sol,
+            &usdc,
+            &chain_data,
+            9,
+            150.0,
+            0.15,
+        ));
+        let usdc_bonk = Arc::new(make_edge(
+            &dex,
+            &pool_2,
+            &usdc,
+            &bonk,
+            &chain_data,
+            6,
+            1.0,
+            50_000.0,
+        ));
+        let sol_bonk = Arc::new(make_edge(
+            &dex,
+            &pool_3,
+            &sol,
+            &bonk,
+            &chain_data,
+            9,
+            150.0,
+            7_000_000.0,
+        ));
+
+        let routing = Routing::new(
+            &Config::default(),
+            vec![100, 1000],
+            vec![sol_usdc.clone(), usdc_bonk.clone(), sol_bonk.clone()],
+        );
+        {
+            let graph = routing.graph();
+            routing.path_discovery_cache.write().unwrap().insert(
+                graph.mint_to_index[&sol],
+                graph.mint_to_index[&bonk],
+                SwapMode::ExactIn,
+                &DexFilter::default(),
+                1_000_000_000,
+                40,
+                millis_since_epoch(),
+                vec![vec![0.into(), 1.into()], vec![2.into()]],
+            );
+        }
+        let exported = routing.export_path_cache();
+
+        // edges in another order, and the direct pool is unknown
+        let other = Routing::new(
+            &Config::default(),
+            vec![100, 1000],
+            vec![usdc_bonk.clone(), sol_usdc.clone()],
+        );
+        assert_eq!(1, other.import_path_cache(exported));
+        assert_eq!(
+            HashSet::from([sol_usdc.unique_id(), usdc_bonk.unique_id()]),
+            other.cached_path_edges()
+        );
+
+        let graph = other.graph();
+        let cache = other.path_discovery_cache.read().unwrap();
+        let entries = &cache.cache[&(
+            graph.mint_to_index[&sol],
+            graph.mint_to_index[&bonk],
+            SwapMode::ExactIn,
+            DexFilter::default(),
+        )];
+        assert_eq!(1, entries.len());
+        assert_eq!(
+            vec![vec![EdgeIndex::from(1), EdgeIndex::from(0)]],
+            entries[0].edges
+        );
+    }
+
+    #[test]
+    fn should_only_route_through_allowed_dexes() {
+        let usdc = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+        let bonk = Pubkey::new_unique();
+        let pool_1 = Pubkey::new_unique();
+        let pool_2 = Pubkey::new_unique();
+
+        let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
+            Default::default(),
+        ))) as AccountProviderView;
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
+        let raydium = Arc::new(MockDexInterface {
+            name: Some("Raydium".to_string()),
+        }) as Arc<dyn DexInterface>;
+        let edges = vec![
+            Arc::new(make_edge(
+                &dex,
+                &pool_1,
+                &sol,
+                &usdc,
+                &chain_data,
+                9,
+                150.0,
+                0.15,
+            )),
+            // raydium is known, but has no pool for the pair
+            Arc::new(make_edge(
+                &raydium,
+                &pool_2,
+                &bonk,
+                &usdc,
+                &chain_data,
+                5,
+                0.0001,
+                0.15,
+            )),
+        ];
+
+        let routing = Routing::new(&Config::default(), vec![100, 1000], edges);
+
+        let find_route = |ignore_cache: bool, dex_filter: DexFilter| {
+            routing.find_best_route(
+                &chain_data,
+                &sol,
+                &usdc,
+                1_000_000_000,
+                40,
+                ignore_cache,
+                &Default::default(),
+                None,
+                SwapMode::ExactIn,
+                &dex_filter,
+                false,
+            )
+        };
+
+        // fills the path cache without any filter
+        assert!(find_route(true, DexFilter::default()).is_ok());
+
+        // cached paths must not leak through a filter
+        assert!(find_route(false, DexFilter::new(None, Some("Raydium,mock"))).is_err());
+        assert!(find_route(false, DexFilter::new(Some("Raydium"), None)).is_err());
+        assert!(find_route(false, DexFilter::new(Some("raydium, MOCK"), None)).is_ok());
+
+        // filters can only name the dexes of the graph
+        let err = find_route(false, DexFilter::new(Some("mock,unknown"), None))
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<RoutingError>(),
+            Some(RoutingError::UnknownDex(dex)) if dex == "unknown"
+        ));
+        assert!(find_route(false, DexFilter::new(None, Some("unknown"))).is_err());
+    }
+
+    #[test]
+    fn should_bound_pruned_edges_kept_per_dex_filter() {
+        let usdc = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+
+        let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
+            Default::default(),
+        ))) as AccountProviderView;
+        let edges = ["A", "B", "C"]
+            .into_iter()
+            .map(|name| {
+                let dex = Arc::new(MockDexInterface {
+                    name: Some(name.to_string()),
+                }) as Arc<dyn DexInterface>;
+                Arc::new(make_edge(
+                    &dex,
+                    &Pubkey::new_unique(),
+                    &sol,
+                    &usdc,
+                    &chain_data,
+                    9,
+                    150.0,
+                    0.15,
+                ))
+            })
+            .collect_vec();
+        let routing = Routing::new(&Config::default(), vec![100, 1000], edges);
+        let graph = routing.graph();
+
+        let names = ["a", "b", "c", "a,b", "a,c", "b,c", "a,b,c"];
+        for swap_mode in [SwapMode::ExactIn, SwapMode::ExactOut] {
+            for (dexes, exclude_dexes) in names.iter().cartesian_product(names) {
+                let dex_filter = DexFilter::new(Some(*dexes), Some(exclude_dexes));
+                routing.pruned_out_edges_for_dex_filter(
+                    &graph,
+                    &Default::default(),
+                    swap_mode,
+                    &dex_filter,
+                );
+                assert!(
+                    routing
+                        .pruned_out_edges_per_dex_filter
+                        .read()
+                        .unwrap()
+                        .len()
+                        <= MAX_DEX_FILTER_PRUNED_EDGES
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_prune_edges_of_excluded_dexes_before_keeping_best_per_pair() {
+        let usdc = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+        let pool_1 = Pubkey::new_unique();
+        let pool_2 = Pubkey::new_unique();
+
+        let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
+            Default::default(),
+        ))) as AccountProviderView;
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
+        let other_dex = Arc::new(MockDexInterface {
+            name: Some("Other".to_string()),
+        }) as Arc<dyn DexInterface>;
+        let edges = vec![
+            Arc::new(make_edge(
+                &dex,
+                &pool_1,
+                &sol,
+                &usdc,
+                &chain_data,
+                9,
+                150.0,
+                0.15,
+            )),
+            Arc::new(make_edge(
+                &other_dex,
+                &pool_2,
+                &sol,
+                &usdc,
+                &chain_data,
+                9,
+                150.0,
+                0.14,
+            )),
+        ];
+
+        let mut config = Config::default();
+        config.routing.max_edge_per_pair = Some(1);
+        let routing = Routing::new(&config, vec![100, 1000], edges);
+
+        let find_route = |dex_filter: DexFilter| {
+            routing.find_best_route(
+                &chain_data,
+                &sol,
+                &usdc,
+                1_000_000_000,
+                40,
+                true,
+                &Default::default(),
+                None,
+                SwapMode::ExactIn,
+                &dex_filter,
+                false,
+            )
+        };
+
+        let route = find_route(DexFilter::default()).unwrap();
+        assert_eq!(pool_1, route.legs[0].steps[0].edge.id.key());
+
+        // the only pool kept for the pair without filter is excluded
+        let route = find_route(DexFilter::new(Some("Other"), None)).unwrap();
+        assert_eq!(pool_2, route.legs[0].steps[0].edge.id.key());
+        let route = find_route(DexFilter::new(None, Some("Mock"))).unwrap();
+        assert_eq!(pool_2, route.legs[0].steps[0].edge.id.key());
+    }
+
+    #[test]
+    fn should_only_use_direct_paths_when_requested() {
+        let usdc = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+        let bonk = Pubkey::new_unique();
+        let mngo = Pubkey::new_unique();
+        let pool_1 = Pubkey::new_unique();
+        let pool_2 = Pubkey::new_unique();
+        let pool_3 = Pubkey::new_unique();
+        let pool_4 = Pubkey::new_unique();
+
+        let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
+            Default::default(),
+        ))) as AccountProviderView;
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
+        let edges = vec![
+            Arc::new(make_edge(
+                &dex,
+                &pool_1,
+                &sol,
+                &usdc,
+                &chain_data,
+                9,
+                150.0,
+                0.15,
+            )),
+            Arc::new(make_edge(
+                &dex,
+                &pool_2,
+                &usdc,
+                &bonk,
+                &chain_data,
+                6,
+                1.0,
+                50_000.0,
+            )),
+            // worse than going through usdc
+            Arc::new(make_edge(
+                &dex,
+                &pool_3,
+                &sol,
+                &bonk,
+                &chain_data,
+                9,
+                150.0,
+                5_000.0,
+            )),
+            Arc::new(make_edge(
+                &dex,
+                &pool_4,
+                &usdc,
+                &mngo,
+                &chain_data,
+                6,
+                1.0,
+                40.0,
+            )),
+        ];
+
+        let routing = Routing::new(&Config::default(), vec![100, 1000], edges);
+
+        let find_route = |output_mint: &Pubkey, only_direct_routes: bool| {
+            routing.find_best_route(
+                &chain_data,
+                &sol,
+                output_mint,
+                1_000_000_000,
+                40,
+                true,
+                &Default::default(),
+                None,
+                SwapMode::ExactIn,
+                &DexFilter::default(),
+                only_direct_routes,
+            )
+        };
+
+        let route = find_route(&bonk, false).unwrap();
+        assert_eq!(2, route.legs[0].steps.len());
+
+        let route = find_route(&bonk, true).unwrap();
+        assert_eq!(1, route.legs.len());
+        assert_eq!(1, route.legs[0].steps.len());
+        assert_eq!(pool_3, route.legs[0].steps[0].edge.id.key());
+
+        // only reachable through usdc
+        assert!(find_route(&mngo, false).is_ok());
+        assert!(find_route(&mngo, true).is_err());
+    }
+
+    #[test]
+    fn should_parse_dex_filter() {
+        assert!(DexFilter::new(None, None).is_all());
+        assert!(DexFilter::new(Some(""), Some(" , ")).is_all());
+
+        let filter = DexFilter::new(Some("Orca,Raydium CP"), Some("orca"));
+        assert!(!filter.accepts("Orca"));
+        assert!(filter.accepts("raydium cp"));
+        assert!(!filter.accepts("Raydium"));
+        assert_eq!(
+            filter,
+            DexFilter::new(Some("raydium cp, orca"), Some("ORCA"))
+        );
+    }
+
+    #[test]
+    fn should_skip_edges_without_a_price_for_every_warming_amount() {
+        let usdc = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+        let pool_1 = Pubkey::new_unique();
+        let pool_2 = Pubkey::new_unique();
+
+        let chain_data = Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
+            Default::default(),
+        ))) as AccountProviderView;
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
+        let edge_1 = Arc::new(make_edge(
+            &dex,
+            &pool_1,
+            &sol,
+            &usdc,
+            &chain_data,
+            9,
+            150.0,
+            0.15,
+        ));
+        let edge_2 = Arc::new(make_edge(
+            &dex,
+            &pool_2,
+            &sol,
+            &usdc,
+            &chain_data,
+            9,
+            150.0,
+            0.14,
+        ));
+        // only priced for the first amount, e.g. warming amounts were changed on a reload
+        edge_2.update_internal(&chain_data, 9, 150.0, &vec![100]);
+
+        let mint_to_index = HashMap::from([
+            (sol, MintNodeIndex::from(0)),
+            (usdc, MintNodeIndex::from(1)),
+        ]);
+        let (_, out_edges_per_mint_index) = Routing::select_best_pools(
+            &HashSet::new(),
+            1,
+            1,
+            &vec![100, 1000],
+            &vec![edge_1, edge_2],
+            &mint_to_index,
+            SwapMode::ExactIn,
+            &DexFilter::default(),
+        );
+
+        let selected = out_edges_per_mint_index[MintNodeIndex::from(0)]
+            .iter()
+            .map(|x| x.edge)
+            .collect_vec();
+        assert_eq!(vec![EdgeIndex::from(0)], selected);
+    }
+
+    fn make_edge(
+        dex: &Arc<dyn DexInterface>,
+        key: &Pubkey,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        chain_data: &AccountProviderView,
+        decimals: u8,
+        input_price_usd: f64,
+        pool_price: f64,
+    ) -> Edge {
+        make_edge_with_depth(
+            dex,
+            key,
+            input_mint,
+            output_mint,
+            chain_data,
+            decimals,
+            input_price_usd,
+            pool_price,
+            None,
+        )
+    }
+
+    fn make_edge_with_depth(
         dex: &Arc<dyn DexInterface>,
         key: &Pubkey,
         input_mint: &Pubkey,
@@ -2792,6 +4147,7 @@ mod tests {
         decimals: u8,
         input_price_usd: f64,
         pool_price: f64,
+        depth: Option<f64>,
     ) -> Edge {
         let edge = Edge {
             input_mint: input_mint.clone(),
@@ -2802,6 +4158,7 @@ mod tests {
                 input_mint: input_mint.clone(),
                 output_mint: output_mint.clone(),
                 price: pool_price,
+                depth,
             }),
             accounts_needed: 10,
             state: Default::default(),
@@ -1,7 +1,7 @@
 use crate::edge::Edge;
 use mango_feeds_connector::chain_data::AccountData;
 use solana_program::pubkey::Pubkey;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::ops::{Deref, DerefMut, Index, IndexMut};
 use std::sync::Arc;
@@ -19,6 +19,14 @@ pub struct RouteStep {
     pub fee_mint: Pubkey,
 }
 
+/// One of the parallel paths of a route, the input amount is split between legs
+#[derive(Clone)]
+pub struct RouteLeg {
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub steps: Vec<RouteStep>,
+}
+
 // no clone
 pub struct Route {
     pub input_mint: Pubkey,
@@ -26,12 +34,98 @@ pub struct Route {
     pub in_amount: u64,
     pub out_amount: u64,
     pub price_impact_bps: u64,
-    // TODO: allow for multiple paths
-    pub steps: Vec<RouteStep>,
+    pub legs: Vec<RouteLeg>,
     pub slot: u64,
     pub accounts: Option<HashMap<Pubkey, AccountData>>,
 }
 
+impl Route {
+    pub fn steps(&self) -> impl Iterator<Item = &RouteStep> {
+        self.legs.iter().flat_map(|leg| leg.steps.iter())
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.legs.len() > 1
+    }
+
+    /// Share of the input amount going through each leg, in percent, summing up to 100
+    pub fn leg_percents(&self) -> Vec<u8> {
+        let total = self
+            .legs
+            .iter()
+            .map(|leg| leg.in_amount as u128)
+            .sum::<u128>();
+        if total == 0 {
+            return self.legs.iter().map(|_| 0).collect();
+        }
+
+        let mut remaining = 100u8;
+        self.legs
+            .iter()
+            .enumerate()
+            .map(|(i, leg)| {
+                let percent = if i == self.legs.len() - 1 {
+                    remaining
+                } else {
+                    ((leg.in_amount as u128 * 100 + total / 2) / total).min(remaining as u128) as u8
+                };
+                remaining -= percent;
+                percent
+            })
+            .collect()
+    }
+}
+
+/// Venues a quote is allowed to go through, matched against `DexInterface::name` (case insensitive)
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, Hash, serde_derive::Serialize, serde_derive::Deserialize,
+)]
+pub struct DexFilter {
+    /// only use these dexes when set
+    only: Option<BTreeSet<String>>,
+    /// never use these dexes
+    exclude: BTreeSet<String>,
+}
+
+impl DexFilter {
+    /// Both lists are comma separated, like the jupiter api `dexes` and `excludeDexes` parameters
+    pub fn new(dexes: Option<&str>, exclude_dexes: Option<&str>) -> Self {
+        fn parse(list: &str) -> BTreeSet<String> {
+            list.split(',')
+                .map(|x| x.trim().to_lowercase())
+                .filter(|x| !x.is_empty())
+                .collect()
+        }
+
+        Self {
+            only: dexes.map(parse).filter(|x| !x.is_empty()),
+            exclude: exclude_dexes.map(parse).unwrap_or_default(),
+        }
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.only.is_none() && self.exclude.is_empty()
+    }
+
+    pub fn accepts(&self, dex_name: &str) -> bool {
+        if self.is_all() {
+            return true;
+        }
+        let dex_name = dex_name.to_lowercase();
+        self.only.as_ref().map_or(true, |x| x.contains(&dex_name))
+            && !self.exclude.contains(&dex_name)
+    }
+
+    /// First dex of the filter that is not one of `dex_names` (lowercase)
+    pub fn unknown_dex(&self, dex_names: &HashSet<String>) -> Option<&String> {
+        self.only
+            .iter()
+            .flatten()
+            .chain(&self.exclude)
+            .find(|x| !dex_names.contains(*x))
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct EdgeWithNodes {
     pub(crate) source_node: MintNodeIndex,
@@ -0,0 +1,412 @@
+use crate::config_reloader::ConfigReloader;
+use crate::edge::{Edge, EdgeState};
+use crate::prelude::*;
+use crate::routing::Routing;
+use crate::server::errors::AppError;
+use axum::extract::{Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::{routing, Json, Router};
+use router_config_lib::{Config, ConfigChanges};
+use serde_derive::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+/// Operator endpoints, served on their own address and protected by a bearer token
+pub struct AdminServer {
+    pub join_handle: JoinHandle<()>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EdgeQuery {
+    pub dex: Option<String>,
+    pub key: Option<String>,
+    pub input_mint: Option<String>,
+    pub output_mint: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EdgeBanRequest {
+    pub key: String,
+    /// Only ban one direction of the pool when set
+    pub input_mint: Option<String>,
+    /// Unix timestamp in milliseconds
+    pub until: u64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EdgeUnbanRequest {
+    pub key: String,
+    pub input_mint: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DexBanRequest {
+    pub dex: String,
+    /// Unix timestamp in milliseconds
+    pub until: u64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DexUnbanRequest {
+    pub dex: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EdgeResponse {
+    pub dex: String,
+    pub key: String,
+    pub desc: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub is_valid: bool,
+    /// (input, price, ln-price), sorted by input asc
+    pub cached_prices: Vec<(u64, f64, f64)>,
+    pub last_update: u64,
+    pub last_update_slot: u64,
+    pub cooldown_event: u64,
+    pub cooldown_until: Option<u64>,
+    pub banned_until: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BanResponse {
+    pub updated_edges: usize,
+}
+
+struct EdgeFilter {
+    dex: Option<String>,
+    key: Option<Pubkey>,
+    input_mint: Option<Pubkey>,
+    output_mint: Option<Pubkey>,
+}
+
+impl EdgeFilter {
+    fn from_query(query: &EdgeQuery) -> anyhow::Result<Self> {
+        Ok(Self {
+            dex: query.dex.clone(),
+            key: parse_optional_pubkey(&query.key)?,
+            input_mint: parse_optional_pubkey(&query.input_mint)?,
+            output_mint: parse_optional_pubkey(&query.output_mint)?,
+        })
+    }
+
+    fn matches(&self, edge: &Edge) -> bool {
+        self.dex.as_ref().map_or(true, |x| edge.dex.name() == *x)
+            && self.key.map_or(true, |x| edge.key() == x)
+            && self.input_mint.map_or(true, |x| edge.input_mint == x)
+            && self.output_mint.map_or(true, |x| edge.output_mint == x)
+    }
+
+    fn select(&self, edges: &[Arc<Edge>]) -> Vec<Arc<Edge>> {
+        edges.iter().filter(|e| self.matches(e)).cloned().collect()
+    }
+}
+
+impl AdminServer {
+    pub async fn start(
+        routing: Arc<Routing>,
+        config_reloader: Arc<ConfigReloader>,
+        config: &Config,
+        exit: tokio::sync::broadcast::Receiver<()>,
+    ) -> anyhow::Result<Option<AdminServer>> {
+        let Some(addr) = config.server.admin_address.clone() else {
+            return Ok(None);
+        };
+        let Some(token) = config.server.admin_token.clone().filter(|x| !x.is_empty()) else {
+            bail!("admin_address is set but admin_token is missing");
+        };
+
+        let app = Self::setup_router(routing, config_reloader, Arc::new(token));
+        let listener = tokio::net::TcpListener::bind(&addr).await?;
+        let handle = axum::serve(listener, app).with_graceful_shutdown(Self::shutdown_signal(exit));
+
+        info!("Admin Server started at {}", addr);
+
+        let join_handle = tokio::spawn(async move {
+            handle.await.expect("Admin Server failed");
+        });
+
+        Ok(Some(AdminServer { join_handle }))
+    }
+
+    async fn shutdown_signal(mut exit: tokio::sync::broadcast::Receiver<()>) {
+        exit.recv()
+            .await
+            .expect("listening to exit broadcast failed");
+        warn!("shutting down admin server...");
+    }
+
+    fn setup_router(
+        routing: Arc<Routing>,
+        config_reloader: Arc<ConfigReloader>,
+        token: Arc<String>,
+    ) -> Router<()> {
+        let mut router = Router::new();
+
+        router = {
+            let routing = routing.clone();
+            router.route(
+                "/admin/edges",
+                routing::get(move |Query(query): Query<EdgeQuery>| async move {
+                    Self::edges_handler(routing, query)
+                }),
+            )
+        };
+
+        router = {
+            let routing = routing.clone();
+            router.route(
+                "/admin/edges/ban",
+                routing::post(move |Json(request): Json<EdgeBanRequest>| async move {
+                    Self::edge_ban_handler(routing, &request.key, &request.input_mint, |state| {
+                        state.ban(request.until)
+                    })
+                }),
+            )
+        };
+
+        router = {
+            let routing = routing.clone();
+            router.route(
+                "/admin/edges/unban",
+                routing::post(move |Json(request): Json<EdgeUnbanRequest>| async move {
+                    Self::edge_ban_handler(routing, &request.key, &request.input_mint, |state| {
+                        state.unban()
+                    })
+                }),
+            )
+        };
+
+        router = {
+            let routing = routing.clone();
+            router.route(
+                "/admin/dexes/ban",
+                routing::post(move |Json(request): Json<DexBanRequest>| async move {
+                    Self::dex_ban_handler(routing, request.dex, Some(request.until))
+                }),
+            )
+        };
+
+        router = {
+            let routing = routing.clone();
+            router.route(
+                "/admin/dexes/unban",
+                routing::post(move |Json(request): Json<DexUnbanRequest>| async move {
+                    Self::dex_ban_handler(routing, request.dex, None)
+                }),
+            )
+        };
+
+        router = router.route(
+            "/admin/config/reload",
+            routing::post(move || async move { Self::config_reload_handler(config_reloader) }),
+        );
+
+        router.route_layer(middleware::from_fn_with_state(token, Self::authorize))
+    }
+
+    async fn authorize(State(token): State<Arc<String>>, request: Request, next: Next) -> Response {
+        let provided = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.strip_prefix("Bearer "));
+
+        match provided {
+            Some(provided) if constant_time_eq(provided.as_bytes(), token.as_bytes()) => {
+                next.run(request).await
+            }
+            _ => StatusCode::UNAUTHORIZED.into_response(),
+        }
+    }
+
+    fn edges_handler(
+        routing: Arc<Routing>,
+        query: EdgeQuery,
+    ) -> Result<Json<Vec<EdgeResponse>>, AppError> {
+        let filter = EdgeFilter::from_query(&query)?;
+        let edges = filter
+            .select(&routing.edges())
+            .iter()
+            .map(|edge| {
+                let state = edge.state.read().unwrap();
+                EdgeResponse {
+                    dex: edge.dex.name(),
+                    key: edge.key().to_string(),
+                    desc: edge.desc(),
+                    input_mint: edge.input_mint.to_string(),
+                    output_mint: edge.output_mint.to_string(),
+                    is_valid: state.is_valid(),
+                    cached_prices: state.cached_prices.clone(),
+                    last_update: state.last_update,
+                    last_update_slot: state.last_update_slot,
+                    cooldown_event: state.cooldown_event,
+                    cooldown_until: state.cooldown_until,
+                    banned_until: state.banned_until,
+                }
+            })
+            .collect();
+
+        Ok(Json(edges))
+    }
+
+    fn edge_ban_handler(
+        routing: Arc<Routing>,
+        key: &str,
+        input_mint: &Option<String>,
+        update: impl Fn(&mut EdgeState),
+    ) -> Result<Json<BanResponse>, AppError> {
+        let filter = EdgeFilter {
+            dex: None,
+            key: Some(Pubkey::from_str(key)?),
+            input_mint: parse_optional_pubkey(input_mint)?,
+            output_mint: None,
+        };
+        Ok(Json(Self::update_edges(&filter, &routing, update)))
+    }
+
+    /// Also covers the edges of the dex added after the ban (pool discovery)
+    fn dex_ban_handler(
+        routing: Arc<Routing>,
+        dex: String,
+        until: Option<u64>,
+    ) -> Result<Json<BanResponse>, AppError> {
+        // recorded first, so that an edge added concurrently is either banned on insert or listed below
+        routing.set_dex_ban(&dex, until);
+        let filter = EdgeFilter {
+            dex: Some(dex),
+            key: None,
+            input_mint: None,
+            output_mint: None,
+        };
+        Ok(Json(Self::update_edges(
+            &filter,
+            &routing,
+            |state| match until {
+                Some(until) => state.ban(until),
+                None => state.unban(),
+            },
+        )))
+    }
+
+    fn update_edges(
+        filter: &EdgeFilter,
+        routing: &Routing,
+        update: impl Fn(&mut EdgeState),
+    ) -> BanResponse {
+        let edges = filter.select(&routing.edges());
+        for edge in edges.iter() {
+            update(&mut edge.state.write().unwrap());
+            info!(edge = edge.desc(), "admin - edge state updated");
+        }
+
+        BanResponse {
+            updated_edges: edges.len(),
+        }
+    }
+
+    fn config_reload_handler(
+        config_reloader: Arc<ConfigReloader>,
+    ) -> Result<Json<ConfigChanges>, AppError> {
+        Ok(Json(config_reloader.reload()?))
+    }
+}
+
+fn parse_optional_pubkey(value: &Option<String>) -> anyhow::Result<Option<Pubkey>> {
+    value
+        .as_ref()
+        .map(|x| Pubkey::from_str(x).context("invalid pubkey"))
+        .transpose()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test::{MockDexIdentifier, MockDexInterface};
+    use router_lib::dex::{DexEdgeIdentifier, DexInterface};
+
+    fn make_edge(dex: &Arc<dyn DexInterface>, key: Pubkey, input: Pubkey, output: Pubkey) -> Edge {
+        Edge {
+            input_mint: input,
+            output_mint: output,
+            dex: dex.clone(),
+            id: Arc::new(MockDexIdentifier {
+                key,
+                input_mint: input,
+                output_mint: output,
+                price: 1.0,
+                depth: None,
+            }) as Arc<dyn DexEdgeIdentifier>,
+            accounts_needed: 0,
+            state: Default::default(),
+        }
+    }
+
+    #[test]
+    fn should_select_edges_by_key_and_direction() {
+        let dex = Arc::new(MockDexInterface::default()) as Arc<dyn DexInterface>;
+        let (pool_1, pool_2) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (usdc, sol) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let edges = vec![
+            Arc::new(make_edge(&dex, pool_1, usdc, sol)),
+            Arc::new(make_edge(&dex, pool_1, sol, usdc)),
+            Arc::new(make_edge(&dex, pool_2, usdc, sol)),
+        ];
+
+        let by_key = EdgeFilter {
+            dex: None,
+            key: Some(pool_1),
+            input_mint: None,
+            output_mint: None,
+        };
+        assert_eq!(by_key.select(&edges).len(), 2);
+
+        let by_direction = EdgeFilter {
+            input_mint: Some(usdc),
+            ..by_key
+        };
+        let selected = by_direction.select(&edges);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].input_mint, usdc);
+
+        let by_dex = EdgeFilter {
+            dex: Some("Mock".to_string()),
+            key: None,
+            input_mint: None,
+            output_mint: None,
+        };
+        assert_eq!(by_dex.select(&edges).len(), 3);
+    }
+
+    #[test]
+    fn should_invalidate_banned_edge_until_unbanned() {
+        let mut state = EdgeState::default();
+        state.ban(u64::MAX);
+        assert!(state.is_banned());
+
+        state.unban();
+        assert!(!state.is_banned());
+
+        state.ban(0);
+        assert!(!state.is_banned());
+    }
+
+    #[test]
+    fn should_compare_tokens() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+    }
+}
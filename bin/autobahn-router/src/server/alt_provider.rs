@@ -1,20 +1,47 @@
+use crate::alt::alt_manager::EdgeUsage;
 use async_trait::async_trait;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_program::address_lookup_table::state::AddressLookupTable;
 use solana_program::address_lookup_table::AddressLookupTableAccount;
 use solana_program::pubkey::Pubkey;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::RwLock;
 use std::time::{Duration, Instant};
 
 #[async_trait]
 pub trait AltProvider {
     async fn get_alt(&self, address: Pubkey) -> anyhow::Result<AddressLookupTableAccount>;
+
+    /// Tables created at runtime, used on top of the configured ones
+    fn managed_alts(&self) -> Vec<Pubkey> {
+        vec![]
+    }
+
+    /// Called with the edges of every quoted route, the accounts of
+    /// frequently routed edges are worth adding to a lookup table
+    fn record_route_edges(&self, _edges: &HashSet<(Pubkey, Pubkey)>) {}
 }
 
 pub struct RpcAltProvider {
     pub rpc_client: RpcClient,
     pub cache: RwLock<HashMap<Pubkey, (Instant, Option<AddressLookupTableAccount>)>>,
+    pub managed: RwLock<Vec<Pubkey>>,
+    /// Only tracked when the alt manager is enabled
+    pub edge_usage: Option<EdgeUsage>,
+}
+
+impl RpcAltProvider {
+    /// Add or refresh a table maintained by the alt manager, its content is known so it is cached right away
+    pub fn update_managed_alt(&self, alt: AddressLookupTableAccount) {
+        {
+            let mut managed = self.managed.write().unwrap();
+            if !managed.contains(&alt.key) {
+                managed.push(alt.key);
+            }
+        }
+        let mut locked = self.cache.write().unwrap();
+        locked.insert(alt.key, (Instant::now(), Some(alt)));
+    }
 }
 
 #[async_trait]
@@ -50,4 +77,14 @@ impl AltProvider for RpcAltProvider {
         locked.insert(address, (Instant::now(), Some(account.clone())));
         Ok(account)
     }
+
+    fn managed_alts(&self) -> Vec<Pubkey> {
+        self.managed.read().unwrap().clone()
+    }
+
+    fn record_route_edges(&self, edges: &HashSet<(Pubkey, Pubkey)>) {
+        if let Some(edge_usage) = &self.edge_usage {
+            edge_usage.record(edges);
+        }
+    }
 }
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Rate limit shared by all the quote streams of a client (by client key),
+/// so that opening more websockets does not get a client more quotes
+pub struct ClientThrottle {
+    throttle: Duration,
+    clients: Mutex<HashMap<String, ClientState>>,
+}
+
+struct ClientState {
+    connections: usize,
+    next_quote_at: Instant,
+}
+
+impl ClientThrottle {
+    pub fn new(throttle: Duration) -> Self {
+        Self {
+            throttle,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn connect(&self, client_key: &str) {
+        let mut clients = self.clients.lock().unwrap();
+        clients
+            .entry(client_key.to_string())
+            .or_insert_with(|| ClientState {
+                connections: 0,
+                next_quote_at: Instant::now(),
+            })
+            .connections += 1;
+    }
+
+    /// Forgets the client once its last stream is closed
+    pub fn disconnect(&self, client_key: &str) {
+        let mut clients = self.clients.lock().unwrap();
+        let Some(state) = clients.get_mut(client_key) else {
+            return;
+        };
+        state.connections = state.connections.saturating_sub(1);
+        if state.connections == 0 {
+            clients.remove(client_key);
+        }
+    }
+
+    /// Returns true if a quote can be pushed to the client now, and consumes the slot
+    pub fn try_acquire(&self, client_key: &str) -> bool {
+        let mut clients = self.clients.lock().unwrap();
+        let Some(state) = clients.get_mut(client_key) else {
+            return false;
+        };
+        let now = Instant::now();
+        if now < state.next_quote_at {
+            return false;
+        }
+        state.next_quote_at = now + self.throttle;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_share_throttle_between_client_connections() {
+        let throttle = ClientThrottle::new(Duration::from_secs(60));
+        throttle.connect("a");
+        throttle.connect("a");
+        throttle.connect("b");
+
+        assert!(throttle.try_acquire("a"));
+        // second connection of the same client
+        assert!(!throttle.try_acquire("a"));
+        assert!(throttle.try_acquire("b"));
+
+        throttle.disconnect("a");
+        assert!(!throttle.try_acquire("a"));
+        throttle.disconnect("a");
+        assert!(!throttle.try_acquire("a"));
+        assert!(throttle.clients.lock().unwrap().get("a").is_none());
+    }
+}
@@ -2,6 +2,9 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use serde_derive::Serialize;
 
+use crate::server::quote_signer::QuoteTokenError;
+use crate::server::route_provider::QuoteRouteError;
+
 // see https://github.com/tokio-rs/axum/blob/main/examples/error-handling/src/main.rs
 // and https://github.com/tokio-rs/axum/blob/main/examples/anyhow-error-response/src/main.rs
 
@@ -28,12 +31,20 @@ impl IntoResponse for AppError {
             message: String,
         }
 
-        let anyhow_message = match self {
-            AppError::Anyhow(err) => err.to_string(),
+        let (status, anyhow_message) = match self {
+            AppError::Anyhow(err) => {
+                // the client sent us a quote we can't (or won't) execute
+                let status = if err.is::<QuoteTokenError>() || err.is::<QuoteRouteError>() {
+                    StatusCode::BAD_REQUEST
+                } else {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                };
+                (status, err.to_string())
+            }
         };
 
         (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            status,
             AppJson(ErrorResponse {
                 message: anyhow_message,
             }),
@@ -1,11 +1,12 @@
 use crate::prelude::*;
 use crate::server::errors::*;
 use crate::server::route_provider::RouteProvider;
-use axum::extract::Query;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, Query};
 use axum::response::Html;
 use axum::{extract::Form, http::header::HeaderMap, routing, Json, Router};
 use router_lib::model::quote_request::QuoteRequest;
-use router_lib::model::quote_response::{QuoteAccount, QuoteResponse};
+use router_lib::model::quote_response::{PlatformFee, QuoteAccount, QuoteResponse};
 use router_lib::model::swap_request::{SwapForm, SwapRequest};
 use router_lib::model::swap_response::{InstructionResponse, SwapIxResponse, SwapResponse};
 use serde_json::Value;
@@ -15,16 +16,20 @@ use solana_sdk::account::ReadableAccount;
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
 use solana_sdk::signature::NullSigner;
 use solana_sdk::transaction::VersionedTransaction;
-use std::time::Instant;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
 use tokio::task::JoinHandle;
 use tower_http::cors::{AllowHeaders, AllowMethods, Any, CorsLayer};
 
 use crate::alt::alt_optimizer;
-use crate::ix_builder::SwapInstructionsBuilder;
+use crate::ix_builder::{PlatformFeeOptions, SwapInstructionsBuilder, SwapOptions};
 use crate::liquidity::{LiquidityProvider, LiquidityProviderArcRw};
-use crate::routing_types::Route;
+use crate::routing_types::{DexFilter, Route};
 use crate::server::alt_provider::AltProvider;
+use crate::server::client_throttle::ClientThrottle;
 use crate::server::hash_provider::HashProvider;
+use crate::server::quote_signer::QuoteSigner;
 use crate::{debug_tools, metrics};
 use router_config_lib::Config;
 use router_lib::dex::{AccountProvider, AccountProviderView, SwapMode};
@@ -36,6 +41,8 @@ use router_lib::model::quote_response::{RoutePlan, SwapInfo};
 const MAX_ACCOUNTS_PER_TX: usize = 64;
 const MAX_TX_SIZE: usize = 1232;
 const DEFAULT_COMPUTE_UNIT_PRICE_MICRO_LAMPORTS: u64 = 10_000;
+const DEFAULT_QUOTE_STREAM_THROTTLE_MS: u64 = 500;
+const MAX_QUOTE_STREAM_RETRY_DELAY: Duration = Duration::from_secs(30);
 
 pub struct HttpServer {
     pub join_handle: JoinHandle<()>,
@@ -55,6 +62,7 @@ impl HttpServer {
         live_account_provider: Arc<TAccountProvider>,
         liquidity_provider: LiquidityProviderArcRw,
         ix_builder: Arc<TIxBuilder>,
+        repriced_edges: broadcast::Sender<Vec<(Pubkey, Pubkey)>>,
         config: Config,
         exit: tokio::sync::broadcast::Receiver<()>,
     ) -> anyhow::Result<HttpServer> {
@@ -65,6 +73,7 @@ impl HttpServer {
             live_account_provider,
             liquidity_provider,
             ix_builder,
+            repriced_edges,
             config,
             exit,
         )
@@ -88,6 +97,7 @@ impl HttpServer {
         live_account_provider: Arc<TAccountProvider>,
         liquidity_provider: LiquidityProviderArcRw,
         ix_builder: Arc<TIxBuilder>,
+        repriced_edges: broadcast::Sender<Vec<(Pubkey, Pubkey)>>,
         config: Config,
         exit: tokio::sync::broadcast::Receiver<()>,
     ) -> anyhow::Result<JoinHandle<()>> {
@@ -108,6 +118,16 @@ impl HttpServer {
             0.0
         };
 
+        let quote_signer = Arc::new(QuoteSigner::from_config(&config.server)?);
+        let quote_stream_throttle = Duration::from_millis(
+            config
+                .server
+                .quote_stream_throttle_ms
+                .unwrap_or(DEFAULT_QUOTE_STREAM_THROTTLE_MS),
+        );
+
+        let quote_stream_clients = Arc::new(ClientThrottle::new(quote_stream_throttle));
+
         let app = Self::setup_router(
             alt,
             route_provider,
@@ -116,10 +136,19 @@ impl HttpServer {
             live_account_provider,
             liquidity_provider,
             ix_builder,
+            quote_signer,
             reprice_frequency,
+            repriced_edges,
+            quote_stream_throttle,
+            quote_stream_clients,
         )?;
         let listener = tokio::net::TcpListener::bind(addr).await?;
-        let handle = axum::serve(listener, app).with_graceful_shutdown(Self::shutdown_signal(exit));
+        // peer addresses key the quote stream throttle of clients without a client key
+        let handle = axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(Self::shutdown_signal(exit));
 
         info!("HTTP Server started at {}", addr);
 
@@ -150,22 +179,75 @@ impl HttpServer {
         alt_provider: Arc<TAltProvider>,
         live_account_provider: Arc<TAccountProvider>,
         ix_builder: Arc<TIxBuilder>,
+        quote_signer: Arc<QuoteSigner>,
         reprice_probability: f64,
         Form(input): Form<QuoteRequest>,
     ) -> Result<Json<Value>, AppError> {
+        let (quote_response, route_edges) = Self::compute_quote(
+            address_lookup_table_addresses,
+            route_provider,
+            hash_provider,
+            alt_provider.clone(),
+            live_account_provider,
+            ix_builder,
+            quote_signer,
+            reprice_probability,
+            &input,
+        )
+        .await?;
+        alt_provider.record_route_edges(&route_edges);
+
+        let json_response = serde_json::json!(quote_response);
+
+        Ok(Json(json_response))
+    }
+
+    /// Returns the signed quote and the edges its route goes through
+    async fn compute_quote<
+        TRouteProvider: RouteProvider + Send + Sync + 'static,
+        THashProvider: HashProvider + Send + Sync + 'static,
+        TAltProvider: AltProvider + Send + Sync + 'static,
+        TAccountProvider: AccountProvider + Send + Sync + 'static,
+        TIxBuilder: SwapInstructionsBuilder + Send + Sync + 'static,
+    >(
+        address_lookup_table_addresses: Vec<String>,
+        route_provider: Arc<TRouteProvider>,
+        hash_provider: Arc<THashProvider>,
+        alt_provider: Arc<TAltProvider>,
+        live_account_provider: Arc<TAccountProvider>,
+        ix_builder: Arc<TIxBuilder>,
+        quote_signer: Arc<QuoteSigner>,
+        reprice_probability: f64,
+        input: &QuoteRequest,
+    ) -> anyhow::Result<(QuoteResponse, HashSet<(Pubkey, Pubkey)>)> {
         let started_at = Instant::now();
         let input_mint = Pubkey::from_str(&input.input_mint)?;
         let output_mint = Pubkey::from_str(&input.output_mint)?;
         let swap_mode = input.swap_mode.or(input.mode).unwrap_or_default();
         let mut max_accounts = input.max_accounts.unwrap_or(64) as usize;
+        let dex_filter = DexFilter::new(input.dexes.as_deref(), input.exclude_dexes.as_deref());
+        let only_direct_routes = input.only_direct_routes.unwrap_or(false);
+
+        let platform_fee_bps = input.platform_fee_bps.unwrap_or(0) as u64;
+        if platform_fee_bps >= 10_000 {
+            anyhow::bail!("platformFeeBps should be below 10000");
+        }
+
+        // Fee is taken on the output, so ExactOut must route the requested amount plus the fee
+        let route_amount = match swap_mode {
+            SwapMode::ExactIn => input.amount,
+            SwapMode::ExactOut => gross_amount_for_net(input.amount, platform_fee_bps),
+        };
 
         let route = loop {
             let route_candidate = route_provider.best_quote(
                 input_mint,
                 output_mint,
-                input.amount,
+                route_amount,
                 max_accounts,
                 swap_mode,
+                &dex_filter,
+                only_direct_routes,
             )?;
 
             let (bytes, accounts_count) = Self::build_swap_tx(
@@ -181,6 +263,8 @@ impl HttpServer {
                 "0".to_string(),
                 swap_mode,
                 DEFAULT_COMPUTE_UNIT_PRICE_MICRO_LAMPORTS,
+                &SwapOptions::default(),
+                false,
             )
             .await?;
 
@@ -201,29 +285,46 @@ impl HttpServer {
 
         Self::log_repriced_amount(live_account_provider, reprice_probability, &route);
 
+        let platform_fee_amount = match swap_mode {
+            SwapMode::ExactIn => {
+                (route.out_amount as u128 * platform_fee_bps as u128 / 10_000) as u64
+            }
+            SwapMode::ExactOut => route.out_amount.saturating_sub(input.amount),
+        };
+        let out_amount = route.out_amount - platform_fee_amount;
+
         let other_amount_threshold = if swap_mode == SwapMode::ExactOut {
             (route.in_amount as f64 * (10_000f64 + input.slippage_bps as f64) / 10_000f64).floor()
                 as u64
         } else {
-            ((route.out_amount as f64 * (10_000f64 - input.slippage_bps as f64)) / 10_000f64)
-                .floor() as u64
+            ((out_amount as f64 * (10_000f64 - input.slippage_bps as f64)) / 10_000f64).floor()
+                as u64
         };
 
+        let route_edges = route
+            .legs
+            .iter()
+            .flat_map(|leg| leg.steps.iter().map(|step| step.edge.unique_id()))
+            .collect();
+
         let route_plan = route
-            .steps
+            .legs
             .iter()
-            .map(|step| RoutePlan {
-                percent: 100,
-                swap_info: Some(SwapInfo {
-                    amm_key: step.edge.key().to_string(),
-                    label: Some(step.edge.dex.name().to_string()),
-                    input_mint: step.edge.input_mint.to_string(),
-                    output_mint: step.edge.output_mint.to_string(),
-                    in_amount: step.in_amount.to_string(),
-                    out_amount: step.out_amount.to_string(),
-                    fee_amount: step.fee_amount.to_string(),
-                    fee_mint: step.fee_mint.to_string(),
-                }),
+            .zip(route.leg_percents())
+            .flat_map(|(leg, percent)| {
+                leg.steps.iter().map(move |step| RoutePlan {
+                    percent: percent as i32,
+                    swap_info: Some(SwapInfo {
+                        amm_key: step.edge.key().to_string(),
+                        label: Some(step.edge.dex.name().to_string()),
+                        input_mint: step.edge.input_mint.to_string(),
+                        output_mint: step.edge.output_mint.to_string(),
+                        in_amount: step.in_amount.to_string(),
+                        out_amount: step.out_amount.to_string(),
+                        fee_amount: step.fee_amount.to_string(),
+                        fee_mint: step.fee_mint.to_string(),
+                    }),
+                })
             })
             .collect_vec();
 
@@ -241,23 +342,170 @@ impl HttpServer {
         };
 
         let context_slot = route.slot;
-        let json_response = serde_json::json!(QuoteResponse {
+        let mut quote_response = QuoteResponse {
             input_mint: input_mint.to_string(),
             in_amount: Some(route.in_amount.to_string()),
             output_mint: output_mint.to_string(),
-            out_amount: route.out_amount.to_string(),
+            out_amount: out_amount.to_string(),
             other_amount_threshold: other_amount_threshold.to_string(),
             swap_mode: swap_mode.to_string(),
             slippage_bps: input.slippage_bps as i32,
-            platform_fee: None, // TODO
+            platform_fee: (platform_fee_bps > 0).then(|| PlatformFee {
+                amount: platform_fee_amount.to_string(),
+                fee_bps: platform_fee_bps as i32,
+            }),
             price_impact_pct: (route.price_impact_bps as f64 / 100.0).to_string(),
             route_plan,
             accounts,
             context_slot,
             time_taken: started_at.elapsed().as_secs_f64(),
-        });
+            quote_token: None,
+        };
+        quote_response.quote_token = quote_signer.sign(&quote_response);
 
-        Ok(Json(json_response))
+        Ok((quote_response, route_edges))
+    }
+
+    /// Websocket counterpart of /quote: the client sends a QuoteRequest as a json text message
+    /// and receives a new QuoteResponse every time an edge of the current best route is repriced.
+    /// Quotes go through the same route provider as /quote, so path discovery stays cached,
+    /// and at most one quote is pushed per throttle period to a client, across all of its streams.
+    /// Sending another request replaces the subscription.
+    async fn quote_stream<
+        TRouteProvider: RouteProvider + Send + Sync + 'static,
+        THashProvider: HashProvider + Send + Sync + 'static,
+        TAltProvider: AltProvider + Send + Sync + 'static,
+        TAccountProvider: AccountProvider + Send + Sync + 'static,
+        TIxBuilder: SwapInstructionsBuilder + Send + Sync + 'static,
+    >(
+        mut socket: WebSocket,
+        client_key: String,
+        throttle_key: String,
+        address_lookup_table_addresses: Vec<String>,
+        route_provider: Arc<TRouteProvider>,
+        hash_provider: Arc<THashProvider>,
+        alt_provider: Arc<TAltProvider>,
+        live_account_provider: Arc<TAccountProvider>,
+        ix_builder: Arc<TIxBuilder>,
+        quote_signer: Arc<QuoteSigner>,
+        reprice_probability: f64,
+        mut repriced_edges: broadcast::Receiver<Vec<(Pubkey, Pubkey)>>,
+        throttle: Duration,
+        client_throttle: Arc<ClientThrottle>,
+    ) {
+        let mut subscription: Option<QuoteRequest> = None;
+        let mut route_edges = HashSet::new();
+        let mut dirty = false;
+        let mut failures = 0u32;
+        let mut retry_at = Instant::now();
+
+        client_throttle.connect(&throttle_key);
+
+        let mut throttle_interval = tokio::time::interval(throttle);
+        throttle_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                msg = socket.recv() => {
+                    match msg {
+                        Some(Ok(Message::Text(text))) => {
+                            match serde_json::from_str::<QuoteRequest>(&text) {
+                                Ok(request) => {
+                                    subscription = Some(request);
+                                    route_edges.clear();
+                                    dirty = true;
+                                    failures = 0;
+                                    retry_at = Instant::now();
+                                }
+                                Err(e) => {
+                                    let error = format!("invalid quote request: {e}");
+                                    if Self::send_stream_error(&mut socket, error).await.is_err() {
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                        Some(Ok(_)) => {}
+                    }
+                }
+                res = repriced_edges.recv() => {
+                    match res {
+                        Ok(edges) => {
+                            if edges.iter().any(|e| route_edges.contains(e)) {
+                                dirty = true;
+                            }
+                        }
+                        // some updates were missed, assume the route was repriced
+                        Err(RecvError::Lagged(_)) => dirty = true,
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+                _ = throttle_interval.tick() => {
+                    let Some(request) = subscription.as_ref().filter(|_| dirty) else {
+                        continue;
+                    };
+                    if Instant::now() < retry_at || !client_throttle.try_acquire(&throttle_key) {
+                        continue;
+                    }
+                    dirty = false;
+
+                    let result = Self::compute_quote(
+                        address_lookup_table_addresses.clone(),
+                        route_provider.clone(),
+                        hash_provider.clone(),
+                        alt_provider.clone(),
+                        live_account_provider.clone(),
+                        ix_builder.clone(),
+                        quote_signer.clone(),
+                        reprice_probability,
+                        request,
+                    )
+                    .await;
+
+                    // on failure the quote is retried with an exponential backoff,
+                    // the error is only sent for the first failure in a row
+                    let sent = match result {
+                        Ok((quote_response, edges)) => {
+                            route_edges = edges;
+                            failures = 0;
+                            metrics::HTTP_REQUESTS_TOTAL
+                                .with_label_values(&["quote-ws", client_key.as_str()])
+                                .inc();
+                            let json = serde_json::json!(quote_response).to_string();
+                            socket.send(Message::Text(json)).await
+                        }
+                        Err(e) => {
+                            metrics::HTTP_REQUESTS_FAILED
+                                .with_label_values(&["quote-ws", client_key.as_str()])
+                                .inc();
+                            dirty = true;
+                            failures += 1;
+                            retry_at = Instant::now()
+                                + throttle
+                                    .saturating_mul(1 << failures.min(16))
+                                    .min(MAX_QUOTE_STREAM_RETRY_DELAY);
+                            if failures == 1 {
+                                Self::send_stream_error(&mut socket, e.to_string()).await
+                            } else {
+                                Ok(())
+                            }
+                        }
+                    };
+                    if sent.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        client_throttle.disconnect(&throttle_key);
+        debug!("quote stream closed for {client_key}");
+    }
+
+    async fn send_stream_error(socket: &mut WebSocket, message: String) -> Result<(), axum::Error> {
+        let json = serde_json::json!({ "message": message }).to_string();
+        socket.send(Message::Text(json)).await
     }
 
     async fn swap_handler<
@@ -273,11 +521,14 @@ impl HttpServer {
         alt_provider: Arc<TAltProvider>,
         live_account_provider: Arc<TAccountProvider>,
         ix_builder: Arc<TIxBuilder>,
+        quote_signer: Arc<QuoteSigner>,
         reprice_probability: f64,
         Query(_query): Query<SwapForm>,
         Json(input): Json<SwapRequest>,
     ) -> Result<Json<Value>, AppError> {
+        quote_signer.verify(&input.quote_response)?;
         let route = route_provider.try_from(&input.quote_response)?;
+        let swap_options = parse_swap_options(&input)?;
 
         Self::log_repriced_amount(live_account_provider, reprice_probability, &route);
 
@@ -302,6 +553,8 @@ impl HttpServer {
             input.quote_response.other_amount_threshold,
             swap_mode,
             compute_unit_price_micro_lamports,
+            &swap_options,
+            input.as_legacy_transaction,
         )
         .await?;
 
@@ -365,6 +618,8 @@ impl HttpServer {
         other_amount_threshold: String,
         swap_mode: SwapMode,
         compute_unit_price_micro_lamports: u64,
+        swap_options: &SwapOptions,
+        as_legacy_transaction: bool,
     ) -> Result<(Vec<u8>, usize), AppError> {
         let wallet_pk = Pubkey::from_str(&wallet_pk)?;
 
@@ -376,6 +631,7 @@ impl HttpServer {
             slippage_bps,
             other_amount_threshold.parse()?,
             swap_mode,
+            swap_options,
         )?;
 
         let compute_budget_ixs = vec![
@@ -391,6 +647,29 @@ impl HttpServer {
             .chain(ixs.cleanup_instructions.into_iter())
             .collect_vec();
 
+        if as_legacy_transaction {
+            // no lookup tables in legacy transactions
+            let accounts = transaction_addresses.iter().unique().count();
+            let message = solana_sdk::message::legacy::Message::new_with_blockhash(
+                instructions.as_slice(),
+                Some(&wallet_pk),
+                &hash_provider.get_latest_hash().await?,
+            );
+            let message = VersionedMessage::Legacy(message);
+            let tx = VersionedTransaction::try_new(message, &[&NullSigner::new(&wallet_pk)])?;
+            let bytes = bincode::serialize(&tx)?;
+
+            if bytes.len() > MAX_TX_SIZE {
+                return Err(anyhow::format_err!(
+                    "Route does not fit in a legacy transaction ({} bytes, {accounts} accounts)",
+                    bytes.len()
+                )
+                .into());
+            }
+
+            return Ok((bytes, accounts));
+        }
+
         let all_alts = Self::load_all_alts(address_lookup_table_addresses, alt_provider).await;
         let alts = alt_optimizer::get_best_alt(&all_alts, &transaction_addresses)?;
         let accounts = transaction_addresses.iter().unique().count()
@@ -419,12 +698,15 @@ impl HttpServer {
         route_provider: Arc<TRouteProvider>,
         alt_provider: Arc<TAltProvider>,
         ix_builder: Arc<TIxBuilder>,
+        quote_signer: Arc<QuoteSigner>,
         Query(_query): Query<SwapForm>,
         Json(input): Json<SwapRequest>,
     ) -> Result<Json<Value>, AppError> {
         let wallet_pk = Pubkey::from_str(&input.user_public_key)?;
 
+        quote_signer.verify(&input.quote_response)?;
         let route_plan = route_provider.try_from(&input.quote_response)?;
+        let swap_options = parse_swap_options(&input)?;
         let swap_mode: SwapMode = SwapMode::from_str(&input.quote_response.swap_mode)
             .map_err(|_| anyhow::Error::msg("Invalid SwapMode"))?;
 
@@ -441,11 +723,16 @@ impl HttpServer {
             input.quote_response.slippage_bps,
             input.quote_response.other_amount_threshold.parse()?,
             swap_mode,
+            &swap_options,
         )?;
 
-        let transaction_addresses = ixs.accounts().into_iter().collect();
-        let all_alts = Self::load_all_alts(address_lookup_table_addresses, alt_provider).await;
-        let alts = alt_optimizer::get_best_alt(&all_alts, &transaction_addresses)?;
+        let alts = if input.as_legacy_transaction {
+            vec![]
+        } else {
+            let transaction_addresses = ixs.accounts().into_iter().collect();
+            let all_alts = Self::load_all_alts(address_lookup_table_addresses, alt_provider).await;
+            alt_optimizer::get_best_alt(&all_alts, &transaction_addresses)?
+        };
 
         let swap_ix = InstructionResponse::from_ix(ixs.swap_instruction)?;
         let setup_ixs: anyhow::Result<Vec<_>> = ixs
@@ -514,6 +801,14 @@ impl HttpServer {
         }
     }
 
+    /// Clients without a client key are throttled by ip, not all together
+    fn throttle_key(headers: &HeaderMap, peer: &SocketAddr) -> String {
+        match headers.get("x-client-key").and_then(|x| x.to_str().ok()) {
+            Some(client_key) => format!("key:{client_key}"),
+            None => format!("ip:{}", peer.ip()),
+        }
+    }
+
     fn setup_router<
         TRouteProvider: RouteProvider + Send + Sync + 'static,
         THashProvider: HashProvider + Send + Sync + 'static,
@@ -528,7 +823,11 @@ impl HttpServer {
         live_account_provider: Arc<TAccountProvider>,
         liquidity_provider: LiquidityProviderArcRw,
         ix_builder: Arc<TIxBuilder>,
+        quote_signer: Arc<QuoteSigner>,
         reprice_probability: f64,
+        repriced_edges: broadcast::Sender<Vec<(Pubkey, Pubkey)>>,
+        quote_stream_throttle: Duration,
+        quote_stream_clients: Arc<ClientThrottle>,
     ) -> anyhow::Result<Router<()>> {
         metrics::HTTP_REQUESTS_FAILED.reset();
 
@@ -552,6 +851,7 @@ impl HttpServer {
         let altp = alt_provider.clone();
         let lap = live_account_provider.clone();
         let ixb = ix_builder.clone();
+        let qs = quote_signer.clone();
         router = router.route(
             "/quote",
             routing::get(move |headers, form| async move {
@@ -561,7 +861,7 @@ impl HttpServer {
                     .start_timer();
 
                 let response =
-                    Self::quote_handler(alt, rp, hp, altp, lap, ixb, reprice_probability, form)
+                    Self::quote_handler(alt, rp, hp, altp, lap, ixb, qs, reprice_probability, form)
                         .await;
 
                 match response {
@@ -587,6 +887,46 @@ impl HttpServer {
         let altp = alt_provider.clone();
         let lap = live_account_provider.clone();
         let ixb = ix_builder.clone();
+        let qs = quote_signer.clone();
+        let re = repriced_edges.clone();
+        let qsc = quote_stream_clients.clone();
+        router = router.route(
+            "/quote/ws",
+            routing::get(
+                move |ConnectInfo(peer): ConnectInfo<SocketAddr>,
+                      headers: HeaderMap,
+                      ws: WebSocketUpgrade| async move {
+                    let client_key = Self::extract_client_key(&headers).to_string();
+                    let throttle_key = Self::throttle_key(&headers, &peer);
+                    ws.on_upgrade(move |socket| {
+                        Self::quote_stream(
+                            socket,
+                            client_key,
+                            throttle_key,
+                            alt,
+                            rp,
+                            hp,
+                            altp,
+                            lap,
+                            ixb,
+                            qs,
+                            reprice_probability,
+                            re.subscribe(),
+                            quote_stream_throttle,
+                            qsc,
+                        )
+                    })
+                },
+            ),
+        );
+
+        let alt = address_lookup_tables.clone();
+        let rp = route_provider.clone();
+        let hp = hash_provider.clone();
+        let altp = alt_provider.clone();
+        let lap = live_account_provider.clone();
+        let ixb = ix_builder.clone();
+        let qs = quote_signer.clone();
         router = router.route(
             "/swap",
             routing::post(move |headers, query, form| async move {
@@ -602,6 +942,7 @@ impl HttpServer {
                     altp,
                     lap,
                     ixb,
+                    qs,
                     reprice_probability,
                     query,
                     form,
@@ -629,6 +970,7 @@ impl HttpServer {
         let rp = route_provider.clone();
         let altp = alt_provider.clone();
         let ixb = ix_builder.clone();
+        let qs = quote_signer.clone();
         router = router.route(
             "/swap-instructions",
             routing::post(move |headers, query, form| async move {
@@ -637,7 +979,7 @@ impl HttpServer {
                     .with_label_values(&["swap-ix", client_key])
                     .start_timer();
 
-                let response = Self::swap_ix_handler(alt, rp, altp, ixb, query, form).await;
+                let response = Self::swap_ix_handler(alt, rp, altp, ixb, qs, query, form).await;
 
                 match response {
                     Ok(_) => {
@@ -664,9 +1006,16 @@ impl HttpServer {
         address_lookup_table_addresses: Vec<String>,
         alt_provider: Arc<TAltProvider>,
     ) -> Vec<AddressLookupTableAccount> {
+        let addresses = address_lookup_table_addresses
+            .iter()
+            .map(|x| Pubkey::from_str(x).unwrap())
+            .chain(alt_provider.managed_alts())
+            .unique()
+            .collect_vec();
+
         let mut all_alts = vec![];
-        for alt in address_lookup_table_addresses {
-            match alt_provider.get_alt(Pubkey::from_str(&alt).unwrap()).await {
+        for alt in addresses {
+            match alt_provider.get_alt(alt).await {
                 Ok(alt) => all_alts.push(alt),
                 Err(_) => {}
             }
@@ -675,16 +1024,92 @@ impl HttpServer {
     }
 }
 
+/// Amount to route so that `net_amount` is left once the platform fee is taken
+fn gross_amount_for_net(net_amount: u64, platform_fee_bps: u64) -> u64 {
+    if platform_fee_bps == 0 {
+        return net_amount;
+    }
+    let gross = (net_amount as u128 * 10_000).div_ceil(10_000 - platform_fee_bps as u128);
+    gross.min(u64::MAX as u128) as u64
+}
+
+/// Swap request fields changing the generated instructions, fails on the ones we can't honour
+fn parse_swap_options(input: &SwapRequest) -> anyhow::Result<SwapOptions> {
+    if input.use_shared_accounts {
+        anyhow::bail!(
+            "useSharedAccounts is not supported, intermediate token accounts are owned by the user"
+        );
+    }
+
+    if input.use_token_ledger {
+        anyhow::bail!("useTokenLedger is not supported");
+    }
+
+    let destination_token_account = input
+        .destination_token_account
+        .as_ref()
+        .map(|x| Pubkey::from_str(x))
+        .transpose()
+        .map_err(|_| anyhow::format_err!("Invalid destinationTokenAccount"))?;
+
+    let fee_account = input
+        .fee_account
+        .as_ref()
+        .map(|x| Pubkey::from_str(x))
+        .transpose()
+        .map_err(|_| anyhow::format_err!("Invalid feeAccount"))?;
+
+    let referrer = input
+        .referrer
+        .as_ref()
+        .map(|x| Pubkey::from_str(x))
+        .transpose()
+        .map_err(|_| anyhow::format_err!("Invalid referrer"))?;
+
+    if referrer.is_some() && fee_account.is_none() {
+        anyhow::bail!("referrer requires a feeAccount");
+    }
+
+    let platform_fee = match (fee_account, &input.quote_response.platform_fee) {
+        (None, _) => None,
+        (Some(fee_account), Some(platform_fee)) if platform_fee.fee_bps > 0 => {
+            let amount = platform_fee
+                .amount
+                .parse::<u64>()
+                .map_err(|_| anyhow::format_err!("Invalid platformFee amount in quote"))?;
+            Some(PlatformFeeOptions {
+                fee_account,
+                amount,
+                referrer,
+            })
+        }
+        (Some(_), _) => anyhow::bail!("feeAccount was provided but the quote has no platform fee"),
+    };
+
+    if destination_token_account.is_some() && platform_fee.is_some() {
+        anyhow::bail!("feeAccount can't be combined with destinationTokenAccount");
+    }
+
+    Ok(SwapOptions {
+        destination_token_account,
+        platform_fee,
+    })
+}
+
 fn reprice<TAccountProvider: AccountProvider + Send + Sync + 'static>(
     route: &Route,
     account_provider: Arc<TAccountProvider>,
 ) -> anyhow::Result<u64> {
     let account_provider = account_provider.clone() as AccountProviderView;
-    let mut amount = route.in_amount;
-    for step in &route.steps {
-        let prepared_quote = step.edge.prepare(&account_provider)?;
-        let quote = step.edge.quote(&prepared_quote, &account_provider, amount);
-        amount = quote?.out_amount;
+    let mut out_amount = 0;
+    for leg in &route.legs {
+        let mut amount = leg.in_amount;
+        for step in &leg.steps {
+            let prepared_quote = step.edge.prepare(&account_provider)?;
+            let quote = step.edge.quote(&prepared_quote, &account_provider, amount);
+            amount = quote?.out_amount;
+        }
+        out_amount += amount;
     }
-    Ok(amount)
+    Ok(out_amount)
 }
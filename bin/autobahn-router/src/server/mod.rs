@@ -1,6 +1,9 @@
+pub mod admin_server;
 pub mod alt_provider;
+pub mod client_throttle;
 pub mod errors;
 pub mod hash_provider;
 pub mod http_server;
 pub mod live_account_provider;
+pub mod quote_signer;
 pub mod route_provider;
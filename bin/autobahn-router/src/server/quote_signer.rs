@@ -0,0 +1,287 @@
+use crate::util::millis_since_epoch;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use router_config_lib::ServerConfig;
+use router_lib::model::quote_response::QuoteResponse;
+use sha2::Sha256;
+use thiserror::Error;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const DEFAULT_QUOTE_VALIDITY_SECS: u64 = 60;
+
+#[derive(Error, Debug)]
+pub enum QuoteTokenError {
+    #[error("missing quote token, quote should come from /quote")]
+    Missing,
+    #[error("malformed quote token")]
+    Malformed,
+    #[error("invalid quote token, quote was modified")]
+    Invalid,
+    #[error("expired quote token, ask for a new quote")]
+    Expired,
+}
+
+/// Sign quotes returned by /quote so that /swap can check they were not tampered with.
+/// The token is the expiry (millis since epoch, u64 le) followed by the hmac of the quote and expiry.
+pub struct QuoteSigner {
+    key: Option<Vec<u8>>,
+    validity_millis: u64,
+}
+
+impl QuoteSigner {
+    /// Signing is enabled by `sign_quotes`, or by default when a key is configured,
+    /// and then fails without a key
+    pub fn from_config(config: &ServerConfig) -> anyhow::Result<Self> {
+        let enabled = config
+            .sign_quotes
+            .unwrap_or(config.quote_signing_key.is_some());
+        if !enabled {
+            return Ok(Self::new(None, 0));
+        }
+
+        let key = config
+            .quote_signing_key
+            .clone()
+            .filter(|x| !x.is_empty())
+            .ok_or_else(|| {
+                anyhow::anyhow!("quote signing is enabled but server.quote_signing_key is empty")
+            })?;
+        let validity_secs = config
+            .quote_validity_secs
+            .unwrap_or(DEFAULT_QUOTE_VALIDITY_SECS);
+        Ok(Self::new(Some(key), validity_secs * 1000))
+    }
+
+    pub fn new(key: Option<String>, validity_millis: u64) -> Self {
+        QuoteSigner {
+            key: key.map(|x| x.into_bytes()),
+            validity_millis,
+        }
+    }
+
+    /// Returns `None` when signing is disabled
+    pub fn sign(&self, quote: &QuoteResponse) -> Option<String> {
+        self.sign_at(quote, millis_since_epoch())
+    }
+
+    /// Always succeed when signing is disabled
+    pub fn verify(&self, quote: &QuoteResponse) -> Result<(), QuoteTokenError> {
+        self.verify_at(quote, millis_since_epoch())
+    }
+
+    fn sign_at(&self, quote: &QuoteResponse, now_millis: u64) -> Option<String> {
+        let expires_at = now_millis + self.validity_millis;
+        let mac = self.mac(quote, expires_at)?;
+
+        let mut token = expires_at.to_le_bytes().to_vec();
+        token.extend_from_slice(&mac.finalize().into_bytes());
+        Some(STANDARD.encode(token))
+    }
+
+    fn verify_at(&self, quote: &QuoteResponse, now_millis: u64) -> Result<(), QuoteTokenError> {
+        if self.key.is_none() {
+            return Ok(());
+        }
+
+        let token = quote.quote_token.as_ref().ok_or(QuoteTokenError::Missing)?;
+        let token = STANDARD
+            .decode(token)
+            .map_err(|_| QuoteTokenError::Malformed)?;
+        if token.len() <= 8 {
+            return Err(QuoteTokenError::Malformed);
+        }
+        let (expires_at, tag) = token.split_at(8);
+        let expires_at = u64::from_le_bytes(expires_at.try_into().unwrap());
+
+        let mac = self.mac(quote, expires_at).unwrap();
+        mac.verify_slice(tag)
+            .map_err(|_| QuoteTokenError::Invalid)?;
+
+        if expires_at < now_millis {
+            return Err(QuoteTokenError::Expired);
+        }
+        Ok(())
+    }
+
+    fn mac(&self, quote: &QuoteResponse, expires_at: u64) -> Option<HmacSha256> {
+        let key = self.key.as_ref()?;
+        let mut mac = HmacSha256::new_from_slice(key).expect("hmac accepts keys of any size");
+        mac.update(&signed_payload(quote));
+        mac.update(&expires_at.to_le_bytes());
+        Some(mac)
+    }
+}
+
+/// Everything used to rebuild the route and the swap transaction
+/// (json encoded so that values can't be shifted from a field to another)
+fn signed_payload(quote: &QuoteResponse) -> Vec<u8> {
+    let route_plan = quote
+        .route_plan
+        .iter()
+        .map(|x| {
+            let swap_info = x.swap_info.as_ref().map(|s| {
+                (
+                    &s.amm_key,
+                    &s.input_mint,
+                    &s.output_mint,
+                    &s.in_amount,
+                    &s.out_amount,
+                    &s.fee_amount,
+                    &s.fee_mint,
+                )
+            });
+            (x.percent, swap_info)
+        })
+        .collect::<Vec<_>>();
+
+    let platform_fee = quote.platform_fee.as_ref().map(|x| (&x.amount, x.fee_bps));
+
+    serde_json::to_vec(&(
+        &quote.input_mint,
+        &quote.in_amount,
+        &quote.output_mint,
+        &quote.out_amount,
+        &quote.other_amount_threshold,
+        &quote.swap_mode,
+        quote.slippage_bps,
+        platform_fee,
+        &quote.price_impact_pct,
+        route_plan,
+        quote.context_slot,
+    ))
+    .expect("quote payload serialization")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use router_lib::model::quote_response::{RoutePlan, SwapInfo};
+
+    fn make_quote() -> QuoteResponse {
+        QuoteResponse {
+            input_mint: "So11111111111111111111111111111111111111112".to_string(),
+            in_amount: Some("1000".to_string()),
+            output_mint: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+            out_amount: "2000".to_string(),
+            other_amount_threshold: "1990".to_string(),
+            swap_mode: "ExactIn".to_string(),
+            slippage_bps: 50,
+            platform_fee: None,
+            price_impact_pct: "0.1".to_string(),
+            route_plan: vec![RoutePlan {
+                percent: 100,
+                swap_info: Some(SwapInfo {
+                    amm_key: "8sLbNZoA1cfnvMJLPfp98ZLAnFSYCFApfJKMbiXNLwxj".to_string(),
+                    label: Some("Raydium".to_string()),
+                    input_mint: "So11111111111111111111111111111111111111112".to_string(),
+                    output_mint: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+                    in_amount: "1000".to_string(),
+                    out_amount: "2000".to_string(),
+                    fee_amount: "1".to_string(),
+                    fee_mint: "So11111111111111111111111111111111111111112".to_string(),
+                }),
+            }],
+            accounts: None,
+            context_slot: 42,
+            time_taken: 0.01,
+            quote_token: None,
+        }
+    }
+
+    #[test]
+    fn should_accept_untouched_quote() {
+        let signer = QuoteSigner::new(Some("secret".to_string()), 60_000);
+        let mut quote = make_quote();
+        quote.quote_token = signer.sign(&quote);
+
+        // not part of the signature
+        quote.time_taken = 1.0;
+
+        assert!(signer.verify(&quote).is_ok());
+    }
+
+    #[test]
+    fn should_reject_tampered_or_unsigned_quote() {
+        let signer = QuoteSigner::new(Some("secret".to_string()), 60_000);
+        let mut quote = make_quote();
+        quote.quote_token = signer.sign(&quote);
+
+        let mut tampered = quote.clone();
+        tampered.other_amount_threshold = "0".to_string();
+        assert!(matches!(
+            signer.verify(&tampered),
+            Err(QuoteTokenError::Invalid)
+        ));
+
+        let mut tampered = quote.clone();
+        tampered.route_plan[0].swap_info.as_mut().unwrap().amm_key =
+            "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2".to_string();
+        assert!(matches!(
+            signer.verify(&tampered),
+            Err(QuoteTokenError::Invalid)
+        ));
+
+        let other_signer = QuoteSigner::new(Some("other".to_string()), 60_000);
+        assert!(matches!(
+            other_signer.verify(&quote),
+            Err(QuoteTokenError::Invalid)
+        ));
+
+        quote.quote_token = None;
+        assert!(matches!(
+            signer.verify(&quote),
+            Err(QuoteTokenError::Missing)
+        ));
+    }
+
+    #[test]
+    fn should_reject_expired_quote() {
+        let signer = QuoteSigner::new(Some("secret".to_string()), 60_000);
+        let mut quote = make_quote();
+        quote.quote_token = signer.sign_at(&quote, 1_000_000);
+
+        assert!(signer.verify_at(&quote, 1_060_000).is_ok());
+        assert!(matches!(
+            signer.verify_at(&quote, 1_060_001),
+            Err(QuoteTokenError::Expired)
+        ));
+
+        // the expiry is part of the signature
+        let mut token = STANDARD.decode(quote.quote_token.unwrap()).unwrap();
+        token[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        quote.quote_token = Some(STANDARD.encode(token));
+        assert!(matches!(
+            signer.verify_at(&quote, 1_060_001),
+            Err(QuoteTokenError::Invalid)
+        ));
+    }
+
+    #[test]
+    fn should_require_a_key_when_signing_is_enabled() {
+        let mut config = ServerConfig::default();
+        assert!(QuoteSigner::from_config(&config).unwrap().key.is_none());
+
+        config.sign_quotes = Some(true);
+        assert!(QuoteSigner::from_config(&config).is_err());
+
+        config.quote_signing_key = Some("".to_string());
+        assert!(QuoteSigner::from_config(&config).is_err());
+
+        config.quote_signing_key = Some("secret".to_string());
+        assert!(QuoteSigner::from_config(&config).unwrap().key.is_some());
+
+        config.sign_quotes = Some(false);
+        assert!(QuoteSigner::from_config(&config).unwrap().key.is_none());
+    }
+
+    #[test]
+    fn should_not_sign_nor_check_without_key() {
+        let signer = QuoteSigner::new(None, 0);
+        let quote = make_quote();
+
+        assert!(signer.sign(&quote).is_none());
+        assert!(signer.verify(&quote).is_ok());
+    }
+}
@@ -1,4 +1,5 @@
 use solana_program::pubkey::Pubkey;
+use thiserror::Error;
 
 use router_lib::model::quote_response::QuoteResponse;
 
@@ -6,12 +7,28 @@ use crate::debug_tools;
 use crate::hot_mints::HotMintsCache;
 use crate::prelude::*;
 use crate::routing::Routing;
-use crate::routing_types::{Route, RouteStep};
+use crate::routing_types::{DexFilter, Route, RouteLeg, RouteStep};
 use crate::token_cache::TokenCache;
 use router_config_lib::SafetyCheckConfig;
 use router_lib::dex::{AccountProviderView, SwapMode};
 use router_lib::price_feeds::price_cache::PriceCache;
 
+#[derive(Error, Debug)]
+pub enum QuoteRouteError {
+    #[error("invalid swap mode {0}")]
+    InvalidSwapMode(String),
+    #[error("quote has no in amount")]
+    MissingInAmount,
+    #[error("quote has an empty route plan")]
+    EmptyRoutePlan,
+    #[error("route plan step {0} has no swap info")]
+    MissingSwapInfo(usize),
+    #[error("route plan uses unknown amm {0}")]
+    UnknownEdge(Pubkey),
+    #[error("route plan does not go from the input mint to the output mint")]
+    InconsistentRoutePlan,
+}
+
 pub trait RouteProvider {
     fn prepare_pruned_edges_and_cleanup_cache(
         &self,
@@ -36,6 +53,8 @@ pub trait RouteProvider {
         amount_native: u64,
         max_accounts: usize,
         swap_mode: SwapMode,
+        dex_filter: &DexFilter,
+        only_direct_routes: bool,
     ) -> anyhow::Result<Route>;
 
     fn try_from(&self, quote_response: &QuoteResponse) -> anyhow::Result<Route>;
@@ -83,6 +102,8 @@ impl RouteProvider for RoutingRouteProvider {
         amount_native: u64,
         max_accounts: usize,
         swap_mode: SwapMode,
+        dex_filter: &DexFilter,
+        only_direct_routes: bool,
     ) -> anyhow::Result<Route> {
         let hot_mints = {
             let mut hot_mints_guard = self.hot_mints.write().unwrap();
@@ -101,6 +122,8 @@ impl RouteProvider for RoutingRouteProvider {
             &hot_mints,
             None,
             swap_mode,
+            dex_filter,
+            only_direct_routes,
         )?;
 
         if !self.config.check_quote_out_amount_deviation {
@@ -136,7 +159,7 @@ impl RouteProvider for RoutingRouteProvider {
                 out_amount_native,
                 in_price_ui,
                 out_price_ui,
-                route = route.steps.iter().map(|x| x.edge.desc()).join(" -> "),
+                route = route.steps().map(|x| x.edge.desc()).join(" -> "),
                 "Very bad route - refusing it",
             );
             anyhow::bail!(
@@ -147,7 +170,7 @@ impl RouteProvider for RoutingRouteProvider {
                 debug_tools::name(&from_mint),
                 out_amount_native,
                 debug_tools::name(&to_mint),
-                route.steps.iter().map(|x| x.edge.desc()).join(" -> "),
+                route.steps().map(|x| x.edge.desc()).join(" -> "),
                 in_price_ui,
                 out_price_ui,
             );
@@ -167,24 +190,34 @@ impl RouteProvider for RoutingRouteProvider {
     fn try_from(&self, quote_response: &QuoteResponse) -> anyhow::Result<Route> {
         let input_mint = Pubkey::from_str(&quote_response.input_mint)?;
         let output_mint = Pubkey::from_str(&quote_response.output_mint)?;
-        let in_amount = quote_response.in_amount.clone().unwrap().parse()?; // TODO Remove opt ? Handle exact out ?
+        let swap_mode = SwapMode::from_str(&quote_response.swap_mode)
+            .map_err(|_| QuoteRouteError::InvalidSwapMode(quote_response.swap_mode.clone()))?;
         let out_amount = quote_response.out_amount.parse()?;
         let price_impact_pct: f64 = quote_response.price_impact_pct.parse()?;
         let price_impact_bps = (price_impact_pct * 100.0).round() as u64;
         let slot = quote_response.context_slot;
 
+        if quote_response.route_plan.is_empty() {
+            return Err(QuoteRouteError::EmptyRoutePlan.into());
+        }
+
         let steps: anyhow::Result<Vec<_>> = quote_response
             .route_plan
-            .clone()
-            .into_iter()
-            .map(|x| -> anyhow::Result<RouteStep> {
-                let step = x.swap_info.unwrap(); // TODO
+            .iter()
+            .enumerate()
+            .map(|(index, x)| -> anyhow::Result<RouteStep> {
+                let step = x
+                    .swap_info
+                    .as_ref()
+                    .ok_or(QuoteRouteError::MissingSwapInfo(index))?;
+                let step_input_mint = step.input_mint.parse()?;
+                let step_output_mint = step.output_mint.parse()?;
+                let amm_key = step.amm_key.parse()?;
                 Ok(RouteStep {
-                    edge: self.routing.find_edge(
-                        step.input_mint.parse()?,
-                        step.output_mint.parse()?,
-                        step.amm_key.parse()?,
-                    )?,
+                    edge: self
+                        .routing
+                        .find_edge(step_input_mint, step_output_mint, amm_key)
+                        .map_err(|_| QuoteRouteError::UnknownEdge(amm_key))?,
                     in_amount: step.in_amount.parse()?,
                     out_amount: step.out_amount.parse()?,
                     fee_amount: step.fee_amount.parse()?,
@@ -193,6 +226,37 @@ impl RouteProvider for RoutingRouteProvider {
             })
             .collect();
 
+        // Paths never loop, so every step leaving the input mint starts a new leg
+        let mut legs: Vec<RouteLeg> = vec![];
+        for step in steps? {
+            match legs.last_mut() {
+                Some(leg) if step.edge.input_mint != input_mint => {
+                    leg.out_amount = step.out_amount;
+                    leg.steps.push(step);
+                }
+                _ => legs.push(RouteLeg {
+                    in_amount: step.in_amount,
+                    out_amount: step.out_amount,
+                    steps: vec![step],
+                }),
+            }
+        }
+
+        if legs.iter().any(|leg| {
+            leg.steps[0].edge.input_mint != input_mint
+                || leg.steps[leg.steps.len() - 1].edge.output_mint != output_mint
+        }) {
+            return Err(QuoteRouteError::InconsistentRoutePlan.into());
+        }
+
+        // ExactOut quotes don't need to carry the in amount, the route plan knows it
+        let legs_in_amount = legs.iter().map(|x| x.in_amount).sum::<u64>();
+        let in_amount = match &quote_response.in_amount {
+            Some(in_amount) => in_amount.parse()?,
+            None if swap_mode == SwapMode::ExactOut => legs_in_amount,
+            None => return Err(QuoteRouteError::MissingInAmount.into()),
+        };
+
         Ok(Route {
             input_mint,
             output_mint,
@@ -200,7 +264,7 @@ impl RouteProvider for RoutingRouteProvider {
             out_amount,
             price_impact_bps,
             slot,
-            steps: steps?,
+            legs,
             accounts: None, // TODO FAS
         })
     }
@@ -0,0 +1,61 @@
+use anchor_spl::token::spl_token;
+use solana_program::pubkey::Pubkey;
+use std::collections::HashSet;
+
+/// Decides which account writes coming from geyser are forwarded to chain data
+#[derive(Clone, Default)]
+pub struct AccountFilter {
+    /// accounts used by known edges
+    pub accounts: HashSet<Pubkey>,
+    /// accept any account owned by these programs, so that new pools can be discovered
+    pub discovery_programs: HashSet<Pubkey>,
+    /// accept token accounts owned by these (pool authorities), so that vaults of new pools are received
+    pub discovery_token_owners: HashSet<Pubkey>,
+}
+
+impl AccountFilter {
+    /// Used for snapshots, that are only taken for accounts known at startup
+    pub fn contains(&self, pubkey: &Pubkey) -> bool {
+        self.accounts.contains(pubkey)
+    }
+
+    pub fn accept(&self, pubkey: &Pubkey, owner: &Pubkey, data: &[u8]) -> bool {
+        if self.accounts.contains(pubkey) || self.discovery_programs.contains(owner) {
+            return true;
+        }
+
+        // spl token account layout: mint (32), owner (32), ...
+        *owner == spl_token::ID
+            && !self.discovery_token_owners.is_empty()
+            && data.len() >= 64
+            && Pubkey::try_from(&data[32..64])
+                .is_ok_and(|token_owner| self.discovery_token_owners.contains(&token_owner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_accept_known_and_discoverable_accounts() {
+        let known = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let filter = AccountFilter {
+            accounts: HashSet::from([known]),
+            discovery_programs: HashSet::from([program]),
+            discovery_token_owners: HashSet::from([authority]),
+        };
+
+        let mut vault_data = vec![0u8; 165];
+        vault_data[32..64].copy_from_slice(authority.as_ref());
+        let other_token_data = vec![0u8; 165];
+
+        assert!(filter.accept(&known, &Pubkey::new_unique(), &[]));
+        assert!(filter.accept(&Pubkey::new_unique(), &program, &[]));
+        assert!(filter.accept(&Pubkey::new_unique(), &spl_token::ID, &vault_data));
+        assert!(!filter.accept(&Pubkey::new_unique(), &spl_token::ID, &other_token_data));
+        assert!(!filter.accept(&Pubkey::new_unique(), &Pubkey::new_unique(), &vault_data));
+    }
+}
@@ -1,52 +1,628 @@
-use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
 
+use anchor_spl::token::spl_token;
+use async_channel::Sender;
+use futures::StreamExt;
+use itertools::Itertools;
+use mango_feeds_connector::chain_data::SlotStatus;
 use mango_feeds_connector::SlotUpdate;
+use prometheus::{IntCounter, IntGauge};
+use solana_program::clock::Slot;
 use solana_program::pubkey::Pubkey;
+use tracing::*;
 
 use router_config_lib::AccountDataSourceConfig;
-use router_feed_lib::account_write::AccountOrSnapshotUpdate;
-use router_feed_lib::get_program_account::FeedMetadata;
+use router_feed_lib::account_write::{AccountOrSnapshotUpdate, AccountWrite};
+use router_feed_lib::get_program_account::{CustomSnapshotProgramAccounts, FeedMetadata};
 
+use crate::metrics;
+use crate::source::account_filter::AccountFilter;
 use crate::source::grpc_plugin_source;
+use crate::source::snapshot::{spawn_snapshot_tasks, SnapshotScheduler};
 
 use super::quic_plugin_source;
 
+/// Index of the source in the list of configured sources (quic sources first, then grpc)
+pub type SourceId = usize;
+
+pub enum SourceMessage {
+    /// write_version is reassigned when merging the sources, see `WriteDedup`
+    AccountUpdate(SourceId, AccountWrite),
+    SlotUpdate(SourceId, SlotUpdate),
+    /// The source lost its connection and is reconnecting
+    Disconnected(SourceId),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceKind {
+    Grpc,
+    Quic,
+}
+
+struct SourceKindMetrics {
+    account_writes: &'static IntCounter,
+    account_write_queue: &'static IntGauge,
+    dedup_queue: &'static IntGauge,
+    slot_updates: &'static IntCounter,
+    slot_update_queue: &'static IntGauge,
+}
+
+impl SourceKind {
+    fn metrics(&self) -> SourceKindMetrics {
+        match self {
+            SourceKind::Grpc => SourceKindMetrics {
+                account_writes: &metrics::GRPC_ACCOUNT_WRITES,
+                account_write_queue: &metrics::GRPC_ACCOUNT_WRITE_QUEUE,
+                dedup_queue: &metrics::GRPC_DEDUP_QUEUE,
+                slot_updates: &metrics::GRPC_SLOT_UPDATES,
+                slot_update_queue: &metrics::GRPC_SLOT_UPDATE_QUEUE,
+            },
+            SourceKind::Quic => SourceKindMetrics {
+                account_writes: &metrics::QUIC_ACCOUNT_WRITES,
+                account_write_queue: &metrics::QUIC_ACCOUNT_WRITE_QUEUE,
+                dedup_queue: &metrics::QUIC_DEDUP_QUEUE,
+                slot_updates: &metrics::QUIC_SLOT_UPDATES,
+                slot_update_queue: &metrics::QUIC_SLOT_UPDATE_QUEUE,
+            },
+        }
+    }
+}
+
+struct SourceState {
+    name: String,
+    kind: SourceKind,
+    highest_slot: Slot,
+    demoted: bool,
+    connected: bool,
+}
+
+/// Compares the sources against the freshest one,
+/// updates from a source lagging too far behind are ignored until it catches up
+pub struct SourceTracker {
+    sources: Vec<SourceState>,
+    newest_slot: Slot,
+    max_slot_lag: u64,
+}
+
+impl SourceTracker {
+    pub fn new(sources: Vec<(String, SourceKind)>, max_slot_lag: u64) -> Self {
+        Self {
+            sources: sources
+                .into_iter()
+                .map(|(name, kind)| SourceState {
+                    name,
+                    kind,
+                    highest_slot: 0,
+                    demoted: false,
+                    connected: false,
+                })
+                .collect(),
+            newest_slot: 0,
+            max_slot_lag,
+        }
+    }
+
+    pub fn kind(&self, source: SourceId) -> SourceKind {
+        self.sources[source].kind
+    }
+
+    pub fn is_demoted(&self, source: SourceId) -> bool {
+        self.sources[source].demoted
+    }
+
+    pub fn on_slot(&mut self, source: SourceId, slot: Slot) {
+        self.sources[source].connected = true;
+        if slot <= self.sources[source].highest_slot {
+            return;
+        }
+        self.sources[source].highest_slot = slot;
+
+        if slot > self.newest_slot {
+            // every other source is now a bit further behind
+            self.newest_slot = slot;
+            for source in 0..self.sources.len() {
+                self.update_lag(source);
+            }
+        } else {
+            self.update_lag(source);
+        }
+    }
+
+    /// Returns true when no source is connected anymore
+    pub fn on_disconnected(&mut self, source: SourceId) -> bool {
+        self.sources[source].connected = false;
+        self.sources.iter().all(|x| !x.connected)
+    }
+
+    fn update_lag(&mut self, source: SourceId) {
+        let state = &mut self.sources[source];
+        let lag = self.newest_slot.saturating_sub(state.highest_slot);
+
+        // promote back only once mostly caught up, to avoid flapping
+        let demoted = if state.demoted {
+            lag > self.max_slot_lag / 2
+        } else {
+            lag > self.max_slot_lag
+        };
+
+        if demoted != state.demoted {
+            if demoted {
+                warn!(
+                    source = state.name,
+                    lag, "geyser source is lagging behind, demoting it"
+                );
+            } else {
+                info!(
+                    source = state.name,
+                    lag, "geyser source caught up, promoting it"
+                );
+            }
+            state.demoted = demoted;
+        }
+
+        metrics::GEYSER_SOURCE_SLOT_LAG
+            .with_label_values(&[&state.name])
+            .set(lag as i64);
+        metrics::GEYSER_SOURCE_DEMOTED
+            .with_label_values(&[&state.name])
+            .set(demoted as i64);
+    }
+}
+
+/// Merges the account writes of every source for a (slot, pubkey)
+///
+/// write_versions are private to each node, so writes are told apart by their content instead:
+/// the n-th write of some data by a source is a duplicate if any source already sent it n times.
+/// Counting occurrences keeps a write that sets an account back to an earlier state within the slot.
+/// Accepted writes get a write_version consistent across sources, starting at 1
+/// (write version 0 is reserved for snapshots).
+#[derive(Default)]
+pub struct WriteDedup {
+    // slot -> (pubkey -> writes)
+    slots: HashMap<Slot, HashMap<Pubkey, SlotPubkeyWrites>>,
+}
+
+#[derive(Default)]
+struct SlotPubkeyWrites {
+    accepted: HashMap<u64, u32>,
+    per_source: HashMap<SourceId, HashMap<u64, u32>>,
+    write_version: u64,
+}
+
+impl WriteDedup {
+    /// Returns the write_version to use, or None for a duplicate
+    pub fn accept(&mut self, source: SourceId, update: &AccountWrite) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        update.lamports.hash(&mut hasher);
+        update.owner.hash(&mut hasher);
+        update.executable.hash(&mut hasher);
+        update.data.hash(&mut hasher);
+        let data_hash = hasher.finish();
+
+        let writes = self
+            .slots
+            .entry(update.slot)
+            .or_default()
+            .entry(update.pubkey)
+            .or_default();
+        let seen = writes
+            .per_source
+            .entry(source)
+            .or_default()
+            .entry(data_hash)
+            .or_default();
+        *seen += 1;
+
+        let accepted = writes.accepted.entry(data_hash).or_default();
+        if *seen <= *accepted {
+            return None;
+        }
+        *accepted = *seen;
+        writes.write_version += 1;
+        Some(writes.write_version)
+    }
+
+    /// Forget the slots older than `retention` slots before `slot`
+    pub fn retain(&mut self, slot: Slot, retention: u64) {
+        self.slots
+            .retain(|&k, _| k >= slot.saturating_sub(retention));
+    }
+}
+
 pub async fn spawn_geyser_source(
     config: &AccountDataSourceConfig,
-    exit_receiver: tokio::sync::broadcast::Receiver<()>,
-    account_write_sender: async_channel::Sender<AccountOrSnapshotUpdate>,
-    metadata_write_sender: async_channel::Sender<FeedMetadata>,
-    slot_sender: async_channel::Sender<SlotUpdate>,
+    mut exit: tokio::sync::broadcast::Receiver<()>,
+    account_write_queue_sender: async_channel::Sender<AccountOrSnapshotUpdate>,
+    metadata_write_queue_sender: async_channel::Sender<FeedMetadata>,
+    slot_queue_sender: async_channel::Sender<SlotUpdate>,
     subscribed_accounts: &HashSet<Pubkey>,
     subscribed_programs: &HashSet<Pubkey>,
     subscribed_token_accounts: &HashSet<Pubkey>,
-    filters: &HashSet<Pubkey>,
+    filters: &AccountFilter,
 ) {
-    if config.quic_sources.is_some() {
-        quic_plugin_source::process_events(
-            config.clone(),
+    // Every source feeds the same dedup queue
+    let (msg_sender, msg_receiver) =
+        async_channel::bounded::<SourceMessage>(config.dedup_queue_size);
+    let mut sources = vec![];
+    let mut source_jobs = vec![];
+
+    for quic_source in config.quic_sources.clone().unwrap_or_default() {
+        sources.push((quic_source.name.clone(), SourceKind::Quic));
+        source_jobs.push(quic_plugin_source::spawn_source_job(
+            sources.len() - 1,
+            quic_source,
             subscribed_accounts.clone(),
             subscribed_programs.clone(),
             subscribed_token_accounts.clone(),
-            filters.clone(),
-            account_write_sender,
-            Some(metadata_write_sender),
-            slot_sender,
-            exit_receiver,
-        )
-        .await;
-    } else if config.grpc_sources.is_some() {
-        grpc_plugin_source::process_events(
-            config.clone(),
+            msg_sender.clone(),
+        ));
+    }
+
+    for grpc_source in config.grpc_sources.clone().unwrap_or_default() {
+        sources.push((grpc_source.name.clone(), SourceKind::Grpc));
+        source_jobs.push(grpc_plugin_source::spawn_source_job(
+            sources.len() - 1,
+            grpc_source,
             subscribed_accounts.clone(),
             subscribed_programs.clone(),
             subscribed_token_accounts.clone(),
-            filters.clone(),
-            account_write_sender,
-            Some(metadata_write_sender),
-            slot_sender,
-            exit_receiver,
-        )
-        .await;
+            msg_sender.clone(),
+        ));
+    }
+
+    info!(
+        "geyser sources: {}",
+        sources.iter().map(|(name, _)| name).join(", ")
+    );
+
+    let mut tracker = SourceTracker::new(sources, config.max_source_slot_lag.unwrap_or(20));
+
+    // Writes already sent by a source are discarded
+    let mut write_dedup = WriteDedup::default();
+
+    // Number of slots to retain in write_dedup
+    let latest_write_retention = 50;
+
+    // Snapshots are taken once for all the sources
+    let mut snapshot_scheduler = SnapshotScheduler::default();
+    let (snapshot_sender, mut snapshot_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut re_snapshot_interval = tokio::time::interval(Duration::from_secs(
+        config.re_snapshot_interval_secs.unwrap_or(60 * 60 * 12),
+    ));
+    re_snapshot_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    re_snapshot_interval.tick().await;
+
+    let mut source_jobs: futures::stream::FuturesUnordered<_> = source_jobs.into_iter().collect();
+
+    loop {
+        tokio::select! {
+            _ = source_jobs.next() => {
+                // other sources take over, as long as there is one
+                if source_jobs.is_empty() {
+                    warn!("shutting down geyser source because every source failed...");
+                    break;
+                }
+                warn!("a geyser source failed, {} left", source_jobs.len());
+            },
+            _ = exit.recv() => {
+                warn!("shutting down geyser source...");
+                break;
+            }
+            _ = re_snapshot_interval.tick() => {
+                info!("Re-snapshot hack");
+                snapshot_scheduler.request();
+            }
+            snapshot = snapshot_receiver.recv() => {
+                // the sender is kept alive by this loop
+                match snapshot.expect("snapshot channel is open") {
+                    Ok(snapshot) => {
+                        snapshot_scheduler.on_snapshot(snapshot.slot);
+                        // New - Don't care if the snapshot is old, we want startup to work anyway
+                        // If an edge is not working properly, it will be disabled when swapping it
+                        process_snapshot(&account_write_queue_sender,
+                            &metadata_write_queue_sender,
+                            snapshot,
+                            filters,
+                            ).await;
+                    }
+                    Err(e) => {
+                        warn!("failed to snapshot accounts, retrying: {:?}", e);
+                        snapshot_scheduler.request();
+                    }
+                }
+            }
+            msg = msg_receiver.recv() => {
+                match msg {
+                    Ok(msg) => {
+                        let start_snapshot = process_account_updated_from_sources(&account_write_queue_sender,
+                            &slot_queue_sender,
+                            &msg_receiver,
+                            msg,
+                            &mut tracker,
+                            &mut write_dedup,
+                            latest_write_retention,
+                            &mut snapshot_scheduler,
+                            filters,
+                            ).await;
+                        if start_snapshot {
+                            spawn_snapshot_tasks(config,
+                                subscribed_accounts,
+                                subscribed_programs,
+                                subscribed_token_accounts,
+                                &snapshot_sender,
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        warn!("failed to process geyser event: {:?}", e);
+                        break;
+                    }
+                };
+            },
+        };
+    }
+
+    // close all channels to notify downstream CSPs of error
+    account_write_queue_sender.close();
+    metadata_write_queue_sender.close();
+    slot_queue_sender.close();
+}
+
+// consume channel with update data, returns true when the snapshot tasks should be started
+async fn process_account_updated_from_sources(
+    account_write_queue_sender: &Sender<AccountOrSnapshotUpdate>,
+    slot_queue_sender: &Sender<SlotUpdate>,
+    msg_receiver: &async_channel::Receiver<SourceMessage>,
+    msg: SourceMessage,
+    tracker: &mut SourceTracker,
+    write_dedup: &mut WriteDedup,
+    // in slots
+    latest_write_retention: u64,
+    snapshot_scheduler: &mut SnapshotScheduler,
+    filters: &AccountFilter,
+) -> bool {
+    let source = match &msg {
+        SourceMessage::AccountUpdate(source, _)
+        | SourceMessage::SlotUpdate(source, _)
+        | SourceMessage::Disconnected(source) => *source,
+    };
+    let source_metrics = tracker.kind(source).metrics();
+
+    source_metrics.dedup_queue.set(msg_receiver.len() as i64);
+    match msg {
+        SourceMessage::AccountUpdate(source, mut update) => {
+            source_metrics.account_writes.inc();
+            source_metrics
+                .account_write_queue
+                .set(account_write_queue_sender.len() as i64);
+
+            tracker.on_slot(source, update.slot);
+            if tracker.is_demoted(source) {
+                return false;
+            }
+
+            if !filters.accept(&update.pubkey, &update.owner, &update.data) {
+                return false;
+            }
+
+            // Skip writes that a different server has already sent
+            let Some(write_version) = write_dedup.accept(source, &update) else {
+                return false;
+            };
+            update.write_version = write_version;
+            write_dedup.retain(update.slot, latest_write_retention);
+
+            account_write_queue_sender
+                .send(AccountOrSnapshotUpdate::AccountUpdate(update))
+                .await
+                .expect("send success");
+            false
+        }
+        SourceMessage::SlotUpdate(source, update) => {
+            source_metrics.slot_updates.inc();
+            source_metrics
+                .slot_update_queue
+                .set(slot_queue_sender.len() as i64);
+
+            tracker.on_slot(source, update.slot);
+            if tracker.is_demoted(source) {
+                return false;
+            }
+
+            let start_snapshot = update.status == SlotStatus::Rooted
+                && snapshot_scheduler.on_rooted_slot(update.slot);
+            slot_queue_sender.send(update).await.expect("send success");
+            start_snapshot
+        }
+        SourceMessage::Disconnected(source) => {
+            if tracker.on_disconnected(source) {
+                warn!("every geyser source is disconnected, accounts will be snapshotted again");
+                snapshot_scheduler.restart();
+            }
+            false
+        }
+    }
+}
+
+async fn process_snapshot(
+    account_write_queue_sender: &Sender<AccountOrSnapshotUpdate>,
+    metadata_write_queue_sender: &Sender<FeedMetadata>,
+    update: CustomSnapshotProgramAccounts,
+    filters: &AccountFilter,
+) {
+    let metadata_sender = |msg| metadata_write_queue_sender.send_blocking(msg);
+
+    let label = if let Some(prg) = update.program_id {
+        if prg == spl_token::ID {
+            "gpa(tokens)"
+        } else {
+            "gpa"
+        }
+    } else {
+        "gma"
+    };
+    metrics::ACCOUNT_SNAPSHOTS
+        .with_label_values(&[&label])
+        .inc();
+    debug!(
+        "processing snapshot for program_id {} -> size={} & missing size={}...",
+        update
+            .program_id
+            .map(|x| x.to_string())
+            .unwrap_or("".to_string()),
+        update.accounts.len(),
+        update.missing_accounts.len()
+    );
+    if let Err(e) = metadata_sender(FeedMetadata::SnapshotStart(update.program_id)) {
+        warn!("failed to send feed matadata event: {}", e);
+    }
+
+    let mut updated_accounts = vec![];
+    for account in update.accounts {
+        metrics::GRPC_SNAPSHOT_ACCOUNT_WRITES.inc();
+        metrics::GRPC_ACCOUNT_WRITE_QUEUE.set(account_write_queue_sender.len() as i64);
+
+        if !filters.contains(&account.pubkey) {
+            continue;
+        }
+
+        updated_accounts.push(account);
+    }
+    account_write_queue_sender
+        .send(AccountOrSnapshotUpdate::SnapshotUpdate(updated_accounts))
+        .await
+        .expect("send success");
+
+    for account in update.missing_accounts {
+        if let Err(e) = metadata_sender(FeedMetadata::InvalidAccount(account)) {
+            warn!("failed to send feed matadata event: {}", e);
+        }
+    }
+    debug!("processing snapshot done");
+    if let Err(e) = metadata_sender(FeedMetadata::SnapshotEnd(update.program_id)) {
+        warn!("failed to send feed matadata event: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_demote_lagging_source_until_it_catches_up() {
+        let mut tracker = SourceTracker::new(
+            vec![
+                ("a".to_string(), SourceKind::Grpc),
+                ("b".to_string(), SourceKind::Quic),
+            ],
+            10,
+        );
+
+        tracker.on_slot(0, 100);
+        tracker.on_slot(1, 100);
+        assert!(!tracker.is_demoted(0));
+        assert!(!tracker.is_demoted(1));
+
+        // b stops sending updates
+        tracker.on_slot(0, 111);
+        assert!(!tracker.is_demoted(0));
+        assert!(tracker.is_demoted(1));
+
+        // not caught up enough yet
+        tracker.on_slot(1, 105);
+        assert!(tracker.is_demoted(1));
+
+        tracker.on_slot(1, 106);
+        assert!(!tracker.is_demoted(1));
+
+        // older slots do not move anything backward
+        tracker.on_slot(0, 50);
+        assert!(!tracker.is_demoted(0));
+    }
+
+    fn account_write(pubkey: Pubkey, slot: Slot, data: Vec<u8>) -> AccountWrite {
+        AccountWrite {
+            pubkey,
+            slot,
+            write_version: 0,
+            lamports: 1,
+            owner: Pubkey::default(),
+            executable: false,
+            rent_epoch: 0,
+            data,
+        }
+    }
+
+    #[test]
+    fn should_dedup_writes_across_sources_on_their_content() {
+        let mut dedup = WriteDedup::default();
+        let pubkey = Pubkey::new_unique();
+
+        // a sets the account to 1, 2 then back to 1 within the slot
+        assert_eq!(
+            Some(1),
+            dedup.accept(0, &account_write(pubkey, 10, vec![1]))
+        );
+        assert_eq!(
+            Some(2),
+            dedup.accept(0, &account_write(pubkey, 10, vec![2]))
+        );
+        assert_eq!(
+            Some(3),
+            dedup.accept(0, &account_write(pubkey, 10, vec![1]))
+        );
+
+        // b sends the same writes, whatever its own write_version
+        let mut from_b = account_write(pubkey, 10, vec![1]);
+        from_b.write_version = 42;
+        assert_eq!(None, dedup.accept(1, &from_b));
+        assert_eq!(None, dedup.accept(1, &account_write(pubkey, 10, vec![2])));
+        assert_eq!(None, dedup.accept(1, &account_write(pubkey, 10, vec![1])));
+
+        // b is ahead of a
+        assert_eq!(
+            Some(4),
+            dedup.accept(1, &account_write(pubkey, 10, vec![3]))
+        );
+        assert_eq!(None, dedup.accept(0, &account_write(pubkey, 10, vec![3])));
+
+        // other slots and accounts are independent
+        assert_eq!(
+            Some(1),
+            dedup.accept(1, &account_write(pubkey, 11, vec![1]))
+        );
+        assert_eq!(
+            Some(1),
+            dedup.accept(1, &account_write(Pubkey::new_unique(), 10, vec![1]))
+        );
+
+        dedup.retain(100, 50);
+        assert_eq!(
+            Some(1),
+            dedup.accept(0, &account_write(pubkey, 10, vec![1]))
+        );
+    }
+
+    #[test]
+    fn should_report_when_every_source_is_disconnected() {
+        let mut tracker = SourceTracker::new(
+            vec![
+                ("a".to_string(), SourceKind::Grpc),
+                ("b".to_string(), SourceKind::Quic),
+            ],
+            10,
+        );
+
+        tracker.on_slot(0, 100);
+        tracker.on_slot(1, 100);
+        assert!(!tracker.on_disconnected(0));
+
+        // a reconnected
+        tracker.on_slot(0, 101);
+        assert!(!tracker.on_disconnected(1));
+        assert!(tracker.on_disconnected(0));
     }
 }
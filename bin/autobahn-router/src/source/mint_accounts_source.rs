@@ -1,5 +1,3 @@
-use anchor_lang::AccountDeserialize;
-use anchor_spl::token::Mint;
 use futures_util::future::join_all;
 use itertools::Itertools;
 use jsonrpc_core_client::transports::http;
@@ -9,6 +7,8 @@ use solana_client::rpc_config::RpcAccountInfoConfig;
 use solana_program::pubkey::Pubkey;
 use solana_sdk::account::Account;
 use solana_sdk::commitment_config::CommitmentConfig;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -24,6 +24,9 @@ const MAX_PARALLEL_HEAVY_RPC_REQUESTS: usize = 16;
 pub struct Token {
     pub mint: Pubkey,
     pub decimals: u8,
+    /// spl-token or spl-token-2022
+    pub program: Pubkey,
+    pub transfer_fee: Option<TransferFeeConfig>,
 }
 
 pub async fn request_mint_metadata(
@@ -70,19 +73,25 @@ pub async fn request_mint_metadata(
             let mut mint_accounts: HashMap<Pubkey, Token> = HashMap::with_capacity(accounts.len());
             for (account_pk, ui_account) in accounts {
                 if let Some(ui_account) = ui_account {
-                    let mut account: Account = ui_account.decode().unwrap();
-                    let data = account.data.as_mut_slice();
-                    let mint_account = Mint::try_deserialize(&mut &*data).unwrap();
+                    let account: Account = ui_account.decode().unwrap();
+                    // also reads legacy mints, which have no extensions
+                    let mint_account =
+                        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data)
+                            .unwrap();
+                    let transfer_fee = mint_account.get_extension::<TransferFeeConfig>().ok();
                     trace!(
-                        "Mint Account {}: decimals={}",
+                        "Mint Account {}: decimals={} transfer_fee={}",
                         account_pk.to_string(),
-                        mint_account.decimals
+                        mint_account.base.decimals,
+                        transfer_fee.is_some()
                     );
                     mint_accounts.insert(
                         account_pk,
                         Token {
                             mint: account_pk,
-                            decimals: mint_account.decimals,
+                            decimals: mint_account.base.decimals,
+                            program: account.owner,
+                            transfer_fee: transfer_fee.copied(),
                         },
                     );
                     count.fetch_add(1, Ordering::Relaxed);
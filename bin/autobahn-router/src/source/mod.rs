@@ -1,4 +1,6 @@
+pub mod account_filter;
 pub mod geyser;
 pub mod grpc_plugin_source;
 pub mod mint_accounts_source;
 pub mod quic_plugin_source;
+pub mod snapshot;
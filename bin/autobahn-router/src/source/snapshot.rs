@@ -0,0 +1,206 @@
+use itertools::Itertools;
+use solana_program::clock::Slot;
+use solana_program::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::env;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Semaphore;
+use tracing::*;
+
+use router_config_lib::AccountDataSourceConfig;
+use router_feed_lib::get_program_account::{
+    get_snapshot_gma, get_snapshot_gpa, get_snapshot_gta, CustomSnapshotProgramAccounts,
+};
+
+// limit number of concurrent gMA/gPA requests
+const MAX_PARALLEL_HEAVY_RPC_REQUESTS: usize = 4;
+
+/// Decides when the gMA/gPA/gTA snapshots are requested, once for all the geyser sources
+///
+/// We can't get a snapshot immediately since the finalized snapshot would be for a
+/// slot in the past and we'd be missing intermediate updates.
+///
+/// Delay the request until the first slot we received all writes for becomes rooted
+/// to avoid that problem - partially. The rooted slot will still be larger than the
+/// finalized slot, so add a number of slots as a buffer.
+///
+/// If that buffer isn't sufficient, there'll be a retry.
+pub struct SnapshotScheduler {
+    // The first slot that we will receive _all_ account writes for
+    first_full_slot: Slot,
+    // If a snapshot should be performed when ready.
+    snapshot_needed: bool,
+    // The highest "rooted" slot that has been seen.
+    max_rooted_slot: Slot,
+    // Number of slots that we expect "finalized" commitment to lag
+    // behind "rooted". This matters for getProgramAccounts based snapshots,
+    // which will have "finalized" commitment.
+    rooted_to_finalized_slots: u64,
+}
+
+impl Default for SnapshotScheduler {
+    fn default() -> Self {
+        Self {
+            first_full_slot: Slot::MAX,
+            snapshot_needed: true,
+            max_rooted_slot: 0,
+            rooted_to_finalized_slots: 30,
+        }
+    }
+}
+
+impl SnapshotScheduler {
+    /// Returns true when the snapshot tasks should be started now
+    pub fn on_rooted_slot(&mut self, slot: Slot) -> bool {
+        if self.first_full_slot == Slot::MAX {
+            self.first_full_slot = slot + 1;
+        }
+        self.max_rooted_slot = self.max_rooted_slot.max(slot);
+
+        let waiting_for_snapshot_slot =
+            self.max_rooted_slot <= self.first_full_slot + self.rooted_to_finalized_slots;
+        if waiting_for_snapshot_slot {
+            debug!(
+                "waiting for snapshot slot: rooted={}, first_full={}, slot={}",
+                self.max_rooted_slot, self.first_full_slot, slot
+            );
+            return false;
+        }
+
+        std::mem::take(&mut self.snapshot_needed)
+    }
+
+    /// Asks for another snapshot if this one is older than the first slot updates were received for
+    pub fn on_snapshot(&mut self, snapshot_slot: Slot) {
+        debug!(
+            "snapshot is for slot {}, first full slot was {}",
+            snapshot_slot, self.first_full_slot
+        );
+        if snapshot_slot < self.first_full_slot {
+            warn!(
+                "snapshot is too old: has slot {}, expected {} minimum - request another one but also use this snapshot",
+                snapshot_slot,
+                self.first_full_slot
+            );
+            // try again in another 25 slots
+            self.snapshot_needed = true;
+            self.rooted_to_finalized_slots += 25;
+        }
+    }
+
+    pub fn request(&mut self) {
+        self.snapshot_needed = true;
+    }
+
+    /// Every source was disconnected, updates may have been missed:
+    /// wait for the sources to deliver full slots again, then snapshot
+    pub fn restart(&mut self) {
+        self.first_full_slot = Slot::MAX;
+        self.snapshot_needed = true;
+    }
+}
+
+pub fn spawn_snapshot_tasks(
+    config: &AccountDataSourceConfig,
+    subscribed_accounts: &HashSet<Pubkey>,
+    subscribed_programs: &HashSet<Pubkey>,
+    subscribed_token_accounts: &HashSet<Pubkey>,
+    sender: &UnboundedSender<anyhow::Result<CustomSnapshotProgramAccounts>>,
+) {
+    let use_compression = config.rpc_support_compression.unwrap_or(false);
+    let number_of_accounts_per_gma = config.number_of_accounts_per_gma.unwrap_or(100);
+    let snapshot_rpc_http_url = match &config.rpc_http_url.chars().next().unwrap() {
+        '$' => env::var(&config.rpc_http_url[1..]).expect("reading connection string from env"),
+        _ => config.rpc_http_url.clone(),
+    };
+
+    debug!("snapshot slot reached - setting up snapshot tasks");
+
+    let permits_parallel_rpc_requests = Arc::new(Semaphore::new(MAX_PARALLEL_HEAVY_RPC_REQUESTS));
+
+    info!(
+        "Requesting snapshot from gMA for {} filter accounts",
+        subscribed_accounts.len()
+    );
+    for pubkey_chunk in subscribed_accounts
+        .iter()
+        .chunks(number_of_accounts_per_gma)
+        .into_iter()
+    {
+        let rpc_http_url = snapshot_rpc_http_url.clone();
+        let account_ids = pubkey_chunk.cloned().collect_vec();
+        let sender = sender.clone();
+        let permits = permits_parallel_rpc_requests.clone();
+        tokio::spawn(async move {
+            let _permit = permits.acquire().await.unwrap();
+            let snapshot = get_snapshot_gma(&rpc_http_url, &account_ids).await;
+            if sender.send(snapshot).is_err() {
+                warn!("Could not send snapshot, geyser source has probably stopped");
+            }
+        });
+    }
+
+    info!(
+        "Requesting snapshot from gPA for {} program filter accounts",
+        subscribed_programs.len()
+    );
+    for program_id in subscribed_programs {
+        let rpc_http_url = snapshot_rpc_http_url.clone();
+        let program_id = *program_id;
+        let sender = sender.clone();
+        let permits = permits_parallel_rpc_requests.clone();
+        tokio::spawn(async move {
+            let _permit = permits.acquire().await.unwrap();
+            let snapshot = get_snapshot_gpa(&rpc_http_url, &program_id, use_compression).await;
+            if sender.send(snapshot).is_err() {
+                warn!("Could not send snapshot, geyser source has probably stopped");
+            }
+        });
+    }
+
+    info!(
+        "Requesting snapshot from gTA for {} owners filter accounts",
+        subscribed_token_accounts.len()
+    );
+    for owner_id in subscribed_token_accounts {
+        let rpc_http_url = snapshot_rpc_http_url.clone();
+        let owner_id = *owner_id;
+        let sender = sender.clone();
+        let permits = permits_parallel_rpc_requests.clone();
+        tokio::spawn(async move {
+            let _permit = permits.acquire().await.unwrap();
+            let snapshot = get_snapshot_gta(&rpc_http_url, &owner_id).await;
+            if sender.send(snapshot).is_err() {
+                warn!("Could not send snapshot, geyser source has probably stopped");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_snapshot_once_rooted_past_the_first_full_slot() {
+        let mut scheduler = SnapshotScheduler::default();
+
+        assert!(!scheduler.on_rooted_slot(100));
+        assert!(!scheduler.on_rooted_slot(131));
+        assert!(scheduler.on_rooted_slot(132));
+        // already requested
+        assert!(!scheduler.on_rooted_slot(133));
+
+        // too old, waits 25 more slots
+        scheduler.on_snapshot(90);
+        assert!(!scheduler.on_rooted_slot(156));
+        assert!(scheduler.on_rooted_slot(157));
+
+        scheduler.request();
+        assert!(scheduler.on_rooted_slot(158));
+
+        scheduler.restart();
+        assert!(!scheduler.on_rooted_slot(200));
+    }
+}
@@ -2,6 +2,7 @@
 mod tests {
     use crate::edge::Edge;
     use crate::routing::Routing;
+    use crate::routing_types::DexFilter;
     use crate::tests::dex_test_utils;
     use crate::{debug_tools, syscallstubs};
     use anchor_spl::token::spl_token::state::Mint;
@@ -122,6 +123,8 @@ mod tests {
                         &hot_mints,
                         None,
                         SwapMode::ExactIn,
+                        &DexFilter::default(),
+                        false,
                     );
 
                     match path {
@@ -137,7 +140,7 @@ mod tests {
                                     debug_tools::name(&p.0),
                                     debug_tools::name(&p.1),
                                     start.elapsed().as_micros() as f64 / 1000.0,
-                                    path.steps.len(),
+                                    path.steps().count(),
                                     in_amount_dollars,
                                     out_amount_dollars,
                                 );
@@ -147,7 +150,7 @@ mod tests {
                                     debug_tools::name(&p.0),
                                     debug_tools::name(&p.1),
                                     start.elapsed().as_micros() as f64 / 1000.0,
-                                    path.steps.len(),
+                                    path.steps().count(),
                                     in_amount_dollars,
                                     out_amount_dollars,
                                 );
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::routing::Routing;
+    use crate::routing_types::DexFilter;
     use crate::syscallstubs;
     use crate::tests::dex_test_utils;
     use itertools::Itertools;
@@ -117,6 +118,8 @@ mod tests {
                 &HashSet::new(),
                 None,
                 SwapMode::ExactIn,
+                &DexFilter::default(),
+                false,
             )
             .unwrap();
 
@@ -126,6 +129,6 @@ mod tests {
         );
         println!("out_amount: {}", path.out_amount);
         println!("price_impact (bps): {}", path.price_impact_bps);
-        println!("steps count: {}", path.steps.len());
+        println!("steps count: {}", path.steps().count());
     }
 }
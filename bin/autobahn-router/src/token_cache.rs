@@ -1,6 +1,11 @@
+use anchor_lang::Id;
+use anchor_spl::token::Token as TokenProgram;
+use mango_feeds_connector::chain_data::AccountData;
+use router_lib::dex::{AccountProvider, AccountProviderView};
 use solana_program::pubkey::Pubkey;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
 use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tracing::warn;
 
 pub type Decimals = u8;
@@ -9,25 +14,31 @@ pub type Decimals = u8;
 pub struct Token {
     pub mint: Pubkey,
     pub decimals: Decimals,
+    /// spl-token or spl-token-2022
+    pub program: Pubkey,
+    /// Token-2022 transfer fee extension, applied on every transfer of the mint
+    pub transfer_fee: Option<TransferFeeConfig>,
 }
 
 #[derive(Clone)]
 pub struct TokenCache {
-    tokens: Arc<HashMap<Pubkey, Decimals>>,
+    tokens: Arc<RwLock<HashMap<Pubkey, Token>>>,
 }
 
 impl TokenCache {
-    pub fn new(data: HashMap<Pubkey, Decimals>) -> Self {
+    pub fn new(data: HashMap<Pubkey, Token>) -> Self {
         Self {
-            tokens: Arc::new(data),
+            tokens: Arc::new(RwLock::new(data)),
         }
     }
 
     // use Result over Option to be compatible
     pub fn token(&self, mint: Pubkey) -> anyhow::Result<Token> {
         self.tokens
+            .read()
+            .unwrap()
             .get(&mint)
-            .map(|&decimals| Token { mint, decimals })
+            .copied()
             .ok_or_else(|| {
                 // this should never happen
                 warn!("Token not found in cache: {}", mint);
@@ -35,10 +46,77 @@ impl TokenCache {
             })
     }
 
+    pub fn contains(&self, mint: &Pubkey) -> bool {
+        self.tokens.read().unwrap().contains_key(mint)
+    }
+
+    /// Used for mints of pools discovered after startup
+    pub fn insert(&self, token: Token) {
+        self.tokens.write().unwrap().insert(token.mint, token);
+    }
+
+    pub fn transfer_fee(&self, mint: &Pubkey) -> Option<TransferFeeConfig> {
+        self.tokens
+            .read()
+            .unwrap()
+            .get(mint)
+            .and_then(|x| x.transfer_fee)
+    }
+
+    /// Falls back to spl-token for unknown mints
+    pub fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.tokens
+            .read()
+            .unwrap()
+            .get(mint)
+            .map_or(TokenProgram::id(), |x| x.program)
+    }
+
     pub fn tokens(&self) -> HashSet<Pubkey> {
         self.tokens
+            .read()
+            .unwrap()
             .iter()
             .map(|(k, _)| *k)
             .collect::<HashSet<Pubkey>>()
     }
+
+    pub fn decimals(&self) -> HashMap<Pubkey, Decimals> {
+        self.tokens
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (*k, v.decimals))
+            .collect()
+    }
+}
+
+/// Chain data whose token programs come from the token cache,
+/// so that adapters derive the same user ATAs as the router creates
+pub struct TokenCacheAccountProvider {
+    chain_data: AccountProviderView,
+    token_cache: TokenCache,
+}
+
+impl TokenCacheAccountProvider {
+    pub fn new(chain_data: AccountProviderView, token_cache: TokenCache) -> Self {
+        Self {
+            chain_data,
+            token_cache,
+        }
+    }
+}
+
+impl AccountProvider for TokenCacheAccountProvider {
+    fn account(&self, address: &Pubkey) -> anyhow::Result<AccountData> {
+        self.chain_data.account(address)
+    }
+
+    fn newest_processed_slot(&self) -> u64 {
+        self.chain_data.newest_processed_slot()
+    }
+
+    fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.token_cache.token_program(mint)
+    }
 }
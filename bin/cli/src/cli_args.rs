@@ -81,10 +81,74 @@ pub struct DecodeLog {
     pub data: String,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct CreateReferral {
+    /// Keypair paying for the vault creation
+    #[clap(short, long)]
+    pub owner: String,
+
+    #[clap(long)]
+    pub referrer: String,
+
+    #[clap(long)]
+    pub mint: String,
+
+    #[clap(flatten)]
+    pub rpc: Rpc,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct WithdrawReferral {
+    /// Referrer keypair
+    #[clap(short, long)]
+    pub owner: String,
+
+    #[clap(long)]
+    pub mint: String,
+
+    /// Defaults to the referrer associated token account
+    #[clap(long)]
+    pub destination: Option<String>,
+
+    #[clap(flatten)]
+    pub rpc: Rpc,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ListReferralBalances {
+    #[clap(long)]
+    pub referrer: String,
+
+    #[clap(long, value_delimiter = ',')]
+    pub mints: Vec<String>,
+
+    #[clap(flatten)]
+    pub rpc: Rpc,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ManageAlts {
+    /// Keypair paying for and owning the lookup tables
+    #[clap(short, long)]
+    pub owner: String,
+
+    /// Added to the owned tables, new tables are created when they are full.
+    /// Tables are only listed when empty
+    #[clap(long, value_delimiter = ',')]
+    pub accounts: Vec<String>,
+
+    #[clap(flatten)]
+    pub rpc: Rpc,
+}
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum Command {
     Swap(Swap),
     Quote(Quote),
     DownloadTestPrograms(DownloadTestPrograms),
     DecodeLog(DecodeLog),
+    CreateReferral(CreateReferral),
+    WithdrawReferral(WithdrawReferral),
+    ListReferralBalances(ListReferralBalances),
+    ManageAlts(ManageAlts),
 }
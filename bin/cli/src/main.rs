@@ -1,12 +1,15 @@
 use crate::cli_args::{Cli, Command};
 use crate::util::{string_or_env, tracing_subscriber_init};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
 use autobahn_executor::logs::*;
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
 use bincode::serialize;
 use clap::Parser;
 use router_config_lib::Config;
+use router_lib::alt_manager;
 use router_lib::dex::SwapMode;
+use router_lib::referral::{create_referral_ix, referrer_vault_address, withdraw_referral_ix};
 use router_lib::router_client::RouterClient;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_client::RpcClient as BlockingRpcClient;
@@ -14,9 +17,11 @@ use solana_client::rpc_config::{RpcSendTransactionConfig, RpcSimulateTransaction
 use solana_sdk::address_lookup_table::state::AddressLookupTable;
 use solana_sdk::address_lookup_table::AddressLookupTableAccount;
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::Keypair;
-use solana_sdk::signer::EncodableKey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::{EncodableKey, Signer};
+use solana_sdk::transaction::Transaction;
 use std::str::FromStr;
 
 mod cli_args;
@@ -146,6 +151,84 @@ async fn main() -> Result<(), anyhow::Error> {
         Command::DownloadTestPrograms(download) => {
             let _config = Config::load(&download.config)?;
         }
+        Command::CreateReferral(create) => {
+            let payer = Keypair::read_from_file(create.owner).expect("couldn't read keypair");
+            let rpc_client = RpcClient::new(string_or_env(create.rpc.url));
+            let referrer = Pubkey::from_str(&create.referrer)?;
+            let mint = Pubkey::from_str(&create.mint)?;
+            let token_program = rpc_client.get_account(&mint).await?.owner;
+
+            let (vault, _) = referrer_vault_address(&referrer, &mint);
+            let ix = create_referral_ix(&payer.pubkey(), &referrer, &mint, &token_program);
+            let sig = send_ix(&rpc_client, ix, &payer).await?;
+
+            println!(
+                "created referral vault {} for mint {}: {}",
+                vault, mint, sig
+            );
+        }
+        Command::WithdrawReferral(withdraw) => {
+            let referrer = Keypair::read_from_file(withdraw.owner).expect("couldn't read keypair");
+            let rpc_client = RpcClient::new(string_or_env(withdraw.rpc.url));
+            let mint = Pubkey::from_str(&withdraw.mint)?;
+            let token_program = rpc_client.get_account(&mint).await?.owner;
+            let destination = match withdraw.destination {
+                Some(destination) => Pubkey::from_str(&destination)?,
+                None => get_associated_token_address_with_program_id(
+                    &referrer.pubkey(),
+                    &mint,
+                    &token_program,
+                ),
+            };
+
+            let ix = withdraw_referral_ix(&referrer.pubkey(), &mint, &destination, &token_program);
+            let sig = send_ix(&rpc_client, ix, &referrer).await?;
+
+            println!("withdrew referral fees to {}: {}", destination, sig);
+        }
+        Command::ListReferralBalances(list) => {
+            let rpc_client = RpcClient::new(string_or_env(list.rpc.url));
+            let referrer = Pubkey::from_str(&list.referrer)?;
+
+            for mint in list.mints {
+                let mint = Pubkey::from_str(&mint)?;
+                let (vault, _) = referrer_vault_address(&referrer, &mint);
+                match rpc_client.get_token_account_balance(&vault).await {
+                    Ok(balance) => println!(
+                        "mint: {}, vault: {}, balance: {}",
+                        mint, vault, balance.ui_amount_string
+                    ),
+                    Err(_) => println!("mint: {}, vault: {} not created", mint, vault),
+                }
+            }
+        }
+        Command::ManageAlts(manage) => {
+            let owner = Keypair::read_from_file(manage.owner).expect("couldn't read keypair");
+            let rpc_client = RpcClient::new(string_or_env(manage.rpc.url));
+            let accounts = manage
+                .accounts
+                .iter()
+                .map(|x| Pubkey::from_str(x))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut tables =
+                alt_manager::fetch_managed_tables(&rpc_client, &owner.pubkey()).await?;
+            if !accounts.is_empty() {
+                let touched = alt_manager::add_accounts(
+                    &rpc_client,
+                    &owner,
+                    &mut tables,
+                    accounts,
+                    &alt_manager::AltLimits::default(),
+                )
+                .await?;
+                println!("created or extended {} table(s)", touched.len());
+            }
+
+            for table in tables {
+                println!("table: {}, addresses: {}", table.key, table.addresses.len());
+            }
+        }
         Command::DecodeLog(log) => {
             let decoded = BASE64_STANDARD.decode(log.data)?;
             let discriminant: &[u8; 8] = &decoded[..8].try_into().unwrap();
@@ -180,3 +263,23 @@ async fn main() -> Result<(), anyhow::Error> {
 
     Ok(())
 }
+
+async fn send_ix(
+    rpc_client: &RpcClient,
+    ix: Instruction,
+    signer: &Keypair,
+) -> anyhow::Result<Signature> {
+    let latest_blockhash = rpc_client.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        latest_blockhash,
+    );
+
+    let sig = rpc_client
+        .send_and_confirm_transaction_with_spinner(&tx)
+        .await?;
+
+    Ok(sig)
+}
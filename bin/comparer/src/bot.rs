@@ -48,6 +48,9 @@ pub(crate) async fn run(
             birdeye_token: config.birdeye_token.to_string(),
             birdeye_single_mode: None,
             refresh_interval_secs: 25,
+            onchain_fallback: None,
+            onchain_only: None,
+            oracles: None,
         },
         exit.resubscribe(),
     );
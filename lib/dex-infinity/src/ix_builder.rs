@@ -2,7 +2,7 @@ use jupiter_amm_interface::SwapParams;
 use s_jup_interface::{SPoolInitAccounts, SPoolInitKeys, SPoolJup};
 use sanctum_lst_list::SanctumLstList;
 use solana_sdk::{account::Account, pubkey::Pubkey};
-use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 use std::collections::HashMap;
 
 use router_lib::dex::{AccountProviderView, SwapInstruction};
@@ -47,8 +47,16 @@ pub fn build_swap_ix(
 
     let (in_mint, out_mint) = (id.input_mint, id.output_mint);
 
-    let in_pubkey = get_associated_token_address(wallet_pk, &in_mint);
-    let out_pubkey = get_associated_token_address(wallet_pk, &out_mint);
+    let in_pubkey = get_associated_token_address_with_program_id(
+        wallet_pk,
+        &in_mint,
+        &chain_data.token_program(&in_mint),
+    );
+    let out_pubkey = get_associated_token_address_with_program_id(
+        wallet_pk,
+        &out_mint,
+        &chain_data.token_program(&out_mint),
+    );
     let min_out_amount =
         ((out_amount as f64 * (10_000f64 - max_slippage_bps as f64)) / 10_000f64).floor() as u64; // TODO
 
@@ -3,7 +3,7 @@ use std::{
     sync::Arc,
 };
 
-use anchor_lang::{AnchorDeserialize, Id};
+use anchor_lang::{AnchorDeserialize, Discriminator, Id};
 use anchor_spl::{
     token::spl_token::{self, state::AccountState},
     token_2022::Token2022,
@@ -326,6 +326,49 @@ impl DexInterface for InvariantDex {
         self.edges.clone()
     }
 
+    fn discover_edges(
+        &self,
+        pk: &Pubkey,
+        chain_data: &AccountProviderView,
+    ) -> anyhow::Result<HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>> {
+        let pool_account = chain_data.account(pk)?;
+        let data = pool_account.account.data();
+        if data.len() != Pool::LEN || !data.starts_with(&Pool::DISCRIMINATOR) {
+            return Ok(HashMap::new());
+        }
+        let pool = Self::deserialize::<Pool>(pool_account.account.data())?;
+
+        let entry: Vec<Arc<dyn DexEdgeIdentifier>> = vec![
+            Arc::new(InvariantEdgeIdentifier {
+                pool: *pk,
+                token_x: pool.token_x,
+                token_y: pool.token_y,
+                x_to_y: true,
+            }),
+            Arc::new(InvariantEdgeIdentifier {
+                pool: *pk,
+                token_x: pool.token_x,
+                token_y: pool.token_y,
+                x_to_y: false,
+            }),
+        ];
+
+        let mut map = HashMap::new();
+        map.insert(*pk, entry.clone());
+        map.insert(pool.tickmap, entry.clone());
+
+        // the tickmap is usually created along the pool, but might not be received yet
+        if let Result::Ok(tickmap_account) = chain_data.account(&pool.tickmap) {
+            let tickmap = Self::deserialize::<Tickmap>(tickmap_account.account.data())?;
+            let indexes = Self::find_all_tick_indexes(pool.tick_spacing, &tickmap)?;
+            for tick in indexes {
+                map.insert(Self::tick_index_to_address(*pk, tick), entry.clone());
+            }
+        }
+
+        Ok(map)
+    }
+
     fn load(
         &self,
         id: &Arc<dyn DexEdgeIdentifier>,
@@ -411,7 +454,7 @@ impl DexInterface for InvariantDex {
     }
 
     fn supports_exact_out(&self, _id: &Arc<dyn DexEdgeIdentifier>) -> bool {
-        false
+        true
     }
 
     fn quote_exact_out(
@@ -421,8 +464,6 @@ impl DexInterface for InvariantDex {
         _chain_data: &AccountProviderView,
         out_amount: u64,
     ) -> anyhow::Result<Quote> {
-        anyhow::bail!("Not supported");
-
         let edge = edge.as_any().downcast_ref::<InvariantEdge>().unwrap();
         let id = id
             .as_any()
@@ -441,7 +482,7 @@ impl DexInterface for InvariantDex {
                 x_to_y,
                 in_amount: out_amount,
                 sqrt_price_limit,
-                by_amount_in: true,
+                by_amount_in: false,
             })
             .map_err(|e| anyhow::format_err!(e))
             .with_context(|| format!("pool {} x_to_y {}", id.pool, id.x_to_y))?;
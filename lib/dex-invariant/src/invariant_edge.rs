@@ -68,6 +68,7 @@ pub struct InvariantEdge {
 #[derive(Debug, Default)]
 pub struct InvariantSimulationParams {
     pub x_to_y: bool,
+    /// output amount when not by_amount_in
     pub in_amount: u64,
     pub sqrt_price_limit: Price,
     pub by_amount_in: bool,
@@ -144,8 +145,11 @@ impl InvariantEdge {
                 formatted
             })?;
 
-            remaining_amount =
-                remaining_amount.checked_sub(result.amount_in.checked_add(result.fee_amount)?)?;
+            remaining_amount = if by_amount_in {
+                remaining_amount.checked_sub(result.amount_in.checked_add(result.fee_amount)?)?
+            } else {
+                remaining_amount.checked_sub(result.amount_out)?
+            };
             pool.sqrt_price = result.next_price_sqrt;
             total_amount_in = total_amount_in
                 .checked_add(result.amount_in)?
@@ -215,9 +219,11 @@ impl InvariantEdge {
                             break;
                         }
                     } else if !remaining_amount.is_zero() {
-                        total_amount_in = total_amount_in
-                            .checked_add(remaining_amount)
-                            .map_err(|_| "add overflow")?;
+                        if by_amount_in {
+                            total_amount_in = total_amount_in
+                                .checked_add(remaining_amount)
+                                .map_err(|_| "add overflow")?;
+                        }
                         remaining_amount = TokenAmount(0);
                     }
                 } else {
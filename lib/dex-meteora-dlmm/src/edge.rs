@@ -0,0 +1,105 @@
+use anchor_lang::AccountDeserialize;
+use commons::quote::get_bin_array_pubkeys_for_swap;
+use lb_clmm::state::bin::BinArray;
+use lb_clmm::state::bin_array_bitmap_extension::BinArrayBitmapExtension;
+use lb_clmm::state::lb_pair::LbPair;
+use lb_clmm::utils::pda::derive_bin_array_bitmap_extension;
+use router_lib::dex::{AccountProviderView, DexEdge, DexEdgeIdentifier};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+use std::any::Any;
+use std::collections::HashMap;
+
+/// Number of bin arrays with liquidity loaded in the swap direction, and passed to the swap instruction
+pub const MAX_BIN_ARRAYS: u8 = 3;
+
+pub struct MeteoraDlmmEdgeIdentifier {
+    pub pool: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// token x is the input
+    pub swap_for_y: bool,
+}
+
+impl DexEdgeIdentifier for MeteoraDlmmEdgeIdentifier {
+    fn key(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn desc(&self) -> String {
+        format!("MeteoraDlmm_{}", self.pool)
+    }
+
+    fn input_mint(&self) -> Pubkey {
+        self.mint_a
+    }
+
+    fn output_mint(&self) -> Pubkey {
+        self.mint_b
+    }
+
+    fn accounts_needed(&self) -> usize {
+        16
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct MeteoraDlmmEdge {
+    pub lb_pair: LbPair,
+    pub bitmap_extension: Option<BinArrayBitmapExtension>,
+    pub bin_arrays: HashMap<Pubkey, BinArray>,
+}
+
+impl DexEdge for MeteoraDlmmEdge {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The extension only exists for pairs with liquidity outside of the bitmap stored in the pair
+pub fn load_bitmap_extension(
+    pool_pk: &Pubkey,
+    chain_data: &AccountProviderView,
+) -> anyhow::Result<Option<BinArrayBitmapExtension>> {
+    let (extension_pk, _) = derive_bin_array_bitmap_extension(*pool_pk);
+    let Ok(account) = chain_data.account(&extension_pk) else {
+        return Ok(None);
+    };
+    Ok(Some(BinArrayBitmapExtension::try_deserialize(
+        &mut account.account.data(),
+    )?))
+}
+
+/// Bin arrays the next swaps in the given direction will go through, in swap order
+pub fn bin_array_pks(
+    pool_pk: &Pubkey,
+    lb_pair: &LbPair,
+    bitmap_extension: &Option<BinArrayBitmapExtension>,
+    swap_for_y: bool,
+) -> anyhow::Result<Vec<Pubkey>> {
+    get_bin_array_pubkeys_for_swap(
+        *pool_pk,
+        lb_pair,
+        bitmap_extension.as_ref(),
+        swap_for_y,
+        MAX_BIN_ARRAYS,
+    )
+}
+
+/// Loads the bin arrays present in chain data, a swap going further will fail to quote
+pub fn load_bin_arrays(
+    bin_array_pks: &[Pubkey],
+    chain_data: &AccountProviderView,
+) -> anyhow::Result<HashMap<Pubkey, BinArray>> {
+    let mut bin_arrays = HashMap::new();
+    for pk in bin_array_pks {
+        let Ok(account) = chain_data.account(pk) else {
+            break;
+        };
+        bin_arrays.insert(*pk, BinArray::try_deserialize(&mut account.account.data())?);
+    }
+    Ok(bin_arrays)
+}
@@ -0,0 +1,5 @@
+mod edge;
+mod meteora_dlmm;
+mod meteora_dlmm_ix_builder;
+
+pub use crate::meteora_dlmm::MeteoraDlmmDex;
@@ -0,0 +1,349 @@
+use crate::edge::{
+    bin_array_pks, load_bin_arrays, load_bitmap_extension, MeteoraDlmmEdge,
+    MeteoraDlmmEdgeIdentifier,
+};
+use crate::meteora_dlmm_ix_builder;
+use anchor_lang::{AccountDeserialize, Discriminator, Id};
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_hook::TransferHookAccount;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::AccountState;
+use anchor_spl::token_2022::Token2022;
+use async_trait::async_trait;
+use commons::quote::{quote_exact_in, quote_exact_out};
+use itertools::Itertools;
+use lb_clmm::state::bin_array_bitmap_extension::BinArrayBitmapExtension;
+use lb_clmm::state::lb_pair::LbPair;
+use lb_clmm::utils::pda::derive_bin_array_bitmap_extension;
+use router_feed_lib::router_rpc_client::{RouterRpcClient, RouterRpcClientTrait};
+use router_lib::dex::{
+    AccountProviderView, DexEdge, DexEdgeIdentifier, DexInterface, DexSubscriptionMode,
+    MixedDexSubscription, Quote, SwapInstruction,
+};
+use router_lib::utils;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::sysvar::SysvarId;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+const LB_PAIR_LEN: usize = 8 + std::mem::size_of::<LbPair>();
+
+pub struct MeteoraDlmmDex {
+    pub edges: HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>,
+}
+
+#[async_trait]
+impl DexInterface for MeteoraDlmmDex {
+    async fn initialize(
+        rpc: &mut RouterRpcClient,
+        _options: HashMap<String, String>,
+    ) -> anyhow::Result<Arc<dyn DexInterface>>
+    where
+        Self: Sized,
+    {
+        let pairs = fetch_lb_pairs(rpc).await?;
+
+        let reserves = pairs
+            .iter()
+            .flat_map(|x| [x.1.reserve_x, x.1.reserve_y])
+            .collect::<HashSet<_>>();
+        let reserves = rpc.get_multiple_accounts(&reserves).await?;
+        let banned_reserves = reserves
+            .iter()
+            .filter(|x| is_banned_reserve(&x.1.owner, x.1.data()))
+            .map(|x| x.0)
+            .collect::<HashSet<_>>();
+
+        let pairs = pairs
+            .into_iter()
+            .filter(|(_pair_pk, pair)| {
+                !banned_reserves.contains(&pair.reserve_x)
+                    && !banned_reserves.contains(&pair.reserve_y)
+            })
+            .collect_vec();
+
+        let extensions = pairs
+            .iter()
+            .map(|(pair_pk, _)| derive_bin_array_bitmap_extension(*pair_pk).0)
+            .collect::<HashSet<_>>();
+        let extensions = rpc
+            .get_multiple_accounts(&extensions)
+            .await?
+            .into_iter()
+            .filter_map(|(pk, account)| {
+                BinArrayBitmapExtension::try_deserialize(&mut account.data())
+                    .ok()
+                    .map(|extension| (pk, extension))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut edges_per_pk = HashMap::new();
+        for (pair_pk, pair) in &pairs {
+            let extension_pk = derive_bin_array_bitmap_extension(*pair_pk).0;
+            let extension = extensions.get(&extension_pk).copied();
+            for (key, entry) in pair_edges(pair_pk, pair, &extension) {
+                utils::insert_or_extend(&mut edges_per_pk, &key, &entry);
+            }
+        }
+
+        Ok(Arc::new(MeteoraDlmmDex {
+            edges: edges_per_pk,
+        }))
+    }
+
+    fn name(&self) -> String {
+        "MeteoraDlmm".to_string()
+    }
+
+    // Fees depend on the time elapsed since the last swap, so the clock is needed to quote
+    fn subscription_mode(&self) -> DexSubscriptionMode {
+        DexSubscriptionMode::Mixed(MixedDexSubscription {
+            accounts: [Clock::id()].into(),
+            programs: self.program_ids(),
+            token_accounts_for_owner: Default::default(),
+        })
+    }
+
+    fn program_ids(&self) -> HashSet<Pubkey> {
+        [lb_clmm::ID].into_iter().collect()
+    }
+
+    fn edges_per_pk(&self) -> HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>> {
+        self.edges.clone()
+    }
+
+    fn discover_edges(
+        &self,
+        pk: &Pubkey,
+        chain_data: &AccountProviderView,
+    ) -> anyhow::Result<HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>> {
+        let pair_account = chain_data.account(pk)?;
+        let data = pair_account.account.data();
+        if data.len() != LB_PAIR_LEN || !data.starts_with(&LbPair::DISCRIMINATOR) {
+            return Ok(HashMap::new());
+        }
+
+        let pair = LbPair::try_deserialize(&mut &data[..])?;
+
+        // reserves are not subscribed, only check them if we already know them
+        let banned_reserve = [pair.reserve_x, pair.reserve_y].iter().any(|reserve| {
+            chain_data
+                .account(reserve)
+                .is_ok_and(|x| is_banned_reserve(x.account.owner(), x.account.data()))
+        });
+        if banned_reserve {
+            return Ok(HashMap::new());
+        }
+
+        let extension = load_bitmap_extension(pk, chain_data)?;
+        let mut map = HashMap::new();
+        for (key, entry) in pair_edges(pk, &pair, &extension) {
+            utils::insert_or_extend(&mut map, &key, &entry);
+        }
+        Ok(map)
+    }
+
+    fn load(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        chain_data: &AccountProviderView,
+    ) -> anyhow::Result<Arc<dyn DexEdge>> {
+        let id = id
+            .as_any()
+            .downcast_ref::<MeteoraDlmmEdgeIdentifier>()
+            .unwrap();
+
+        let pair_account = chain_data.account(&id.pool)?;
+        let lb_pair = LbPair::try_deserialize(&mut pair_account.account.data())?;
+
+        let bitmap_extension = load_bitmap_extension(&id.pool, chain_data)?;
+        let pks = bin_array_pks(&id.pool, &lb_pair, &bitmap_extension, id.swap_for_y)?;
+        let bin_arrays = load_bin_arrays(&pks, chain_data)?;
+
+        Ok(Arc::new(MeteoraDlmmEdge {
+            lb_pair,
+            bitmap_extension,
+            bin_arrays,
+        }))
+    }
+
+    fn quote(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        edge: &Arc<dyn DexEdge>,
+        chain_data: &AccountProviderView,
+        in_amount: u64,
+    ) -> anyhow::Result<Quote> {
+        let id = id
+            .as_any()
+            .downcast_ref::<MeteoraDlmmEdgeIdentifier>()
+            .unwrap();
+        let edge = edge.as_any().downcast_ref::<MeteoraDlmmEdge>().unwrap();
+
+        let clock = chain_data.clock()?;
+        let quote = quote_exact_in(
+            id.pool,
+            &edge.lb_pair,
+            in_amount,
+            id.swap_for_y,
+            edge.bin_arrays.clone(),
+            edge.bitmap_extension.as_ref(),
+            clock.unix_timestamp as u64,
+            clock.slot,
+        )?;
+
+        Ok(Quote {
+            in_amount,
+            out_amount: quote.amount_out,
+            fee_amount: quote.fee,
+            fee_mint: id.mint_a,
+        })
+    }
+
+    fn build_swap_ix(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        chain_data: &AccountProviderView,
+        wallet_pk: &Pubkey,
+        in_amount: u64,
+        out_amount: u64,
+        max_slippage_bps: i32,
+    ) -> anyhow::Result<SwapInstruction> {
+        let id = id
+            .as_any()
+            .downcast_ref::<MeteoraDlmmEdgeIdentifier>()
+            .unwrap();
+        meteora_dlmm_ix_builder::build_swap_ix(
+            id,
+            chain_data,
+            wallet_pk,
+            in_amount,
+            out_amount,
+            max_slippage_bps,
+        )
+    }
+
+    fn supports_exact_out(&self, _id: &Arc<dyn DexEdgeIdentifier>) -> bool {
+        true
+    }
+
+    fn quote_exact_out(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        edge: &Arc<dyn DexEdge>,
+        chain_data: &AccountProviderView,
+        out_amount: u64,
+    ) -> anyhow::Result<Quote> {
+        let id = id
+            .as_any()
+            .downcast_ref::<MeteoraDlmmEdgeIdentifier>()
+            .unwrap();
+        let edge = edge.as_any().downcast_ref::<MeteoraDlmmEdge>().unwrap();
+
+        let clock = chain_data.clock()?;
+        let quote = quote_exact_out(
+            id.pool,
+            &edge.lb_pair,
+            out_amount,
+            id.swap_for_y,
+            edge.bin_arrays.clone(),
+            edge.bitmap_extension.as_ref(),
+            clock.unix_timestamp as u64,
+            clock.slot,
+        )?;
+
+        Ok(Quote {
+            in_amount: quote.amount_in,
+            out_amount,
+            fee_amount: quote.fee,
+            fee_mint: id.mint_a,
+        })
+    }
+}
+
+/// Frozen reserves can't be swapped against, and transfer hooks need extra accounts
+/// that the swap instruction does not pass. Token-2022 transfer fees are applied by the router.
+fn is_banned_reserve(owner: &Pubkey, data: &[u8]) -> bool {
+    if *owner != Token::id() && *owner != Token2022::id() {
+        return true;
+    }
+    // also reads spl-token accounts, which have no extensions
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(data).map_or(true, |account| {
+        account.base.state == AccountState::Frozen
+            || account.get_extension::<TransferHookAccount>().is_ok()
+    })
+}
+
+/// Accounts whose changes affect the pair edges: the pair, its bitmap extension
+/// and the bin arrays the next swaps will go through in both directions
+fn pair_edges(
+    pair_pk: &Pubkey,
+    pair: &LbPair,
+    extension: &Option<BinArrayBitmapExtension>,
+) -> Vec<(Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>)> {
+    let entry = vec![
+        Arc::new(MeteoraDlmmEdgeIdentifier {
+            pool: *pair_pk,
+            mint_a: pair.token_x_mint,
+            mint_b: pair.token_y_mint,
+            swap_for_y: true,
+        }) as Arc<dyn DexEdgeIdentifier>,
+        Arc::new(MeteoraDlmmEdgeIdentifier {
+            pool: *pair_pk,
+            mint_a: pair.token_y_mint,
+            mint_b: pair.token_x_mint,
+            swap_for_y: false,
+        }),
+    ];
+
+    let bin_arrays = [true, false]
+        .into_iter()
+        .flat_map(|swap_for_y| {
+            bin_array_pks(pair_pk, pair, extension, swap_for_y).unwrap_or_default()
+        })
+        .unique();
+
+    [*pair_pk, derive_bin_array_bitmap_extension(*pair_pk).0]
+        .into_iter()
+        .chain(bin_arrays)
+        .map(|key| (key, entry.clone()))
+        .collect()
+}
+
+async fn fetch_lb_pairs(rpc: &mut RouterRpcClient) -> anyhow::Result<Vec<(Pubkey, LbPair)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(LB_PAIR_LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, LbPair::DISCRIMINATOR.to_vec())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::finalized()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let snapshot = rpc
+        .get_program_accounts_with_config(&lb_clmm::ID, config)
+        .await?;
+
+    let result = snapshot
+        .iter()
+        .map(|account| {
+            let pair = LbPair::try_deserialize(&mut account.data.as_slice()).unwrap();
+            (account.pubkey, pair)
+        })
+        .collect_vec();
+
+    Ok(result)
+}
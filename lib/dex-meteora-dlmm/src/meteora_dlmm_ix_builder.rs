@@ -0,0 +1,86 @@
+use crate::edge::{bin_array_pks, load_bitmap_extension, MeteoraDlmmEdgeIdentifier};
+use anchor_lang::{AccountDeserialize, Id, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use lb_clmm::state::lb_pair::LbPair;
+use lb_clmm::utils::pda::{derive_bin_array_bitmap_extension, derive_event_authority_pda};
+use router_lib::dex::{AccountProviderView, SwapInstruction};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+
+pub fn build_swap_ix(
+    id: &MeteoraDlmmEdgeIdentifier,
+    chain_data: &AccountProviderView,
+    wallet_pk: &Pubkey,
+    in_amount: u64,
+    out_amount: u64,
+    max_slippage_bps: i32,
+) -> anyhow::Result<SwapInstruction> {
+    let pair_account = chain_data.account(&id.pool)?;
+    let lb_pair = LbPair::try_deserialize(&mut pair_account.account.data())?;
+
+    let amount_in = in_amount;
+    let min_amount_out =
+        ((out_amount as f64 * (10_000f64 - max_slippage_bps as f64)) / 10_000f64).floor() as u64;
+
+    let (user_token_in, user_token_out) = (
+        get_associated_token_address_with_program_id(
+            wallet_pk,
+            &id.mint_a,
+            &chain_data.token_program(&id.mint_a),
+        ),
+        get_associated_token_address_with_program_id(
+            wallet_pk,
+            &id.mint_b,
+            &chain_data.token_program(&id.mint_b),
+        ),
+    );
+
+    // Without extension the program id takes its place, the bin arrays follow in swap order
+    let bitmap_extension = load_bitmap_extension(&id.pool, chain_data)?;
+    let bin_arrays = bin_array_pks(&id.pool, &lb_pair, &bitmap_extension, id.swap_for_y)?
+        .into_iter()
+        .map(|pk| AccountMeta::new(pk, false));
+
+    let instruction = lb_clmm::instruction::Swap {
+        amount_in,
+        min_amount_out,
+    };
+
+    let accounts = lb_clmm::accounts::Swap {
+        lb_pair: id.pool,
+        bin_array_bitmap_extension: bitmap_extension
+            .map(|_| derive_bin_array_bitmap_extension(id.pool).0),
+        reserve_x: lb_pair.reserve_x,
+        reserve_y: lb_pair.reserve_y,
+        user_token_in,
+        user_token_out,
+        token_x_mint: lb_pair.token_x_mint,
+        token_y_mint: lb_pair.token_y_mint,
+        oracle: lb_pair.oracle,
+        host_fee_in: None,
+        user: *wallet_pk,
+        token_x_program: chain_data.token_program(&lb_pair.token_x_mint),
+        token_y_program: chain_data.token_program(&lb_pair.token_y_mint),
+        event_authority: derive_event_authority_pda().0,
+        program: lb_clmm::ID,
+    };
+
+    let result = SwapInstruction {
+        instruction: Instruction {
+            program_id: lb_clmm::ID,
+            accounts: accounts
+                .to_account_metas(None)
+                .into_iter()
+                .chain(bin_arrays)
+                .collect(),
+            data: instruction.data(),
+        },
+        out_pubkey: user_token_out,
+        out_mint: id.mint_b,
+        in_amount_offset: 8,
+        cu_estimate: Some(100_000),
+    };
+
+    Ok(result)
+}
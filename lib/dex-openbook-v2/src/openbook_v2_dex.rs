@@ -1,7 +1,6 @@
 use crate::edge::{load_anchor, OpenbookV2Edge, OpenbookV2EdgeIdentifier};
 use crate::openbook_v2_ix_builder;
 use anchor_lang::Discriminator;
-use anyhow::Context;
 use bytemuck::Zeroable;
 use itertools::Itertools;
 use openbook_v2::state::Market;
@@ -168,8 +167,7 @@ impl DexInterface for OpenbookV2Dex {
 
         use openbook_v2::state as o2s;
 
-        #[allow(clippy::clone_on_copy)]
-        let mut market = edge.market.clone();
+        let market = &edge.market;
 
         if edge.bids.is_none() || edge.asks.is_none() {
             warn!("Cant quote {} because missing bid/ask", market.name());
@@ -181,22 +179,6 @@ impl DexInterface for OpenbookV2Dex {
             });
         }
 
-        let bids = RefCell::new(edge.bids.unwrap());
-        let asks = RefCell::new(edge.asks.unwrap());
-        let mut event_heap = Box::new({
-            let mut heap = o2s::EventHeap::zeroed();
-            heap.init();
-            heap
-        });
-
-        let mut orderbook = o2s::Orderbook {
-            bids: bids.borrow_mut(),
-            asks: asks.borrow_mut(),
-        };
-
-        let clock = chain_data.account(&Clock::id()).context("read clock")?;
-        let now_ts = clock.account.deserialize_data::<Clock>()?.unix_timestamp as u64;
-
         let input_native;
         let order = if id.is_bid {
             let input_lots = in_amount as i64 / market.quote_lot_size;
@@ -233,18 +215,7 @@ impl DexInterface for OpenbookV2Dex {
             });
         }
 
-        let result = orderbook.new_order(
-            &order,
-            &mut market,
-            &id.market,
-            &mut event_heap,
-            None,
-            None,
-            &Pubkey::default(),
-            now_ts,
-            10,
-            &[],
-        )?;
+        let result = take_order(id, edge, chain_data, &order)?;
 
         let out_amount = if id.is_bid {
             result.total_base_taken_native
@@ -284,123 +255,154 @@ impl DexInterface for OpenbookV2Dex {
     }
 
     fn supports_exact_out(&self, _id: &Arc<dyn DexEdgeIdentifier>) -> bool {
-        false
+        true
     }
 
     fn quote_exact_out(
         &self,
-        _id: &Arc<dyn DexEdgeIdentifier>,
-        _edge: &Arc<dyn DexEdge>,
-        _chain_data: &AccountProviderView,
-        _out_amount: u64,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        edge: &Arc<dyn DexEdge>,
+        chain_data: &AccountProviderView,
+        out_amount: u64,
     ) -> anyhow::Result<Quote> {
+        let id = id
+            .as_any()
+            .downcast_ref::<OpenbookV2EdgeIdentifier>()
+            .unwrap();
+        let edge = edge.as_any().downcast_ref::<OpenbookV2Edge>().unwrap();
+
+        use openbook_v2::state as o2s;
+
+        let market = &edge.market;
+
+        if edge.bids.is_none() || edge.asks.is_none() {
+            warn!("Cant quote {} because missing bid/ask", market.name());
+            return Ok(Quote {
+                in_amount: u64::MAX,
+                out_amount: 0,
+                fee_amount: 0,
+                fee_mint: market.quote_mint,
+            });
+        }
+
+        // Walk the book until the output is reached: a bid is limited by the base lots
+        // to buy, an ask by the quote lots to receive before taker fees are deducted
+        let output_lots;
+        let order = if id.is_bid {
+            output_lots = out_amount.div_ceil(market.base_lot_size as u64) as i64;
+            o2s::Order {
+                side: o2s::Side::Bid,
+                max_base_lots: output_lots,
+                max_quote_lots_including_fees: market.max_quote_lots(),
+                client_order_id: 0,
+                time_in_force: 0,
+                params: o2s::OrderParams::Market,
+                self_trade_behavior: o2s::SelfTradeBehavior::DecrementTake,
+            }
+        } else {
+            let out_amount_before_fees = (out_amount as u128 * 1_000_000)
+                .div_ceil(1_000_000 - market.taker_fee as u128)
+                as u64;
+            output_lots = out_amount_before_fees.div_ceil(market.quote_lot_size as u64) as i64;
+            o2s::Order {
+                side: o2s::Side::Ask,
+                max_base_lots: market.max_base_lots(),
+                max_quote_lots_including_fees: output_lots,
+                client_order_id: 0,
+                time_in_force: 0,
+                params: o2s::OrderParams::Market,
+                self_trade_behavior: o2s::SelfTradeBehavior::DecrementTake,
+            }
+        };
+
+        if output_lots == 0 {
+            return Ok(Quote {
+                in_amount: 0,
+                out_amount: 0,
+                fee_amount: 0,
+                fee_mint: market.quote_mint,
+            });
+        }
+
+        let result = take_order(id, edge, chain_data, &order)?;
+
+        // The swap instruction takes the input in lots, round it up so that
+        // it is not truncated below what the book walk used
+        let (in_amount, filled_out_amount) = if id.is_bid {
+            let in_native = result.total_quote_taken_native + result.taker_fees;
+            (
+                in_native.div_ceil(market.quote_lot_size as u64) * market.quote_lot_size as u64,
+                result.total_base_taken_native,
+            )
+        } else {
+            (
+                result.total_base_taken_native,
+                result.total_quote_taken_native - result.taker_fees,
+            )
+        };
+
+        // The book is too thin to deliver the requested output
+        if filled_out_amount < out_amount {
+            return Ok(Quote {
+                in_amount: u64::MAX,
+                out_amount: 0,
+                fee_amount: 0,
+                fee_mint: market.quote_mint,
+            });
+        }
+
         Ok(Quote {
-            in_amount: u64::MAX,
-            out_amount: 0,
-            fee_amount: 0,
-            fee_mint: Pubkey::default(),
+            fee_amount: result.taker_fees,
+            fee_mint: market.quote_mint,
+            in_amount,
+            out_amount: filled_out_amount,
         })
-        // let id = id
-        //     .as_any()
-        //     .downcast_ref::<OpenbookV2EdgeIdentifier>()
-        //     .unwrap();
-        // let edge = edge.as_any().downcast_ref::<OpenbookV2Edge>().unwrap();
-
-        // use openbook_v2::state as o2s;
-
-        // #[allow(clippy::clone_on_copy)]
-        // let mut market = edge.market.clone();
-
-        // if edge.bids.is_none() || edge.asks.is_none() {
-        //     warn!("Cant quote {} because missing bid/ask", market.name());
-        //     return Ok(Quote {
-        //         in_amount: 0,
-        //         out_amount: 0,
-        //         fee_amount: 0,
-        //         fee_mint: market.quote_mint,
-        //     });
-        // }
-
-        // let bids = RefCell::new(edge.bids.unwrap());
-        // let asks = RefCell::new(edge.asks.unwrap());
-        // let mut event_heap = Box::new({
-        //     let mut heap = o2s::EventHeap::zeroed();
-        //     heap.init();
-        //     heap
-        // });
-
-        // let mut orderbook = o2s::Orderbook {
-        //     bids: bids.borrow_mut(),
-        //     asks: asks.borrow_mut(),
-        // };
-
-        // // TODO: maybe a time service that is primarily based on on-chain time? (can desync!)
-        // let now_ts = millis_since_epoch() / 1000;
-
-        // let output_native;
-        // let order = if id.is_bid {
-        //     let output_lots = out_amount as i64 / market.base_lot_size;
-        //     output_native = output_lots * market.base_lot_size;
-        //     o2s::Order {
-        //         side: o2s::Side::Bid,
-        //         max_base_lots: output_native,
-        //         max_quote_lots_including_fees: market.max_quote_lots(),
-        //         client_order_id: 0,
-        //         time_in_force: 0,
-        //         params: o2s::OrderParams::Market,
-        //         self_trade_behavior: o2s::SelfTradeBehavior::DecrementTake,
-        //     }
-        // } else {
-        //     let output_lots = out_amount as i64 / market.quote_lot_size;
-        //     output_native = output_lots * market.quote_lot_size;
-        //     o2s::Order {
-        //         side: o2s::Side::Ask,
-        //         max_base_lots: market.max_base_lots(),
-        //         max_quote_lots_including_fees: market.max_quote_lots(),
-        //         client_order_id: 0,
-        //         time_in_force: 0,
-        //         params: o2s::OrderParams::Market,
-        //         self_trade_behavior: o2s::SelfTradeBehavior::DecrementTake,
-        //     }
-        // };
-
-        // if output_native == 0 {
-        //     return Ok(Quote {
-        //         in_amount: 0,
-        //         out_amount: 0,
-        //         fee_amount: 0,
-        //         fee_mint: market.quote_mint,
-        //     });
-        // }
-
-        // let result = orderbook.new_order(
-        //     &order,
-        //     &mut market,
-        //     &id.market,
-        //     &mut event_heap,
-        //     None,
-        //     None,
-        //     &Pubkey::default(),
-        //     now_ts,
-        //     10,
-        //     &[],
-        // )?;
-
-        // let in_amount = if id.is_bid {
-        //     result.total_quote_taken_native
-        // } else {
-        //     result.total_base_taken_native
-        // };
-
-        // Ok(Quote {
-        //     fee_amount: result.taker_fees,
-        //     fee_mint: market.quote_mint,
-        //     in_amount,
-        //     out_amount,
-        // })
     }
 }
 
+/// Matches a taker order against a copy of the book, nothing is written back to the edge
+fn take_order(
+    id: &OpenbookV2EdgeIdentifier,
+    edge: &OpenbookV2Edge,
+    chain_data: &AccountProviderView,
+    order: &openbook_v2::state::Order,
+) -> anyhow::Result<openbook_v2::state::OrderWithAmounts> {
+    use openbook_v2::state as o2s;
+
+    #[allow(clippy::clone_on_copy)]
+    let mut market = edge.market.clone();
+
+    let bids = RefCell::new(edge.bids.unwrap());
+    let asks = RefCell::new(edge.asks.unwrap());
+    let mut event_heap = Box::new({
+        let mut heap = o2s::EventHeap::zeroed();
+        heap.init();
+        heap
+    });
+
+    let mut orderbook = o2s::Orderbook {
+        bids: bids.borrow_mut(),
+        asks: asks.borrow_mut(),
+    };
+
+    let now_ts = chain_data.clock()?.unix_timestamp as u64;
+
+    let result = orderbook.new_order(
+        order,
+        &mut market,
+        &id.market,
+        &mut event_heap,
+        None,
+        None,
+        &Pubkey::default(),
+        now_ts,
+        10,
+        &[],
+    )?;
+
+    Ok(result)
+}
+
 async fn fetch_openbook_v2_account(
     rpc: &mut RouterRpcClient,
     program_id: Pubkey,
@@ -1,14 +1,12 @@
 use super::edge::load_anchor;
 use crate::edge::OpenbookV2EdgeIdentifier;
 use anchor_lang::Id;
-use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
 use itertools::Itertools;
 use router_lib::dex::{AccountProviderView, SwapInstruction};
 use solana_program::instruction::Instruction;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::clock::Clock;
 use solana_sdk::instruction::AccountMeta;
-use solana_sdk::sysvar::SysvarId;
 
 pub const INCLUDED_MAKERS_COUNT: usize = 2;
 
@@ -56,8 +54,16 @@ pub fn build_swap_ix(
         signer: *wallet_pk,
         system_program: anchor_lang::system_program::System::id(),
         token_program: anchor_spl::token::ID,
-        user_base_account: get_associated_token_address(wallet_pk, &market.base_mint),
-        user_quote_account: get_associated_token_address(wallet_pk, &market.quote_mint),
+        user_base_account: get_associated_token_address_with_program_id(
+            wallet_pk,
+            &market.base_mint,
+            &chain_data.token_program(&market.base_mint),
+        ),
+        user_quote_account: get_associated_token_address_with_program_id(
+            wallet_pk,
+            &market.quote_mint,
+            &chain_data.token_program(&market.quote_mint),
+        ),
     };
 
     let (out_pubkey, out_mint) = if id.is_bid {
@@ -66,8 +72,7 @@ pub fn build_swap_ix(
         (accounts.user_quote_account, market.quote_mint)
     };
 
-    let clock = chain_data.account(&Clock::id())?;
-    let now_ts = clock.account.deserialize_data::<Clock>()?.unix_timestamp as u64;
+    let now_ts = chain_data.clock()?.unix_timestamp as u64;
 
     let mut account_metas = anchor_lang::ToAccountMetas::to_account_metas(&accounts, None);
     let makers = other_side
@@ -273,6 +273,10 @@ pub fn simulate_swap_with_tick_array(
         )
     };
 
+    // rewards can not be updated backward, in case the clock lags behind the pool account
+    let timestamp =
+        (chain_data.clock()?.unix_timestamp as u64).max(whirlpool.reward_last_updated_timestamp);
+
     let swap_update = swap(
         whirlpool,
         &mut swap_tick_sequence,
@@ -280,7 +284,7 @@ pub fn simulate_swap_with_tick_array(
         sqrt_price_limit,
         amount_specificed_is_input,
         a_to_b,
-        whirlpool.reward_last_updated_timestamp,
+        timestamp,
     );
 
     swap_update.context("whirlpool swap")
@@ -3,7 +3,7 @@ use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 
-use anchor_lang::Id;
+use anchor_lang::{Discriminator, Id};
 use anchor_spl::token::spl_token;
 use anchor_spl::token::spl_token::state::{Account, AccountState};
 use anchor_spl::token_2022::Token2022;
@@ -86,6 +86,58 @@ impl DexInterface for OrcaDex {
         self.edges.clone()
     }
 
+    fn discover_edges(
+        &self,
+        pk: &Pubkey,
+        chain_data: &AccountProviderView,
+    ) -> anyhow::Result<HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>> {
+        let account = chain_data.account(pk)?;
+        let data = account.account.data();
+        if data.len() != Whirlpool::LEN || !data.starts_with(&Whirlpool::DISCRIMINATOR) {
+            return Ok(HashMap::new());
+        }
+
+        let wp = load_whirpool(chain_data, pk)?;
+
+        // vaults are not subscribed, only check them if we already know them
+        let banned_vault = [wp.token_vault_a, wp.token_vault_b].iter().any(|vault| {
+            chain_data.account(vault).is_ok_and(|x| {
+                x.account.owner() == &Token2022::id()
+                    || spl_token::state::Account::unpack(x.account.data())
+                        .map_or(true, |x| x.state == AccountState::Frozen)
+            })
+        });
+        if banned_vault {
+            return Ok(HashMap::new());
+        }
+
+        let entry = vec![
+            Arc::new(OrcaEdgeIdentifier {
+                pool: *pk,
+                program: self.program_id,
+                program_name: self.program_name.clone(),
+                input_mint: wp.token_mint_a,
+                output_mint: wp.token_mint_b,
+                is_a_to_b: true,
+            }) as Arc<dyn DexEdgeIdentifier>,
+            Arc::new(OrcaEdgeIdentifier {
+                pool: *pk,
+                program: self.program_id,
+                program_name: self.program_name.clone(),
+                input_mint: wp.token_mint_b,
+                output_mint: wp.token_mint_a,
+                is_a_to_b: false,
+            }),
+        ];
+
+        let mut map = HashMap::new();
+        map.insert(*pk, entry.clone());
+        for tick_array in whirlpool_tick_array_pks(&wp, pk, &self.program_id) {
+            map.insert(tick_array, entry.clone());
+        }
+        Ok(map)
+    }
+
     fn load(
         &self,
         id: &Arc<dyn DexEdgeIdentifier>,
@@ -2,7 +2,7 @@ use super::orca::*;
 use crate::orca;
 use crate::orca_dex::OrcaEdgeIdentifier;
 use anchor_lang::Id;
-use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
 use anchor_spl::token::Token;
 use router_lib::dex::{AccountProviderView, SwapInstruction};
 use sha2::{Digest, Sha256};
@@ -121,6 +121,17 @@ pub fn build_swap_ix(
 
     assert_eq!(data.len(), exptected_size);
 
+    let token_account_a = get_associated_token_address_with_program_id(
+        wallet_pk,
+        &whirlpool.token_mint_a,
+        &account_fetcher.token_program(&whirlpool.token_mint_a),
+    );
+    let token_account_b = get_associated_token_address_with_program_id(
+        wallet_pk,
+        &whirlpool.token_mint_b,
+        &account_fetcher.token_program(&whirlpool.token_mint_b),
+    );
+
     let swap_ix = Instruction {
         program_id: id.program,
 
@@ -129,15 +140,9 @@ pub fn build_swap_ix(
             AccountMeta::new_readonly(Token::id(), false),
             AccountMeta::new(*wallet_pk, true),
             AccountMeta::new(id.pool, false),
-            AccountMeta::new(
-                get_associated_token_address(wallet_pk, &whirlpool.token_mint_a),
-                false,
-            ),
+            AccountMeta::new(token_account_a, false),
             AccountMeta::new(whirlpool.token_vault_a, false),
-            AccountMeta::new(
-                get_associated_token_address(wallet_pk, &whirlpool.token_mint_b),
-                false,
-            ),
+            AccountMeta::new(token_account_b, false),
             AccountMeta::new(whirlpool.token_vault_b, false),
             AccountMeta::new(tick_array_pks.0, false),
             AccountMeta::new(tick_array_pks.1, false),
@@ -153,9 +158,9 @@ pub fn build_swap_ix(
     Ok(SwapInstruction {
         instruction: swap_ix,
         out_pubkey: if id.is_a_to_b {
-            get_associated_token_address(wallet_pk, &whirlpool.token_mint_b)
+            token_account_b
         } else {
-            get_associated_token_address(wallet_pk, &whirlpool.token_mint_a)
+            token_account_a
         },
         out_mint: if id.is_a_to_b {
             whirlpool.token_mint_b
@@ -0,0 +1,108 @@
+use phoenix::program::load_with_dispatch;
+use phoenix::program::status::MarketStatus;
+use phoenix::program::MarketHeader;
+use phoenix::state::markets::LadderOrder;
+use router_lib::dex::{AccountProviderView, DexEdge, DexEdgeIdentifier};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+use std::any::Any;
+
+pub const MARKET_HEADER_LEN: usize = std::mem::size_of::<MarketHeader>();
+
+pub struct PhoenixEdgeIdentifier {
+    pub market: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// quote token is the input
+    pub is_bid: bool,
+}
+
+impl DexEdgeIdentifier for PhoenixEdgeIdentifier {
+    fn key(&self) -> Pubkey {
+        self.market
+    }
+
+    fn desc(&self) -> String {
+        format!("Phoenix_{}", self.market)
+    }
+
+    fn input_mint(&self) -> Pubkey {
+        self.mint_a
+    }
+
+    fn output_mint(&self) -> Pubkey {
+        self.mint_b
+    }
+
+    fn accounts_needed(&self) -> usize {
+        1 // phoenix program
+            + 2 // log authority, market
+            + 2 // base vault, quote vault
+            + 1 // out token account
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct PhoenixEdge {
+    pub header: MarketHeader,
+    pub base_lots_per_base_unit: u64,
+    pub tick_size_in_quote_lots_per_base_unit: u64,
+    pub taker_fee_bps: u64,
+    /// resting bids, best price first
+    pub bids: Vec<LadderOrder>,
+    /// resting asks, best price first
+    pub asks: Vec<LadderOrder>,
+}
+
+impl DexEdge for PhoenixEdge {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Cheap check on the header only, the market itself is not deserialized
+pub fn parse_header(data: &[u8]) -> Option<MarketHeader> {
+    if data.len() <= MARKET_HEADER_LEN {
+        return None;
+    }
+    let header = *bytemuck::try_from_bytes::<MarketHeader>(&data[..MARKET_HEADER_LEN]).ok()?;
+    if header.status != MarketStatus::Active as u64 || header.market_size_params.bids_size == 0 {
+        return None;
+    }
+    Some(header)
+}
+
+pub fn load_market(
+    market_pk: &Pubkey,
+    chain_data: &AccountProviderView,
+) -> anyhow::Result<PhoenixEdge> {
+    let account = chain_data.account(market_pk)?;
+    let data = account.account.data();
+    let Some(header) = parse_header(data) else {
+        anyhow::bail!("Phoenix market {} is not active", market_pk);
+    };
+
+    let market = load_with_dispatch(&header.market_size_params, &data[MARKET_HEADER_LEN..])
+        .map_err(|e| anyhow::format_err!("Failed to load phoenix market {market_pk}: {e:?}"))?
+        .inner;
+
+    // Expired orders are skipped when matching, leave them out of the ladder
+    let clock = chain_data.clock()?;
+    let ladder = market.get_ladder_with_expiration(
+        u64::MAX,
+        Some(clock.slot),
+        Some(clock.unix_timestamp as u64),
+    );
+
+    Ok(PhoenixEdge {
+        header,
+        base_lots_per_base_unit: market.get_base_lots_per_base_unit().as_u64(),
+        tick_size_in_quote_lots_per_base_unit: market.get_tick_size().as_u64(),
+        taker_fee_bps: market.get_taker_fee_bps(),
+        bids: ladder.bids,
+        asks: ladder.asks,
+    })
+}
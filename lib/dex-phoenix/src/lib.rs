@@ -0,0 +1,6 @@
+mod edge;
+mod phoenix_dex;
+mod phoenix_ix_builder;
+
+pub use edge::PhoenixEdge;
+pub use phoenix_dex::PhoenixDex;
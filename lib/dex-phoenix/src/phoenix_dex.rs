@@ -0,0 +1,258 @@
+use crate::edge::{load_market, parse_header, PhoenixEdge, PhoenixEdgeIdentifier};
+use crate::phoenix_ix_builder;
+use itertools::Itertools;
+use phoenix::program::MarketHeader;
+use router_feed_lib::router_rpc_client::{RouterRpcClient, RouterRpcClientTrait};
+use router_lib::dex::{
+    AccountProviderView, DexEdge, DexEdgeIdentifier, DexInterface, DexSubscriptionMode,
+    MixedDexSubscription, Quote, SwapInstruction,
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::sysvar::SysvarId;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::info;
+
+pub struct PhoenixDex {
+    pub edges: HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>,
+}
+
+#[async_trait::async_trait]
+impl DexInterface for PhoenixDex {
+    async fn initialize(
+        rpc: &mut RouterRpcClient,
+        _options: HashMap<String, String>,
+    ) -> anyhow::Result<Arc<dyn DexInterface>>
+    where
+        Self: Sized,
+    {
+        let markets = fetch_phoenix_markets(rpc).await?;
+
+        info!("phoenix markets #{}", markets.len());
+
+        // Everything needed to quote is in the market account
+        let edges_per_pk = markets
+            .iter()
+            .map(|(market_pk, header)| (*market_pk, market_edges(market_pk, header)))
+            .collect();
+
+        Ok(Arc::new(PhoenixDex {
+            edges: edges_per_pk,
+        }))
+    }
+
+    fn name(&self) -> String {
+        "Phoenix".to_string()
+    }
+
+    // Orders can expire, so the clock is needed to build the ladder
+    fn subscription_mode(&self) -> DexSubscriptionMode {
+        DexSubscriptionMode::Mixed(MixedDexSubscription {
+            accounts: [Clock::id()].into(),
+            programs: self.program_ids(),
+            token_accounts_for_owner: Default::default(),
+        })
+    }
+
+    fn program_ids(&self) -> HashSet<Pubkey> {
+        [phoenix::id()].into_iter().collect()
+    }
+
+    fn edges_per_pk(&self) -> HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>> {
+        self.edges.clone()
+    }
+
+    fn discover_edges(
+        &self,
+        pk: &Pubkey,
+        chain_data: &AccountProviderView,
+    ) -> anyhow::Result<HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>> {
+        let account = chain_data.account(pk)?;
+        let Some(header) = parse_header(account.account.data()) else {
+            return Ok(HashMap::new());
+        };
+        Ok(HashMap::from([(*pk, market_edges(pk, &header))]))
+    }
+
+    fn load(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        chain_data: &AccountProviderView,
+    ) -> anyhow::Result<Arc<dyn DexEdge>> {
+        let id = id.as_any().downcast_ref::<PhoenixEdgeIdentifier>().unwrap();
+        Ok(Arc::new(load_market(&id.market, chain_data)?))
+    }
+
+    fn quote(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        edge: &Arc<dyn DexEdge>,
+        _chain_data: &AccountProviderView,
+        in_amount: u64,
+    ) -> anyhow::Result<Quote> {
+        let id = id.as_any().downcast_ref::<PhoenixEdgeIdentifier>().unwrap();
+        let edge = edge.as_any().downcast_ref::<PhoenixEdge>().unwrap();
+
+        let base_lot_size = edge.header.base_lot_size.as_u64();
+        let quote_lot_size = edge.header.quote_lot_size.as_u64();
+
+        // Amounts are truncated to lots the same way execute_phoenix_swap() does
+        let (out_amount, fee_lots) = if id.is_bid {
+            let (base_lots, fee_lots) = match_asks(edge, in_amount / quote_lot_size);
+            (base_lots * base_lot_size, fee_lots)
+        } else {
+            let (quote_lots, fee_lots) = match_bids(edge, in_amount / base_lot_size);
+            (quote_lots * quote_lot_size, fee_lots)
+        };
+
+        Ok(Quote {
+            in_amount,
+            out_amount,
+            fee_amount: fee_lots * quote_lot_size,
+            fee_mint: edge.header.quote_params.mint_key,
+        })
+    }
+
+    fn build_swap_ix(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        chain_data: &AccountProviderView,
+        wallet_pk: &Pubkey,
+        in_amount: u64,
+        out_amount: u64,
+        max_slippage_bps: i32,
+    ) -> anyhow::Result<SwapInstruction> {
+        let id = id.as_any().downcast_ref::<PhoenixEdgeIdentifier>().unwrap();
+        phoenix_ix_builder::build_swap_ix(
+            id,
+            chain_data,
+            wallet_pk,
+            in_amount,
+            out_amount,
+            max_slippage_bps,
+        )
+    }
+
+    fn supports_exact_out(&self, _id: &Arc<dyn DexEdgeIdentifier>) -> bool {
+        false
+    }
+
+    fn quote_exact_out(
+        &self,
+        _id: &Arc<dyn DexEdgeIdentifier>,
+        _edge: &Arc<dyn DexEdge>,
+        _chain_data: &AccountProviderView,
+        _out_amount: u64,
+    ) -> anyhow::Result<Quote> {
+        anyhow::bail!("exact out is not supported for phoenix")
+    }
+}
+
+/// Walks the asks with a quote lot budget, the taker fee is charged on top of
+/// the matched quote lots, so the budget is reduced by the fee upfront.
+/// Returns the base lots bought and the fee in quote lots.
+///
+/// Quote lots are scaled by base_lots_per_base_unit while matching, as phoenix does,
+/// to avoid rounding on every level.
+fn match_asks(edge: &PhoenixEdge, quote_lots: u64) -> (u64, u64) {
+    let fee_bps = edge.taker_fee_bps as u128;
+    let base_lots_per_base_unit = edge.base_lots_per_base_unit as u128;
+    let tick_size = edge.tick_size_in_quote_lots_per_base_unit as u128;
+
+    let budget = quote_lots as u128 * 10_000 / (10_000 + fee_bps);
+    let budget_adjusted = budget * base_lots_per_base_unit;
+
+    let mut remaining_adjusted = budget_adjusted;
+    let mut base_lots = 0u128;
+    for order in &edge.asks {
+        let price_adjusted = order.price_in_ticks as u128 * tick_size;
+        if price_adjusted == 0 {
+            continue;
+        }
+        let filled = (order.size_in_base_lots as u128).min(remaining_adjusted / price_adjusted);
+        if filled == 0 {
+            break;
+        }
+        base_lots += filled;
+        remaining_adjusted -= filled * price_adjusted;
+    }
+
+    let matched_quote_lots =
+        (budget_adjusted - remaining_adjusted).div_ceil(base_lots_per_base_unit);
+    let fee_lots = (matched_quote_lots * fee_bps).div_ceil(10_000);
+
+    (base_lots as u64, fee_lots as u64)
+}
+
+/// Walks the bids with a base lot budget.
+/// Returns the quote lots received net of the taker fee, and that fee in quote lots.
+fn match_bids(edge: &PhoenixEdge, base_lots: u64) -> (u64, u64) {
+    let fee_bps = edge.taker_fee_bps as u128;
+    let base_lots_per_base_unit = edge.base_lots_per_base_unit as u128;
+    let tick_size = edge.tick_size_in_quote_lots_per_base_unit as u128;
+
+    let mut remaining = base_lots as u128;
+    let mut matched_adjusted = 0u128;
+    for order in &edge.bids {
+        if remaining == 0 {
+            break;
+        }
+        let filled = (order.size_in_base_lots as u128).min(remaining);
+        matched_adjusted += filled * order.price_in_ticks as u128 * tick_size;
+        remaining -= filled;
+    }
+
+    let matched_quote_lots = matched_adjusted / base_lots_per_base_unit;
+    let fee_lots = (matched_quote_lots * fee_bps).div_ceil(10_000);
+
+    ((matched_quote_lots - fee_lots) as u64, fee_lots as u64)
+}
+
+fn market_edges(market_pk: &Pubkey, header: &MarketHeader) -> Vec<Arc<dyn DexEdgeIdentifier>> {
+    vec![
+        Arc::new(PhoenixEdgeIdentifier {
+            market: *market_pk,
+            mint_a: header.base_params.mint_key,
+            mint_b: header.quote_params.mint_key,
+            is_bid: false,
+        }),
+        Arc::new(PhoenixEdgeIdentifier {
+            market: *market_pk,
+            mint_a: header.quote_params.mint_key,
+            mint_b: header.base_params.mint_key,
+            is_bid: true,
+        }),
+    ]
+}
+
+async fn fetch_phoenix_markets(
+    rpc: &mut RouterRpcClient,
+) -> anyhow::Result<Vec<(Pubkey, MarketHeader)>> {
+    // Markets have different sizes depending on their book size, seats are filtered out
+    // by parse_header() as they are smaller than the market header
+    let config = RpcProgramAccountsConfig {
+        filters: None,
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::finalized()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let snapshot = rpc
+        .get_program_accounts_with_config(&phoenix::id(), config)
+        .await?;
+
+    let result = snapshot
+        .iter()
+        .filter_map(|account| parse_header(&account.data).map(|header| (account.pubkey, header)))
+        .collect_vec();
+
+    Ok(result)
+}
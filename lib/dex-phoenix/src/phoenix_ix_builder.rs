@@ -0,0 +1,75 @@
+use crate::edge::{parse_header, PhoenixEdgeIdentifier};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use router_lib::dex::{AccountProviderView, SwapInstruction};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+
+pub fn build_swap_ix(
+    id: &PhoenixEdgeIdentifier,
+    chain_data: &AccountProviderView,
+    wallet_pk: &Pubkey,
+    in_amount: u64,
+    _out_amount: u64,
+    _max_slippage_bps: i32,
+) -> anyhow::Result<SwapInstruction> {
+    let market_account = chain_data.account(&id.market)?;
+    let Some(header) = parse_header(market_account.account.data()) else {
+        anyhow::bail!("Phoenix market {} is not active", id.market);
+    };
+
+    // This does not call phoenix's Swap directly: that requires input amounts in lots
+    // and the autobahn-executor program can only dynamically adjust native amounts. So instead, we call
+    // a wrapper for Swap on the autobahn-executor program, see execute_phoenix_swap().
+    let mut data = Vec::with_capacity(10);
+    data.push(9u8); // PhoenixSwap discriminator
+    data.extend_from_slice(&in_amount.to_le_bytes());
+    data.push(if id.is_bid { 1 } else { 0 });
+
+    let (base_mint, quote_mint) = (header.base_params.mint_key, header.quote_params.mint_key);
+    let base_account = get_associated_token_address_with_program_id(
+        wallet_pk,
+        &base_mint,
+        &chain_data.token_program(&base_mint),
+    );
+    let quote_account = get_associated_token_address_with_program_id(
+        wallet_pk,
+        &quote_mint,
+        &chain_data.token_program(&quote_mint),
+    );
+
+    // Accounts passed to the autobahn-executor are phoenix's Swap accounts, prefixed
+    // by the phoenix program to call
+    let account_metas = vec![
+        AccountMeta::new_readonly(phoenix::id(), false),
+        AccountMeta::new_readonly(phoenix::id(), false),
+        AccountMeta::new_readonly(phoenix::phoenix_log_authority::id(), false),
+        AccountMeta::new(id.market, false),
+        AccountMeta::new_readonly(*wallet_pk, true),
+        AccountMeta::new(base_account, false),
+        AccountMeta::new(quote_account, false),
+        AccountMeta::new(header.base_params.vault_key, false),
+        AccountMeta::new(header.quote_params.vault_key, false),
+        AccountMeta::new_readonly(anchor_spl::token::ID, false),
+    ];
+
+    let out_pubkey = if id.is_bid {
+        base_account
+    } else {
+        quote_account
+    };
+
+    let instruction = Instruction {
+        program_id: router_lib::autobahn_executor::id(),
+        accounts: account_metas,
+        data,
+    };
+
+    Ok(SwapInstruction {
+        instruction,
+        out_pubkey,
+        out_mint: id.mint_b,
+        in_amount_offset: 1,
+        cu_estimate: Some(70_000),
+    })
+}
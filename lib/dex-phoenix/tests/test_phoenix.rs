@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::env;
+
+use dex_phoenix::PhoenixEdge;
+use router_feed_lib::utils::tracing_subscriber_init;
+use router_lib::dex::DexInterface;
+use router_lib::test_tools::{generate_dex_rpc_dump, rpc};
+use solana_program_test::tokio;
+
+#[tokio::test]
+async fn test_dump_input_data_phoenix() -> anyhow::Result<()> {
+    tracing_subscriber_init();
+    let options = HashMap::from([]);
+    if router_test_lib::config_should_dump_mainnet_data() {
+        phoenix_step_1(&options).await?;
+    }
+
+    phoenix_step_2(&options).await?;
+
+    Ok(())
+}
+
+async fn phoenix_step_1(options: &HashMap<String, String>) -> anyhow::Result<()> {
+    let rpc_url: String = env::var("RPC_HTTP_URL")?;
+
+    let (mut rpc_client, chain_data) = rpc::rpc_dumper_client(rpc_url, "phoenix_dump.lz4");
+
+    let dex = dex_phoenix::PhoenixDex::initialize(&mut rpc_client, options.clone()).await?;
+
+    generate_dex_rpc_dump::run_dump_mainnet_data_with_custom_amount(
+        dex,
+        rpc_client,
+        chain_data,
+        Box::new(|edge| {
+            let edge = edge.as_any().downcast_ref::<PhoenixEdge>().unwrap();
+            5 * edge
+                .header
+                .quote_lot_size
+                .as_u64()
+                .max(edge.header.base_lot_size.as_u64())
+        }),
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn phoenix_step_2(options: &HashMap<String, String>) -> anyhow::Result<()> {
+    // Replay
+    let (mut rpc_client, chain_data) = rpc::rpc_replayer_client("phoenix_dump.lz4");
+
+    let dex = dex_phoenix::PhoenixDex::initialize(&mut rpc_client, options.clone()).await?;
+
+    generate_dex_rpc_dump::run_dump_swap_ix_with_custom_amount(
+        "phoenix_swap.lz4",
+        dex,
+        chain_data,
+        Box::new(|edge| {
+            let edge = edge.as_any().downcast_ref::<PhoenixEdge>().unwrap();
+            5 * edge
+                .header
+                .quote_lot_size
+                .as_u64()
+                .max(edge.header.base_lot_size.as_u64())
+        }),
+    )
+    .await?;
+
+    Ok(())
+}
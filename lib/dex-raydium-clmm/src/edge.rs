@@ -0,0 +1,318 @@
+use anchor_lang::AccountDeserialize;
+use anyhow::Context;
+use raydium_amm_v3::libraries::{liquidity_math, swap_math, tick_math};
+use raydium_amm_v3::states::{
+    AmmConfig, PoolState, TickArrayBitmapExtension, TickArrayState, TickState,
+    POOL_TICK_ARRAY_BITMAP_SEED, TICK_ARRAY_SEED,
+};
+use router_lib::dex::{AccountProviderView, DexEdge, DexEdgeIdentifier};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+use std::any::Any;
+use std::collections::VecDeque;
+
+/// Number of initialized tick arrays loaded in the swap direction, and passed to the swap instruction
+pub const MAX_TICK_ARRAYS: usize = 3;
+
+pub struct RaydiumClmmEdgeIdentifier {
+    pub pool: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub is_a_to_b: bool,
+}
+
+impl DexEdgeIdentifier for RaydiumClmmEdgeIdentifier {
+    fn key(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn desc(&self) -> String {
+        format!("RaydiumClmm_{}", self.pool)
+    }
+
+    fn input_mint(&self) -> Pubkey {
+        self.mint_a
+    }
+
+    fn output_mint(&self) -> Pubkey {
+        self.mint_b
+    }
+
+    fn accounts_needed(&self) -> usize {
+        // swap accounts, bitmap extension and tick arrays
+        13 + 1 + MAX_TICK_ARRAYS
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct RaydiumClmmEdge {
+    pub pool: PoolState,
+    pub config: AmmConfig,
+    pub bitmap_extension: Option<TickArrayBitmapExtension>,
+    /// Initialized tick arrays in the swap direction, starting with the current one
+    pub tick_arrays: VecDeque<TickArrayState>,
+}
+
+impl DexEdge for RaydiumClmmEdge {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct SwapResult {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+pub fn tick_array_pk(pool_pk: &Pubkey, start_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_pk.as_ref(),
+            &start_index.to_be_bytes(),
+        ],
+        &raydium_amm_v3::id(),
+    )
+    .0
+}
+
+pub fn bitmap_extension_pk(pool_pk: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_pk.as_ref()],
+        &raydium_amm_v3::id(),
+    )
+    .0
+}
+
+/// The extension only exists for pools with liquidity outside of the default bitmap range
+pub fn load_bitmap_extension(
+    pool_pk: &Pubkey,
+    chain_data: &AccountProviderView,
+) -> anyhow::Result<Option<TickArrayBitmapExtension>> {
+    let Ok(account) = chain_data.account(&bitmap_extension_pk(pool_pk)) else {
+        return Ok(None);
+    };
+    Ok(Some(TickArrayBitmapExtension::try_deserialize(
+        &mut account.account.data(),
+    )?))
+}
+
+/// Start indexes of the next initialized tick arrays in the swap direction, the current one first
+pub fn tick_array_start_indexes(
+    pool: &PoolState,
+    bitmap_extension: &Option<TickArrayBitmapExtension>,
+    zero_for_one: bool,
+    count: usize,
+) -> anyhow::Result<Vec<i32>> {
+    let bitmap_extension = bitmap_extension.as_ref();
+    let (_, mut start_index) =
+        pool.get_first_initialized_tick_array(&bitmap_extension, zero_for_one)?;
+
+    let mut result = vec![start_index];
+    while result.len() < count {
+        match pool.next_initialized_tick_array_start_index(
+            &bitmap_extension,
+            start_index,
+            zero_for_one,
+        )? {
+            Some(next) => {
+                result.push(next);
+                start_index = next;
+            }
+            None => break,
+        }
+    }
+    Ok(result)
+}
+
+/// Loads the tick arrays in order, stopping at the first one missing from chain data
+pub fn load_tick_arrays(
+    pool_pk: &Pubkey,
+    start_indexes: &[i32],
+    chain_data: &AccountProviderView,
+) -> anyhow::Result<VecDeque<TickArrayState>> {
+    let mut tick_arrays = VecDeque::new();
+    for start_index in start_indexes {
+        let Ok(account) = chain_data.account(&tick_array_pk(pool_pk, *start_index)) else {
+            break;
+        };
+        tick_arrays.push_back(TickArrayState::try_deserialize(
+            &mut account.account.data(),
+        )?);
+    }
+    Ok(tick_arrays)
+}
+
+struct SwapState {
+    amount_specified_remaining: u64,
+    amount_calculated: u64,
+    sqrt_price_x64: u128,
+    tick: i32,
+    liquidity: u128,
+    fee_amount: u64,
+}
+
+/// Off-chain version of the program swap loop, it walks the loaded tick arrays
+/// and fails if the swap would need more of them than the instruction gets
+pub fn swap_compute(
+    edge: &RaydiumClmmEdge,
+    zero_for_one: bool,
+    is_base_input: bool,
+    amount_specified: u64,
+    block_timestamp: u32,
+) -> anyhow::Result<SwapResult> {
+    if amount_specified == 0 {
+        anyhow::bail!("amount must be gt 0");
+    }
+
+    let pool = &edge.pool;
+    let bitmap_extension = edge.bitmap_extension.as_ref();
+    let sqrt_price_limit_x64 = if zero_for_one {
+        tick_math::MIN_SQRT_PRICE_X64 + 1
+    } else {
+        tick_math::MAX_SQRT_PRICE_X64 - 1
+    };
+
+    let mut tick_arrays = edge.tick_arrays.clone();
+    let (mut is_match_pool_current_tick_array, mut current_start_index) =
+        pool.get_first_initialized_tick_array(&bitmap_extension, zero_for_one)?;
+    let mut tick_array_current = tick_arrays.pop_front().context("no tick array loaded")?;
+    if tick_array_current.start_tick_index != current_start_index {
+        anyhow::bail!("first tick array does not match pool state");
+    }
+
+    let mut state = SwapState {
+        amount_specified_remaining: amount_specified,
+        amount_calculated: 0,
+        sqrt_price_x64: pool.sqrt_price_x64,
+        tick: pool.tick_current,
+        liquidity: pool.liquidity,
+        fee_amount: 0,
+    };
+
+    while state.amount_specified_remaining != 0
+        && state.sqrt_price_x64 != sqrt_price_limit_x64
+        && state.tick < tick_math::MAX_TICK
+        && state.tick > tick_math::MIN_TICK
+    {
+        let sqrt_price_start_x64 = state.sqrt_price_x64;
+
+        let next_in_current_array = tick_array_current
+            .next_initialized_tick(state.tick, pool.tick_spacing, zero_for_one)?
+            .map(|tick_state| *tick_state);
+        let mut next_initialized_tick = match next_in_current_array {
+            Some(tick_state) => tick_state,
+            None if !is_match_pool_current_tick_array => {
+                is_match_pool_current_tick_array = true;
+                *tick_array_current.first_initialized_tick(zero_for_one)?
+            }
+            None => TickState::default(),
+        };
+
+        if !next_initialized_tick.is_initialized() {
+            current_start_index = pool
+                .next_initialized_tick_array_start_index(
+                    &bitmap_extension,
+                    current_start_index,
+                    zero_for_one,
+                )?
+                .context("not enough liquidity")?;
+            tick_array_current = tick_arrays
+                .pop_front()
+                .context("swap needs more tick arrays")?;
+            if tick_array_current.start_tick_index != current_start_index {
+                anyhow::bail!("tick array does not match pool bitmap");
+            }
+            next_initialized_tick = *tick_array_current.first_initialized_tick(zero_for_one)?;
+        }
+
+        let tick_next = next_initialized_tick
+            .tick
+            .clamp(tick_math::MIN_TICK, tick_math::MAX_TICK);
+        let sqrt_price_next_x64 = tick_math::get_sqrt_price_at_tick(tick_next)?;
+
+        let target_price = if (zero_for_one && sqrt_price_next_x64 < sqrt_price_limit_x64)
+            || (!zero_for_one && sqrt_price_next_x64 > sqrt_price_limit_x64)
+        {
+            sqrt_price_limit_x64
+        } else {
+            sqrt_price_next_x64
+        };
+
+        let step = swap_math::compute_swap_step(
+            state.sqrt_price_x64,
+            target_price,
+            state.liquidity,
+            state.amount_specified_remaining,
+            edge.config.trade_fee_rate,
+            is_base_input,
+            zero_for_one,
+            block_timestamp,
+        )?;
+        state.sqrt_price_x64 = step.sqrt_price_next_x64;
+        state.fee_amount = state
+            .fee_amount
+            .checked_add(step.fee_amount)
+            .context("fee overflow")?;
+
+        if is_base_input {
+            state.amount_specified_remaining = state
+                .amount_specified_remaining
+                .checked_sub(step.amount_in + step.fee_amount)
+                .context("amount underflow")?;
+            state.amount_calculated = state
+                .amount_calculated
+                .checked_add(step.amount_out)
+                .context("amount overflow")?;
+        } else {
+            state.amount_specified_remaining = state
+                .amount_specified_remaining
+                .checked_sub(step.amount_out)
+                .context("amount underflow")?;
+            state.amount_calculated = state
+                .amount_calculated
+                .checked_add(step.amount_in + step.fee_amount)
+                .context("amount overflow")?;
+        }
+
+        if state.sqrt_price_x64 == sqrt_price_next_x64 {
+            if next_initialized_tick.is_initialized() {
+                let liquidity_net = if zero_for_one {
+                    -next_initialized_tick.liquidity_net
+                } else {
+                    next_initialized_tick.liquidity_net
+                };
+                state.liquidity = liquidity_math::add_delta(state.liquidity, liquidity_net)?;
+            }
+            state.tick = if zero_for_one {
+                tick_next - 1
+            } else {
+                tick_next
+            };
+        } else if state.sqrt_price_x64 != sqrt_price_start_x64 {
+            state.tick = tick_math::get_tick_at_sqrt_price(state.sqrt_price_x64)?;
+        }
+    }
+
+    if state.amount_specified_remaining != 0 {
+        anyhow::bail!("not enough liquidity");
+    }
+
+    if is_base_input {
+        Ok(SwapResult {
+            amount_in: amount_specified,
+            amount_out: state.amount_calculated,
+            fee_amount: state.fee_amount,
+        })
+    } else {
+        Ok(SwapResult {
+            amount_in: state.amount_calculated,
+            amount_out: amount_specified,
+            fee_amount: state.fee_amount,
+        })
+    }
+}
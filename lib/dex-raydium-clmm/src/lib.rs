@@ -0,0 +1,5 @@
+mod edge;
+mod raydium_clmm;
+mod raydium_clmm_ix_builder;
+
+pub use crate::raydium_clmm::RaydiumClmmDex;
@@ -0,0 +1,365 @@
+use crate::edge::{
+    bitmap_extension_pk, load_bitmap_extension, load_tick_arrays, swap_compute, tick_array_pk,
+    tick_array_start_indexes, RaydiumClmmEdge, RaydiumClmmEdgeIdentifier, MAX_TICK_ARRAYS,
+};
+use crate::raydium_clmm_ix_builder;
+use anchor_lang::{AccountDeserialize, Discriminator, Id};
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_hook::TransferHookAccount;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::AccountState;
+use anchor_spl::token_2022::Token2022;
+use async_trait::async_trait;
+use itertools::Itertools;
+use raydium_amm_v3::states::{AmmConfig, PoolState, PoolStatusBitIndex, TickArrayBitmapExtension};
+use router_feed_lib::router_rpc_client::{RouterRpcClient, RouterRpcClientTrait};
+use router_lib::dex::{
+    AccountProviderView, DexEdge, DexEdgeIdentifier, DexInterface, DexSubscriptionMode, Quote,
+    SwapInstruction,
+};
+use router_lib::utils;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+pub struct RaydiumClmmDex {
+    pub edges: HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>,
+}
+
+#[async_trait]
+impl DexInterface for RaydiumClmmDex {
+    async fn initialize(
+        rpc: &mut RouterRpcClient,
+        _options: HashMap<String, String>,
+    ) -> anyhow::Result<Arc<dyn DexInterface>>
+    where
+        Self: Sized,
+    {
+        let pools =
+            fetch_raydium_account::<PoolState>(rpc, raydium_amm_v3::id(), PoolState::LEN).await?;
+
+        let vaults = pools
+            .iter()
+            .flat_map(|x| [x.1.token_vault_0, x.1.token_vault_1])
+            .collect::<HashSet<_>>();
+        let vaults = rpc.get_multiple_accounts(&vaults).await?;
+        let banned_vaults = vaults
+            .iter()
+            .filter(|x| is_banned_vault(&x.1.owner, x.1.data()))
+            .map(|x| x.0)
+            .collect::<HashSet<_>>();
+
+        let pools = pools
+            .into_iter()
+            .filter(|(_pool_pk, pool)| {
+                !banned_vaults.contains(&pool.token_vault_0)
+                    && !banned_vaults.contains(&pool.token_vault_1)
+            })
+            .collect_vec();
+
+        let extensions = pools
+            .iter()
+            .map(|(pool_pk, _)| bitmap_extension_pk(pool_pk))
+            .collect::<HashSet<_>>();
+        let extensions = rpc
+            .get_multiple_accounts(&extensions)
+            .await?
+            .into_iter()
+            .filter_map(|(pk, account)| {
+                TickArrayBitmapExtension::try_deserialize(&mut account.data())
+                    .ok()
+                    .map(|extension| (pk, extension))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut edges_per_pk = HashMap::new();
+        for (pool_pk, pool) in &pools {
+            let extension = extensions.get(&bitmap_extension_pk(pool_pk)).copied();
+            for (key, entry) in pool_edges(pool_pk, pool, &extension) {
+                utils::insert_or_extend(&mut edges_per_pk, &key, &entry);
+            }
+        }
+
+        Ok(Arc::new(RaydiumClmmDex {
+            edges: edges_per_pk,
+        }))
+    }
+
+    fn name(&self) -> String {
+        "RaydiumClmm".to_string()
+    }
+
+    fn subscription_mode(&self) -> DexSubscriptionMode {
+        DexSubscriptionMode::Programs(HashSet::from([raydium_amm_v3::id()]))
+    }
+
+    fn program_ids(&self) -> HashSet<Pubkey> {
+        [raydium_amm_v3::id()].into_iter().collect()
+    }
+
+    fn edges_per_pk(&self) -> HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>> {
+        self.edges.clone()
+    }
+
+    fn discover_edges(
+        &self,
+        pk: &Pubkey,
+        chain_data: &AccountProviderView,
+    ) -> anyhow::Result<HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>> {
+        let pool_account = chain_data.account(pk)?;
+        let data = pool_account.account.data();
+        if data.len() != PoolState::LEN || !data.starts_with(&PoolState::DISCRIMINATOR) {
+            return Ok(HashMap::new());
+        }
+
+        let pool = PoolState::try_deserialize(&mut &data[..])?;
+
+        // vaults are not subscribed, only check them if we already know them
+        let banned_vault = [pool.token_vault_0, pool.token_vault_1]
+            .iter()
+            .any(|vault| {
+                chain_data
+                    .account(vault)
+                    .is_ok_and(|x| is_banned_vault(x.account.owner(), x.account.data()))
+            });
+        if banned_vault {
+            return Ok(HashMap::new());
+        }
+
+        let extension = load_bitmap_extension(pk, chain_data)?;
+        let mut map = HashMap::new();
+        for (key, entry) in pool_edges(pk, &pool, &extension) {
+            utils::insert_or_extend(&mut map, &key, &entry);
+        }
+        Ok(map)
+    }
+
+    fn load(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        chain_data: &AccountProviderView,
+    ) -> anyhow::Result<Arc<dyn DexEdge>> {
+        let id = id
+            .as_any()
+            .downcast_ref::<RaydiumClmmEdgeIdentifier>()
+            .unwrap();
+
+        let pool_account = chain_data.account(&id.pool)?;
+        let pool = PoolState::try_deserialize(&mut pool_account.account.data())?;
+        let config_account = chain_data.account(&pool.amm_config)?;
+        let config = AmmConfig::try_deserialize(&mut config_account.account.data())?;
+
+        let bitmap_extension = load_bitmap_extension(&id.pool, chain_data)?;
+        let start_indexes =
+            tick_array_start_indexes(&pool, &bitmap_extension, id.is_a_to_b, MAX_TICK_ARRAYS)?;
+        let tick_arrays = load_tick_arrays(&id.pool, &start_indexes, chain_data)?;
+
+        Ok(Arc::new(RaydiumClmmEdge {
+            pool,
+            config,
+            bitmap_extension,
+            tick_arrays,
+        }))
+    }
+
+    fn quote(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        edge: &Arc<dyn DexEdge>,
+        chain_data: &AccountProviderView,
+        in_amount: u64,
+    ) -> anyhow::Result<Quote> {
+        let id = id
+            .as_any()
+            .downcast_ref::<RaydiumClmmEdgeIdentifier>()
+            .unwrap();
+        let edge = edge.as_any().downcast_ref::<RaydiumClmmEdge>().unwrap();
+
+        let clock = chain_data.clock()?;
+        if !is_open(&edge.pool, clock.unix_timestamp) {
+            return Ok(Quote {
+                in_amount: 0,
+                out_amount: 0,
+                fee_amount: 0,
+                fee_mint: id.mint_a,
+            });
+        }
+
+        let result = swap_compute(
+            edge,
+            id.is_a_to_b,
+            true,
+            in_amount,
+            clock.unix_timestamp as u32,
+        )?;
+
+        Ok(Quote {
+            in_amount: result.amount_in,
+            out_amount: result.amount_out,
+            fee_amount: result.fee_amount,
+            fee_mint: id.mint_a,
+        })
+    }
+
+    fn build_swap_ix(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        chain_data: &AccountProviderView,
+        wallet_pk: &Pubkey,
+        in_amount: u64,
+        out_amount: u64,
+        max_slippage_bps: i32,
+    ) -> anyhow::Result<SwapInstruction> {
+        let id = id
+            .as_any()
+            .downcast_ref::<RaydiumClmmEdgeIdentifier>()
+            .unwrap();
+        raydium_clmm_ix_builder::build_swap_ix(
+            id,
+            chain_data,
+            wallet_pk,
+            in_amount,
+            out_amount,
+            max_slippage_bps,
+        )
+    }
+
+    fn supports_exact_out(&self, _id: &Arc<dyn DexEdgeIdentifier>) -> bool {
+        true
+    }
+
+    fn quote_exact_out(
+        &self,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        edge: &Arc<dyn DexEdge>,
+        chain_data: &AccountProviderView,
+        out_amount: u64,
+    ) -> anyhow::Result<Quote> {
+        let id = id
+            .as_any()
+            .downcast_ref::<RaydiumClmmEdgeIdentifier>()
+            .unwrap();
+        let edge = edge.as_any().downcast_ref::<RaydiumClmmEdge>().unwrap();
+
+        let clock = chain_data.clock()?;
+        if !is_open(&edge.pool, clock.unix_timestamp) {
+            return Ok(Quote {
+                in_amount: u64::MAX,
+                out_amount: 0,
+                fee_amount: 0,
+                fee_mint: id.mint_a,
+            });
+        }
+
+        let result = swap_compute(
+            edge,
+            id.is_a_to_b,
+            false,
+            out_amount,
+            clock.unix_timestamp as u32,
+        )?;
+
+        Ok(Quote {
+            in_amount: result.amount_in,
+            out_amount: result.amount_out,
+            fee_amount: result.fee_amount,
+            fee_mint: id.mint_a,
+        })
+    }
+}
+
+/// Frozen vaults can't be swapped against, and transfer hooks need extra accounts
+/// that the swap instruction does not pass. Token-2022 transfer fees are applied by the router.
+fn is_banned_vault(owner: &Pubkey, data: &[u8]) -> bool {
+    if *owner != Token::id() && *owner != Token2022::id() {
+        return true;
+    }
+    // also reads spl-token accounts, which have no extensions
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(data).map_or(true, |account| {
+        account.base.state == AccountState::Frozen
+            || account.get_extension::<TransferHookAccount>().is_ok()
+    })
+}
+
+fn is_open(pool: &PoolState, unix_timestamp: i64) -> bool {
+    pool.get_status_by_bit(PoolStatusBitIndex::Swap) && pool.open_time < unix_timestamp as u64
+}
+
+/// Accounts whose changes affect the pool edges: the pool, its config, the bitmap
+/// extension and the tick arrays the next swaps will go through in both directions
+fn pool_edges(
+    pool_pk: &Pubkey,
+    pool: &PoolState,
+    extension: &Option<TickArrayBitmapExtension>,
+) -> Vec<(Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>)> {
+    let entry = vec![
+        Arc::new(RaydiumClmmEdgeIdentifier {
+            pool: *pool_pk,
+            mint_a: pool.token_mint_0,
+            mint_b: pool.token_mint_1,
+            is_a_to_b: true,
+        }) as Arc<dyn DexEdgeIdentifier>,
+        Arc::new(RaydiumClmmEdgeIdentifier {
+            pool: *pool_pk,
+            mint_a: pool.token_mint_1,
+            mint_b: pool.token_mint_0,
+            is_a_to_b: false,
+        }),
+    ];
+
+    let tick_arrays = [true, false]
+        .into_iter()
+        .flat_map(|zero_for_one| {
+            tick_array_start_indexes(pool, extension, zero_for_one, MAX_TICK_ARRAYS)
+                .unwrap_or_default()
+        })
+        .unique()
+        .map(|start_index| tick_array_pk(pool_pk, start_index));
+
+    [*pool_pk, pool.amm_config, bitmap_extension_pk(pool_pk)]
+        .into_iter()
+        .chain(tick_arrays)
+        .map(|key| (key, entry.clone()))
+        .collect()
+}
+
+async fn fetch_raydium_account<T: Discriminator + AccountDeserialize>(
+    rpc: &mut RouterRpcClient,
+    program_id: Pubkey,
+    len: usize,
+) -> anyhow::Result<Vec<(Pubkey, T)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(len as u64),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, T::DISCRIMINATOR.to_vec())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::finalized()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let snapshot = rpc
+        .get_program_accounts_with_config(&program_id, config)
+        .await?;
+
+    let result = snapshot
+        .iter()
+        .map(|account| {
+            let pool: T = T::try_deserialize(&mut account.data.as_slice()).unwrap();
+            (account.pubkey, pool)
+        })
+        .collect_vec();
+
+    Ok(result)
+}
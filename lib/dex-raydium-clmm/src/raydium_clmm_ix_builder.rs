@@ -0,0 +1,104 @@
+use crate::edge::{
+    bitmap_extension_pk, load_bitmap_extension, tick_array_pk, tick_array_start_indexes,
+    RaydiumClmmEdgeIdentifier, MAX_TICK_ARRAYS,
+};
+use anchor_lang::{AccountDeserialize, Id, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use raydium_amm_v3::states::PoolState;
+use router_lib::dex::{AccountProviderView, SwapInstruction};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+
+const MEMO_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+pub fn build_swap_ix(
+    id: &RaydiumClmmEdgeIdentifier,
+    chain_data: &AccountProviderView,
+    wallet_pk: &Pubkey,
+    in_amount: u64,
+    out_amount: u64,
+    max_slippage_bps: i32,
+) -> anyhow::Result<SwapInstruction> {
+    let pool_account = chain_data.account(&id.pool)?;
+    let pool = PoolState::try_deserialize(&mut pool_account.account.data())?;
+
+    let amount = in_amount;
+    let other_amount_threshold =
+        ((out_amount as f64 * (10_000f64 - max_slippage_bps as f64)) / 10_000f64).floor() as u64;
+
+    let (input_vault, output_vault) = if id.is_a_to_b {
+        (pool.token_vault_0, pool.token_vault_1)
+    } else {
+        (pool.token_vault_1, pool.token_vault_0)
+    };
+
+    let (input_token_account, output_token_account) = (
+        get_associated_token_address_with_program_id(
+            wallet_pk,
+            &id.mint_a,
+            &chain_data.token_program(&id.mint_a),
+        ),
+        get_associated_token_address_with_program_id(
+            wallet_pk,
+            &id.mint_b,
+            &chain_data.token_program(&id.mint_b),
+        ),
+    );
+
+    // Remaining accounts: the bitmap extension if the pool has one, then the tick arrays in swap direction
+    let bitmap_extension = load_bitmap_extension(&id.pool, chain_data)?;
+    let tick_arrays =
+        tick_array_start_indexes(&pool, &bitmap_extension, id.is_a_to_b, MAX_TICK_ARRAYS)?
+            .into_iter()
+            .map(|start_index| tick_array_pk(&id.pool, start_index));
+    let remaining_accounts = bitmap_extension
+        .map(|_| bitmap_extension_pk(&id.pool))
+        .into_iter()
+        .chain(tick_arrays)
+        .map(|pk| AccountMeta::new(pk, false));
+
+    let instruction = raydium_amm_v3::instruction::SwapV2 {
+        amount,
+        other_amount_threshold,
+        sqrt_price_limit_x64: 0,
+        is_base_input: true,
+    };
+
+    let accounts = raydium_amm_v3::accounts::SwapSingleV2 {
+        payer: *wallet_pk,
+        amm_config: pool.amm_config,
+        pool_state: id.pool,
+        input_token_account,
+        output_token_account,
+        input_vault,
+        output_vault,
+        observation_state: pool.observation_key,
+        token_program: Token::id(),
+        token_program_2022: Token2022::id(),
+        memo_program: MEMO_PROGRAM_ID,
+        input_vault_mint: id.mint_a,
+        output_vault_mint: id.mint_b,
+    };
+
+    let result = SwapInstruction {
+        instruction: Instruction {
+            program_id: raydium_amm_v3::id(),
+            accounts: accounts
+                .to_account_metas(None)
+                .into_iter()
+                .chain(remaining_accounts)
+                .collect(),
+            data: instruction.data(),
+        },
+        out_pubkey: output_token_account,
+        out_mint: id.mint_b,
+        in_amount_offset: 8,
+        cu_estimate: Some(80_000),
+    };
+
+    Ok(result)
+}
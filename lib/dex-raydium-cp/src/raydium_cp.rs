@@ -4,7 +4,6 @@ use anchor_lang::{AccountDeserialize, Discriminator, Id};
 use anchor_spl::token::spl_token::state::AccountState;
 use anchor_spl::token::{spl_token, Token};
 use anchor_spl::token_2022::spl_token_2022;
-use anyhow::Context;
 use async_trait::async_trait;
 use itertools::Itertools;
 use raydium_cp_swap::program::RaydiumCpSwap;
@@ -21,9 +20,7 @@ use solana_client::rpc_filter::{Memcmp, RpcFilterType};
 use solana_program::program_pack::Pack;
 use solana_program::pubkey::Pubkey;
 use solana_sdk::account::ReadableAccount;
-use solana_sdk::clock::Clock;
 use solana_sdk::commitment_config::CommitmentConfig;
-use solana_sdk::sysvar::SysvarId;
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -113,9 +110,6 @@ impl DexInterface for RaydiumCpDex {
                 needed_accounts.insert(pool.amm_config);
                 needed_accounts.insert(pool.token_0_vault);
                 needed_accounts.insert(pool.token_1_vault);
-                // TODO Uncomment for Token-2022
-                // needed_accounts.insert(pool.token_0_mint);
-                // needed_accounts.insert(pool.token_1_mint);
             }
             map
         };
@@ -149,6 +143,50 @@ impl DexInterface for RaydiumCpDex {
         self.edges.clone()
     }
 
+    fn discover_edges(
+        &self,
+        pk: &Pubkey,
+        chain_data: &AccountProviderView,
+    ) -> anyhow::Result<HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>> {
+        let pool_account = chain_data.account(pk)?;
+        let data = pool_account.account.data();
+        if data.len() != PoolState::LEN || !data.starts_with(&PoolState::DISCRIMINATOR) {
+            return Ok(HashMap::new());
+        }
+
+        let pool = PoolState::try_deserialize(&mut &data[..])?;
+        // TODO Remove filter when 2022 are working
+        if pool.token_0_program != Token::id() || pool.token_1_program != Token::id() {
+            return Ok(HashMap::new());
+        }
+
+        let entry = vec![
+            Arc::new(RaydiumCpEdgeIdentifier {
+                pool: *pk,
+                mint_a: pool.token_0_mint,
+                mint_b: pool.token_1_mint,
+                is_a_to_b: true,
+            }) as Arc<dyn DexEdgeIdentifier>,
+            Arc::new(RaydiumCpEdgeIdentifier {
+                pool: *pk,
+                mint_a: pool.token_1_mint,
+                mint_b: pool.token_0_mint,
+                is_a_to_b: false,
+            }),
+        ];
+
+        let mut map = HashMap::new();
+        for key in [
+            pk,
+            &pool.amm_config,
+            &pool.token_0_vault,
+            &pool.token_1_vault,
+        ] {
+            utils::insert_or_extend(&mut map, key, &entry);
+        }
+        Ok(map)
+    }
+
     fn load(
         &self,
         id: &Arc<dyn DexEdgeIdentifier>,
@@ -170,22 +208,11 @@ impl DexInterface for RaydiumCpDex {
         let vault_1_account = chain_data.account(&pool.token_1_vault)?;
         let vault_1 = spl_token_2022::state::Account::unpack(vault_1_account.account.data())?;
 
-        let transfer_0_fee = None;
-        let transfer_1_fee = None;
-
-        // TODO Uncomment for Token-2022
-        // let mint_0_account = chain_data.account(&pool.token_0_mint)?;
-        // let mint_1_account = chain_data.account(&pool.token_1_mint)?;
-        // let transfer_0_fee = crate::edge::get_transfer_config(mint_0_account)?;
-        // let transfer_1_fee = crate::edge::get_transfer_config(mint_1_account)?;
-
         Ok(Arc::new(RaydiumCpEdge {
             pool,
             config,
             vault_0_amount: vault_0.amount,
             vault_1_amount: vault_1.amount,
-            mint_0: transfer_0_fee,
-            mint_1: transfer_1_fee,
         }))
     }
 
@@ -211,9 +238,8 @@ impl DexInterface for RaydiumCpDex {
             });
         }
 
-        let clock = chain_data.account(&Clock::id()).context("read clock")?;
-        let now_ts = clock.account.deserialize_data::<Clock>()?.unix_timestamp as u64;
-        if edge.pool.open_time > now_ts {
+        let clock = chain_data.clock()?;
+        if edge.pool.open_time > clock.unix_timestamp as u64 {
             return Ok(Quote {
                 in_amount: 0,
                 out_amount: 0,
@@ -226,12 +252,11 @@ impl DexInterface for RaydiumCpDex {
             let result = swap_base_input(
                 &edge.pool,
                 &edge.config,
+                &clock,
                 edge.pool.token_0_vault,
                 edge.vault_0_amount,
-                &edge.mint_0,
                 edge.pool.token_1_vault,
                 edge.vault_1_amount,
-                &edge.mint_1,
                 in_amount,
             )?;
 
@@ -245,12 +270,11 @@ impl DexInterface for RaydiumCpDex {
             let result = swap_base_input(
                 &edge.pool,
                 &edge.config,
+                &clock,
                 edge.pool.token_1_vault,
                 edge.vault_1_amount,
-                &edge.mint_1,
                 edge.pool.token_0_vault,
                 edge.vault_0_amount,
-                &edge.mint_0,
                 in_amount,
             )?;
 
@@ -313,9 +337,8 @@ impl DexInterface for RaydiumCpDex {
             });
         }
 
-        let clock = chain_data.account(&Clock::id()).context("read clock")?;
-        let now_ts = clock.account.deserialize_data::<Clock>()?.unix_timestamp as u64;
-        if edge.pool.open_time > now_ts {
+        let clock = chain_data.clock()?;
+        if edge.pool.open_time > clock.unix_timestamp as u64 {
             return Ok(Quote {
                 in_amount: u64::MAX,
                 out_amount: 0,
@@ -328,12 +351,11 @@ impl DexInterface for RaydiumCpDex {
             let result = swap_base_output(
                 &edge.pool,
                 &edge.config,
+                &clock,
                 edge.pool.token_0_vault,
                 edge.vault_0_amount,
-                &edge.mint_0,
                 edge.pool.token_1_vault,
                 edge.vault_1_amount,
-                &edge.mint_1,
                 out_amount,
             )?;
 
@@ -347,12 +369,11 @@ impl DexInterface for RaydiumCpDex {
             let result = swap_base_output(
                 &edge.pool,
                 &edge.config,
+                &clock,
                 edge.pool.token_1_vault,
                 edge.vault_1_amount,
-                &edge.mint_1,
                 edge.pool.token_0_vault,
                 edge.vault_0_amount,
-                &edge.mint_0,
                 out_amount,
             )?;
 
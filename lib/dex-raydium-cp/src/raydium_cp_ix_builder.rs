@@ -1,6 +1,6 @@
 use crate::edge::RaydiumCpEdgeIdentifier;
 use anchor_lang::{AccountDeserialize, Id, InstructionData, ToAccountMetas};
-use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
 use raydium_cp_swap::program::RaydiumCpSwap;
 use raydium_cp_swap::states::PoolState;
 use raydium_cp_swap::AUTH_SEED;
@@ -41,8 +41,16 @@ pub fn build_swap_ix(
     };
 
     let (input_token_account, output_token_account) = (
-        get_associated_token_address(wallet_pk, &input_token_mint),
-        get_associated_token_address(wallet_pk, &output_token_mint),
+        get_associated_token_address_with_program_id(
+            wallet_pk,
+            &input_token_mint,
+            &input_token_program,
+        ),
+        get_associated_token_address_with_program_id(
+            wallet_pk,
+            &output_token_mint,
+            &output_token_program,
+        ),
     );
 
     let instruction = raydium_cp_swap::instruction::SwapBaseInput {
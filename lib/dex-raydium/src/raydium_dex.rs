@@ -7,7 +7,6 @@ use anchor_lang::Id;
 use anchor_spl::token::spl_token::state::{Account, AccountState};
 use anchor_spl::token::{spl_token, Token};
 use async_trait::async_trait;
-use chrono::Utc;
 use itertools::Itertools;
 use router_feed_lib::router_rpc_client::{RouterRpcClient, RouterRpcClientTrait};
 use router_lib::dex::{
@@ -49,10 +48,6 @@ impl DexInterface for RaydiumDex {
                     && !AmmStatus::from_u64(amm.status).orderbook_permission()
             })
             .filter(|(_, amm)| amm.coin_vault_mint != amm.pc_vault_mint)
-            .filter(|(_, amm)| {
-                amm.status != AmmStatus::WaitingTrade as u64
-                    || amm.state_data.pool_open_time < (Utc::now().timestamp() as u64)
-            })
             .collect_vec();
 
         info!(
@@ -146,7 +141,7 @@ impl DexInterface for RaydiumDex {
         &self,
         id: &Arc<dyn DexEdgeIdentifier>,
         edge: &Arc<dyn DexEdge>,
-        _chain_data: &AccountProviderView,
+        chain_data: &AccountProviderView,
         in_amount: u64,
     ) -> anyhow::Result<Quote> {
         let id = id.as_any().downcast_ref::<RaydiumEdgeIdentifier>().unwrap();
@@ -159,7 +154,7 @@ impl DexInterface for RaydiumDex {
         let coin_vault_is_frozen = coin_vault.state == AccountState::Frozen;
         let pc_vault_is_frozen = pc_vault.state == AccountState::Frozen;
 
-        if coin_vault_is_frozen || pc_vault_is_frozen {
+        if coin_vault_is_frozen || pc_vault_is_frozen || !is_open(amm, chain_data)? {
             return Ok(Quote {
                 in_amount,
                 out_amount: 0,
@@ -233,7 +228,7 @@ impl DexInterface for RaydiumDex {
         &self,
         id: &Arc<dyn DexEdgeIdentifier>,
         edge: &Arc<dyn DexEdge>,
-        _chain_data: &AccountProviderView,
+        chain_data: &AccountProviderView,
         out_amount: u64,
     ) -> anyhow::Result<Quote> {
         let id = id.as_any().downcast_ref::<RaydiumEdgeIdentifier>().unwrap();
@@ -243,6 +238,15 @@ impl DexInterface for RaydiumDex {
         let coin_vault = &edge.coin_vault;
         let pc_vault = &edge.pc_vault;
 
+        if !is_open(amm, chain_data)? {
+            return Ok(Quote {
+                in_amount: u64::MAX,
+                out_amount: 0,
+                fee_amount: 0,
+                fee_mint: Default::default(),
+            });
+        }
+
         let swap_direction = if id.is_pc_to_coin {
             SwapDirection::PC2Coin
         } else {
@@ -281,6 +285,16 @@ impl DexInterface for RaydiumDex {
     }
 }
 
+/// Pools waiting for trade can only be swapped once the on-chain clock reached their open time
+fn is_open(amm: &AmmInfo, chain_data: &AccountProviderView) -> anyhow::Result<bool> {
+    if amm.status != AmmStatus::WaitingTrade as u64 {
+        return Ok(true);
+    }
+
+    let now_ts = chain_data.clock()?.unix_timestamp as u64;
+    Ok(amm.state_data.pool_open_time <= now_ts)
+}
+
 async fn fetch_raydium_accounts(
     rpc: &mut RouterRpcClient,
     program_id: Pubkey,
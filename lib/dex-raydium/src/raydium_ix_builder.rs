@@ -1,7 +1,7 @@
 use crate::internal::state::AmmInfo;
 use crate::raydium_edge::RaydiumEdgeIdentifier;
 use anchor_lang::Id;
-use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
 use anchor_spl::token::Token;
 use router_lib::dex::{AccountProviderView, DexEdgeIdentifier, SwapInstruction};
 use solana_program::instruction::{AccountMeta, Instruction};
@@ -25,8 +25,16 @@ pub fn build_swap_ix(
     let (input_token_mint, output_token_mint) = (id.input_mint(), id.output_mint());
 
     let (input_token_account, output_token_account) = (
-        get_associated_token_address(wallet_pk, &input_token_mint),
-        get_associated_token_address(wallet_pk, &output_token_mint),
+        get_associated_token_address_with_program_id(
+            wallet_pk,
+            &input_token_mint,
+            &chain_data.token_program(&input_token_mint),
+        ),
+        get_associated_token_address_with_program_id(
+            wallet_pk,
+            &output_token_mint,
+            &chain_data.token_program(&output_token_mint),
+        ),
     );
 
     let exptected_size = 1 + 8 + 8;
@@ -1,7 +1,6 @@
 use std::any::Any;
 
 use anchor_spl::token::spl_token::state::Account;
-use solana_program::clock::Clock;
 use solana_program::pubkey::Pubkey;
 use stable_swap_client::state::SwapInfo;
 
@@ -44,7 +43,6 @@ pub struct SaberEdge {
     pub pool: SwapInfo,
     pub vault_a: Account,
     pub vault_b: Account,
-    pub clock: Clock,
 }
 
 impl DexEdge for SaberEdge {
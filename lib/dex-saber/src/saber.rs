@@ -5,7 +5,7 @@ use crate::edge::SaberEdge;
 use crate::edge::SaberEdgeIdentifier;
 use crate::saber_ix_builder;
 use anchor_spl::token::spl_token::state::Account;
-use anyhow::{bail, Context};
+use anyhow::bail;
 use async_trait::async_trait;
 use itertools::Itertools;
 use router_feed_lib::router_rpc_client::{RouterRpcClient, RouterRpcClientTrait};
@@ -16,10 +16,8 @@ use router_lib::dex::{
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
 use solana_client::rpc_filter::RpcFilterType;
-use solana_program::clock::Clock;
 use solana_program::program_pack::{IsInitialized, Pack};
 use solana_program::pubkey::Pubkey;
-use solana_program::sysvar::SysvarId;
 use solana_sdk::account::ReadableAccount;
 use solana_sdk::commitment_config::CommitmentConfig;
 use stable_swap_client::state::SwapInfo;
@@ -110,14 +108,11 @@ impl DexInterface for SaberDex {
         let vault_a = Account::unpack(vault_a_account.account.data())?;
         let vault_b_account = chain_data.account(&pool.token_b.reserves)?;
         let vault_b = Account::unpack(vault_b_account.account.data())?;
-        let clock_account = chain_data.account(&Clock::id()).context("read clock")?;
-        let clock = clock_account.account.deserialize_data::<Clock>()?;
 
         Ok(Arc::new(SaberEdge {
             pool,
             vault_a,
             vault_b,
-            clock,
         }))
     }
 
@@ -125,30 +120,20 @@ impl DexInterface for SaberDex {
         &self,
         id: &Arc<dyn DexEdgeIdentifier>,
         edge: &Arc<dyn DexEdge>,
-        _chain_data: &AccountProviderView,
+        chain_data: &AccountProviderView,
         in_amount: u64,
     ) -> anyhow::Result<Quote> {
         let id = id.as_any().downcast_ref::<SaberEdgeIdentifier>().unwrap();
         let edge = edge.as_any().downcast_ref::<SaberEdge>().unwrap();
 
         let pool = &edge.pool;
+        // amp factor is ramping with time, the pool account does not change meanwhile
+        let now_ts = chain_data.clock()?.unix_timestamp;
 
         let (out_amount, fee_amount) = if id.is_a_to_b {
-            simulate_swap(
-                pool,
-                &edge.vault_a,
-                &edge.vault_b,
-                edge.clock.unix_timestamp,
-                in_amount,
-            )?
+            simulate_swap(pool, &edge.vault_a, &edge.vault_b, now_ts, in_amount)?
         } else {
-            simulate_swap(
-                pool,
-                &edge.vault_b,
-                &edge.vault_a,
-                edge.clock.unix_timestamp,
-                in_amount,
-            )?
+            simulate_swap(pool, &edge.vault_b, &edge.vault_a, now_ts, in_amount)?
         };
 
         let fee_mint = if id.is_a_to_b {
@@ -195,17 +180,49 @@ impl DexInterface for SaberDex {
     }
 
     fn supports_exact_out(&self, _id: &Arc<dyn DexEdgeIdentifier>) -> bool {
-        false
+        true
     }
 
     fn quote_exact_out(
         &self,
-        _id: &Arc<dyn DexEdgeIdentifier>,
-        _edge: &Arc<dyn DexEdge>,
-        _chain_data: &AccountProviderView,
-        _out_amount: u64,
+        id: &Arc<dyn DexEdgeIdentifier>,
+        edge: &Arc<dyn DexEdge>,
+        chain_data: &AccountProviderView,
+        out_amount: u64,
     ) -> anyhow::Result<Quote> {
-        bail!("exact out not supported")
+        let id = id.as_any().downcast_ref::<SaberEdgeIdentifier>().unwrap();
+        let edge = edge.as_any().downcast_ref::<SaberEdge>().unwrap();
+
+        let pool = &edge.pool;
+        let now_ts = chain_data.clock()?.unix_timestamp;
+
+        let fee_mint = if id.is_a_to_b {
+            pool.token_b.mint
+        } else {
+            pool.token_a.mint
+        };
+
+        if pool.is_paused {
+            return Ok(Quote {
+                in_amount: u64::MAX,
+                out_amount: 0,
+                fee_amount: 0,
+                fee_mint,
+            });
+        }
+
+        let (in_amount, fee_amount) = if id.is_a_to_b {
+            simulate_swap_exact_out(pool, &edge.vault_a, &edge.vault_b, now_ts, out_amount)?
+        } else {
+            simulate_swap_exact_out(pool, &edge.vault_b, &edge.vault_a, now_ts, out_amount)?
+        };
+
+        Ok(Quote {
+            in_amount,
+            out_amount,
+            fee_amount,
+            fee_mint,
+        })
     }
 }
 
@@ -266,3 +283,64 @@ fn simulate_swap(
     let fees = result.fee + result.admin_fee;
     Ok((amount_swapped, fees))
 }
+
+/// Inverse of simulate_swap(): the trade fee is taken on the output, so the pool
+/// has to release amount_out plus that fee, then the input is found on the curve.
+fn simulate_swap_exact_out(
+    token_swap: &SwapInfo,
+    swap_source_account: &Account,
+    swap_destination_account: &Account,
+    unix_timestamp: i64,
+    amount_out: u64,
+) -> anyhow::Result<(u64, u64)> {
+    let invariant = StableSwap::new(
+        token_swap.initial_amp_factor,
+        token_swap.target_amp_factor,
+        unix_timestamp,
+        token_swap.start_ramp_ts,
+        token_swap.stop_ramp_ts,
+    );
+
+    let fees = &token_swap.fees;
+    let amount_out_before_fees = if fees.trade_fee_numerator == 0 {
+        amount_out as u128
+    } else {
+        (amount_out as u128 * fees.trade_fee_denominator as u128).div_ceil(
+            (fees.trade_fee_denominator as u128).saturating_sub(fees.trade_fee_numerator as u128),
+        )
+    };
+
+    let Some(estimate) = u64::try_from(amount_out_before_fees)
+        .ok()
+        .and_then(|dy| swap_destination_account.amount.checked_sub(dy))
+        .and_then(|new_destination_amount| {
+            let d =
+                invariant.compute_d(swap_source_account.amount, swap_destination_account.amount)?;
+            invariant.compute_y(new_destination_amount, d)
+        })
+        .and_then(|new_source_amount| new_source_amount.checked_sub(swap_source_account.amount))
+    else {
+        bail!("Invalid saber swap");
+    };
+
+    // The curve is solved with rounding on both sides, step up until the forward
+    // simulation gives at least the requested output
+    let mut amount_in = estimate;
+    let mut step = 1;
+    for _ in 0..32 {
+        let (amount_swapped, fees) = simulate_swap(
+            token_swap,
+            swap_source_account,
+            swap_destination_account,
+            unix_timestamp,
+            amount_in,
+        )?;
+        if amount_swapped >= amount_out {
+            return Ok((amount_in, fees));
+        }
+        amount_in = amount_in.saturating_add(step);
+        step = step.saturating_mul(2);
+    }
+
+    bail!("Invalid saber swap");
+}
@@ -1,6 +1,6 @@
 use crate::edge::SaberEdgeIdentifier;
 use anchor_lang::Id;
-use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
 use anchor_spl::token::Token;
 use router_lib::dex::{AccountProviderView, SwapInstruction};
 use solana_program::instruction::{AccountMeta, Instruction};
@@ -41,8 +41,16 @@ pub fn build_swap_ix(
     };
 
     let (input_token_account, output_token_account) = (
-        get_associated_token_address(wallet_pk, &input_token_mint),
-        get_associated_token_address(wallet_pk, &output_token_mint),
+        get_associated_token_address_with_program_id(
+            wallet_pk,
+            &input_token_mint,
+            &chain_data.token_program(&input_token_mint),
+        ),
+        get_associated_token_address_with_program_id(
+            wallet_pk,
+            &output_token_mint,
+            &chain_data.token_program(&output_token_mint),
+        ),
     );
 
     let instruction = stable_swap_client::instruction::SwapInstruction::Swap(SwapData {
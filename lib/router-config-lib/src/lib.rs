@@ -2,7 +2,7 @@ use std::{env, fs::File, io::Read};
 
 use serde::{Deserialize, Deserializer};
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct GrpcSourceConfig {
     pub name: String,
     pub connection_string: String,
@@ -11,7 +11,7 @@ pub struct GrpcSourceConfig {
     pub tls: Option<TlsConfig>,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct QuicSourceConfig {
     pub name: String,
     #[serde(deserialize_with = "serde_string_or_env")]
@@ -20,7 +20,7 @@ pub struct QuicSourceConfig {
     pub enable_gso: Option<bool>,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct TlsConfig {
     pub ca_cert_path: String,
     pub client_cert_path: String,
@@ -28,7 +28,7 @@ pub struct TlsConfig {
     pub domain_name: String,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct Config {
     pub routing: RoutingConfig,
     pub server: ServerConfig,
@@ -38,8 +38,11 @@ pub struct Config {
     pub orca: DexConfig,
     pub cropper: DexConfig,
     pub openbook_v2: DexConfig,
+    pub phoenix: DexConfig,
     pub raydium_cp: DexConfig,
     pub raydium: DexConfig,
+    pub raydium_clmm: DexConfig,
+    pub meteora_dlmm: DexConfig,
     pub saber: DexConfig,
     pub invariant: DexConfig,
     pub infinity: InfinityConfig,
@@ -47,6 +50,8 @@ pub struct Config {
     pub hot_mints: Option<HotMintsConfig>,
     pub debug_config: Option<DebugConfig>,
     pub snapshot_timeout_in_seconds: Option<u64>,
+    pub checkpoint: Option<CheckpointConfig>,
+    pub alt_manager: Option<AltManagerConfig>,
 }
 
 impl Config {
@@ -59,21 +64,144 @@ impl Config {
             Err(e) => Err(anyhow::Error::new(e)),
         }
     }
+
+    /// List settings that differ in `new`, split between the ones that
+    /// a running router can apply and the ones that need a restart
+    ///
+    /// Only route search settings are live, dex sections (enabled, mints)
+    /// decide which edges are loaded at startup and always need a restart
+    pub fn changes(&self, new: &Config) -> ConfigChanges {
+        let mut changes = ConfigChanges::default();
+
+        macro_rules! compare {
+            ($target:expr, $prefix:literal, $old:expr, $new:expr, [$($field:ident),*]) => {
+                $(
+                    if $old.$field != $new.$field {
+                        $target.push(format!("{}{}", $prefix, stringify!($field)));
+                    }
+                )*
+            };
+        }
+
+        compare!(
+            changes.live,
+            "routing.",
+            self.routing,
+            new.routing,
+            [
+                path_warming_interval_secs,
+                path_warming_for_mints,
+                path_warming_mode,
+                path_warming_amounts,
+                path_warming_max_accounts,
+                overquote,
+                max_path_length,
+                max_edge_per_pair,
+                max_edge_per_cold_pair,
+                max_split_paths,
+                split_chunk_count,
+                split_min_improvement_bps
+            ]
+        );
+        compare!(
+            changes.restart_required,
+            "routing.",
+            self.routing,
+            new.routing,
+            [
+                path_cache_validity_ms,
+                lookup_tables,
+                slot_excessive_lag,
+                slot_excessive_lag_max_duration_secs,
+                edge_updater_threads,
+                cooldown_duration_multihop_secs,
+                cooldown_duration_singlehop_secs,
+                retain_path_count,
+                discover_new_pools
+            ]
+        );
+        compare!(
+            changes.restart_required,
+            "",
+            self,
+            new,
+            [
+                server,
+                metrics,
+                sources,
+                price_feed,
+                orca,
+                cropper,
+                openbook_v2,
+                phoenix,
+                raydium_cp,
+                raydium,
+                raydium_clmm,
+                meteora_dlmm,
+                saber,
+                invariant,
+                infinity,
+                safety_checks,
+                hot_mints,
+                debug_config,
+                snapshot_timeout_in_seconds,
+                checkpoint,
+                alt_manager
+            ]
+        );
+
+        changes
+    }
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Serialize)]
+pub struct ConfigChanges {
+    /// applied without restart
+    pub live: Vec<String>,
+    /// ignored until the next restart
+    pub restart_required: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct HotMintsConfig {
     pub always_hot_mints: Vec<String>,
     pub keep_latest_count: usize,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+/// Warm state (accounts, edge prices, hot mints, path cache) saved to disk and restored on boot
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
+pub struct CheckpointConfig {
+    pub path: String,
+    pub interval_secs: Option<u64>,
+    /// Older checkpoints are ignored on boot
+    pub max_age_secs: Option<u64>,
+}
+
+/// Address lookup tables maintained by the router, used on top of `routing.lookup_tables`
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
+pub struct AltManagerConfig {
+    /// Pays for and is the authority of the managed tables
+    #[serde(deserialize_with = "serde_string_or_env")]
+    pub payer_keypair_path: String,
+    pub interval_secs: Option<u64>,
+    /// Add the accounts of frequently routed edges, otherwise tables are only extended with the cli.
+    /// Defaults to false
+    pub auto_extend: Option<bool>,
+    /// Edges used by fewer quoted routes during an interval are not added
+    pub min_route_count: Option<u64>,
+    /// Tables owned by the payer past which auto extend stops creating tables, defaults to 4
+    pub max_tables: Option<usize>,
+    /// Lamports auto extend may spend on rent and fees since the router started, defaults to 1 SOL
+    pub max_lamports: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct SafetyCheckConfig {
     pub check_quote_out_amount_deviation: bool,
     pub min_quote_out_to_in_amount_ratio: f64,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct DexConfig {
     pub enabled: bool,
     pub mints: Vec<String>,
@@ -81,12 +209,12 @@ pub struct DexConfig {
     pub take_all_mints: bool,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct InfinityConfig {
     pub enabled: bool,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct AccountDataSourceConfig {
     pub region: Option<String>,
     pub quic_sources: Option<Vec<QuicSourceConfig>>,
@@ -99,9 +227,13 @@ pub struct AccountDataSourceConfig {
     pub dedup_queue_size: usize,
     pub request_timeout_in_seconds: Option<u64>,
     pub number_of_accounts_per_gma: Option<usize>,
+    /// Updates from a grpc/quic source more than this many slots behind the freshest source are ignored
+    pub max_source_slot_lag: Option<u64>,
+    /// Only apply account writes once their slot is confirmed, so that quotes never use processed-only data
+    pub confirmed_only: Option<bool>,
 }
 
-#[derive(Clone, Debug, serde_derive::Deserialize)]
+#[derive(Clone, Debug, PartialEq, serde_derive::Deserialize)]
 pub enum PathWarmingMode {
     None,
     ConfiguredMints,
@@ -110,7 +242,7 @@ pub enum PathWarmingMode {
     HotMints,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct RoutingConfig {
     pub path_cache_validity_ms: u64,
     pub lookup_tables: Vec<String>,
@@ -121,6 +253,8 @@ pub struct RoutingConfig {
     pub path_warming_max_accounts: Option<Vec<usize>>,
     pub slot_excessive_lag: Option<u64>,
     pub slot_excessive_lag_max_duration_secs: Option<u64>,
+    /// Threads repricing dirty edges, shared by all dexes, defaults to the number of cores
+    pub edge_updater_threads: Option<usize>,
     pub cooldown_duration_multihop_secs: Option<u64>,
     pub cooldown_duration_singlehop_secs: Option<u64>,
 
@@ -132,33 +266,73 @@ pub struct RoutingConfig {
     pub retain_path_count: Option<usize>,
     pub max_edge_per_pair: Option<usize>,
     pub max_edge_per_cold_pair: Option<usize>,
+
+    /// Max number of parallel paths a single quote can be split into (1 = no split)
+    pub max_split_paths: Option<usize>,
+    /// Number of chunks the amount is divided into when looking for the best split
+    pub split_chunk_count: Option<usize>,
+    /// Only split if the result is better than the best single path by at least this
+    pub split_min_improvement_bps: Option<u64>,
+
+    /// Add pools created after startup to the routing graph (only for dexes subscribed by program)
+    pub discover_new_pools: Option<bool>,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct ServerConfig {
     #[serde(deserialize_with = "serde_string_or_env")]
     pub address: String,
+    /// Secret used to HMAC quotes; when signing is on /swap and /swap-instructions only accept signed quotes
+    #[serde(default, deserialize_with = "serde_opt_string_or_env")]
+    pub quote_signing_key: Option<String>,
+    /// Sign quotes, the router refuses to start without `quote_signing_key`.
+    /// Defaults to true when a key is set
+    pub sign_quotes: Option<bool>,
+    /// How long a signed quote can be swapped, defaults to 60s
+    pub quote_validity_secs: Option<u64>,
+    /// Admin API (edges inspection and bans, config reload) is only served when set
+    pub admin_address: Option<String>,
+    /// Bearer token required by the admin API
+    #[serde(default, deserialize_with = "serde_opt_string_or_env")]
+    pub admin_token: Option<String>,
+    /// Minimum delay between two quotes pushed to the websocket streams of a client, defaults to 500ms
+    pub quote_stream_throttle_ms: Option<u64>,
+    /// Platform share (percent) of the platform fee when the swap has a referrer,
+    /// the referrer gets the rest, defaults to 50
+    pub platform_fee_percent_with_referrer: Option<u8>,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct DebugConfig {
     pub reprice_using_live_rpc: bool,
     pub reprice_probability: f64,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct MetricsConfig {
     pub output_stdout: bool,
     pub output_http: bool,
     pub prometheus_address: Option<String>,
 }
 
-#[derive(Clone, Debug, Default, serde_derive::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
 pub struct PriceFeedConfig {
     #[serde(deserialize_with = "serde_string_or_env")]
     pub birdeye_token: String,
     pub birdeye_single_mode: Option<bool>,
     pub refresh_interval_secs: u64,
+    /// Derive prices from the router's pools for mints the external feeds have no price for
+    pub onchain_fallback: Option<bool>,
+    /// Only derive prices from the router's pools, birdeye and fill.city are not queried
+    pub onchain_only: Option<bool>,
+    /// Pyth pull oracle or Switchboard on-demand accounts, a price is published on every oracle write
+    pub oracles: Option<Vec<OracleConfig>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
+pub struct OracleConfig {
+    pub mint: String,
+    pub oracle: String,
 }
 
 /// Get a string content, or the content of an Env variable it the string start with $
@@ -213,3 +387,34 @@ pub fn string_or_env(value_or_env: String) -> String {
 
     value
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_split_live_and_restart_required_changes() {
+        let old = Config::default();
+        let mut new = old.clone();
+        assert_eq!(ConfigChanges::default(), old.changes(&new));
+
+        new.routing.overquote = Some(0.1);
+        new.routing.path_warming_amounts = Some(vec![10, 100]);
+        new.routing.retain_path_count = Some(3);
+        new.orca
+            .mints
+            .push("So11111111111111111111111111111111111111112".to_string());
+        // dexes can't be toggled live
+        new.raydium.enabled = !old.raydium.enabled;
+
+        let changes = old.changes(&new);
+        assert_eq!(
+            vec!["routing.path_warming_amounts", "routing.overquote"],
+            changes.live
+        );
+        assert_eq!(
+            vec!["routing.retain_path_count", "orca", "raydium"],
+            changes.restart_required
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use itertools::Itertools;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::address_lookup_table::instruction::{create_lookup_table, extend_lookup_table};
+use solana_sdk::address_lookup_table::state::{
+    AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES, LOOKUP_TABLE_META_SIZE,
+};
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::clock::Slot;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashSet;
+use tracing::info;
+
+// LookupTableMeta: enum tag (u32), deactivation_slot, last_extended_slot,
+// last_extended_slot_start_index (u8), then the authority as an Option<Pubkey>
+const AUTHORITY_OFFSET: usize = 4 + 8 + 8 + 1 + 1;
+
+/// Addresses added per transaction, more would not fit
+const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+
+/// Fee of the single signature transactions sent
+const TX_FEE_LAMPORTS: u64 = 5_000;
+
+/// Bounds the tables `add_accounts` creates and what the payer spends on them
+pub struct AltLimits {
+    /// Tables owned by the payer, no table is created past this count
+    pub max_tables: usize,
+    /// Balance left to the payer, a transaction whose rent and fee would go below it is not sent
+    pub min_payer_balance: Option<u64>,
+}
+
+impl Default for AltLimits {
+    fn default() -> Self {
+        Self {
+            max_tables: usize::MAX,
+            min_payer_balance: None,
+        }
+    }
+}
+
+/// Active lookup tables that can be extended by `authority`
+pub async fn fetch_managed_tables(
+    rpc_client: &RpcClient,
+    authority: &Pubkey,
+) -> anyhow::Result<Vec<AddressLookupTableAccount>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            AUTHORITY_OFFSET,
+            authority.to_bytes().to_vec(),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let accounts = rpc_client
+        .get_program_accounts_with_config(&solana_sdk::address_lookup_table::program::id(), config)
+        .await?;
+
+    let tables = accounts
+        .iter()
+        .filter_map(|(key, account)| {
+            let table = AddressLookupTable::deserialize(&account.data).ok()?;
+            if table.meta.deactivation_slot != Slot::MAX || table.meta.authority != Some(*authority)
+            {
+                return None;
+            }
+            Some(AddressLookupTableAccount {
+                key: *key,
+                addresses: table.addresses.to_vec(),
+            })
+        })
+        .sorted_by_key(|table| table.key)
+        .collect();
+
+    Ok(tables)
+}
+
+/// Accounts found in none of the tables, without duplicates
+pub fn missing_accounts(
+    tables: &[AddressLookupTableAccount],
+    accounts: impl IntoIterator<Item = Pubkey>,
+) -> Vec<Pubkey> {
+    let known: HashSet<&Pubkey> = tables.iter().flat_map(|x| x.addresses.iter()).collect();
+    accounts
+        .into_iter()
+        .filter(|x| !known.contains(x))
+        .unique()
+        .collect()
+}
+
+/// Add the accounts missing from `tables`, filling tables with free slots first and
+/// creating new ones owned by `payer` when they are all full, within `limits`.
+/// `tables` is updated as transactions confirm, the created or extended table keys are returned.
+pub async fn add_accounts(
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    tables: &mut Vec<AddressLookupTableAccount>,
+    accounts: Vec<Pubkey>,
+    limits: &AltLimits,
+) -> anyhow::Result<Vec<Pubkey>> {
+    let missing = missing_accounts(tables, accounts);
+    let mut touched = vec![];
+
+    let mut remaining = missing.as_slice();
+    while !remaining.is_empty() {
+        let index = match tables
+            .iter()
+            .position(|x| x.addresses.len() < LOOKUP_TABLE_MAX_ADDRESSES)
+        {
+            Some(index) => index,
+            None => {
+                if tables.len() >= limits.max_tables {
+                    anyhow::bail!(
+                        "max table count {} reached, {} account(s) not added",
+                        limits.max_tables,
+                        remaining.len()
+                    );
+                }
+                check_payer_balance(rpc_client, payer, limits, table_rent(0)).await?;
+                let key = create_table(rpc_client, payer).await?;
+                tables.push(AddressLookupTableAccount {
+                    key,
+                    addresses: vec![],
+                });
+                tables.len() - 1
+            }
+        };
+
+        let table = &mut tables[index];
+        let count = remaining
+            .len()
+            .min(LOOKUP_TABLE_MAX_ADDRESSES - table.addresses.len())
+            .min(MAX_ADDRESSES_PER_EXTEND);
+        let (chunk, rest) = remaining.split_at(count);
+
+        let rent = table_rent(table.addresses.len() + count) - table_rent(table.addresses.len());
+        check_payer_balance(rpc_client, payer, limits, rent).await?;
+        let ix = extend_lookup_table(
+            table.key,
+            payer.pubkey(),
+            Some(payer.pubkey()),
+            chunk.to_vec(),
+        );
+        let sig = send_ix(rpc_client, ix, payer).await?;
+        info!(
+            "Added {} account(s) to lookup table {}: {}",
+            chunk.len(),
+            table.key,
+            sig
+        );
+
+        table.addresses.extend_from_slice(chunk);
+        if !touched.contains(&table.key) {
+            touched.push(table.key);
+        }
+        remaining = rest;
+    }
+
+    Ok(touched)
+}
+
+fn table_rent(address_count: usize) -> u64 {
+    Rent::default().minimum_balance(LOOKUP_TABLE_META_SIZE + address_count * 32)
+}
+
+async fn check_payer_balance(
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    limits: &AltLimits,
+    rent: u64,
+) -> anyhow::Result<()> {
+    let Some(min_payer_balance) = limits.min_payer_balance else {
+        return Ok(());
+    };
+    let balance = rpc_client.get_balance(&payer.pubkey()).await?;
+    let cost = rent + TX_FEE_LAMPORTS;
+    if balance < min_payer_balance.saturating_add(cost) {
+        anyhow::bail!(
+            "payer balance {} would go below {} lamports",
+            balance.saturating_sub(cost),
+            min_payer_balance
+        );
+    }
+    Ok(())
+}
+
+async fn create_table(rpc_client: &RpcClient, payer: &Keypair) -> anyhow::Result<Pubkey> {
+    // the table address is derived from a slot that must be in the SlotHashes sysvar
+    let recent_slot = rpc_client
+        .get_slot_with_commitment(CommitmentConfig::finalized())
+        .await?;
+    let (ix, key) = create_lookup_table(payer.pubkey(), payer.pubkey(), recent_slot);
+    let sig = send_ix(rpc_client, ix, payer).await?;
+    info!("Created lookup table {}: {}", key, sig);
+    Ok(key)
+}
+
+async fn send_ix(
+    rpc_client: &RpcClient,
+    ix: Instruction,
+    payer: &Keypair,
+) -> anyhow::Result<Signature> {
+    let latest_blockhash = rpc_client.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[payer],
+        latest_blockhash,
+    );
+    let sig = rpc_client.send_and_confirm_transaction(&tx).await?;
+    Ok(sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_return_accounts_missing_from_all_tables() {
+        let addr = (0..5).map(|_| Pubkey::new_unique()).collect_vec();
+        let tables = vec![
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![addr[0], addr[1]],
+            },
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![addr[2]],
+            },
+        ];
+
+        let missing = missing_accounts(&tables, [addr[3], addr[0], addr[4], addr[2], addr[3]]);
+
+        assert_eq!(vec![addr[3], addr[4]], missing);
+    }
+}
@@ -1,9 +1,13 @@
 use crate::chain_data::ChainDataArcRw;
+use anyhow::Context;
 use mango_feeds_connector::chain_data::AccountData;
 use router_feed_lib::router_rpc_client::RouterRpcClient;
 use serde_derive::{Deserialize, Serialize};
+use solana_sdk::account::ReadableAccount;
+use solana_sdk::clock::Clock;
 use solana_sdk::instruction::Instruction;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar::SysvarId;
 use std::any::Any;
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
@@ -128,6 +132,22 @@ pub trait DexEdge {
 pub trait AccountProvider: Sync + Send {
     fn account(&self, address: &Pubkey) -> anyhow::Result<AccountData>;
     fn newest_processed_slot(&self) -> u64;
+
+    /// On-chain time, to be used by adapters instead of the local clock
+    /// so that quotes match what the program will see when executing.
+    /// The router always subscribes to the Clock sysvar.
+    fn clock(&self) -> anyhow::Result<Clock> {
+        let clock = self.account(&Clock::id()).context("read clock")?;
+        Ok(clock.account.deserialize_data::<Clock>()?)
+    }
+
+    /// Token program owning the mint (spl-token or spl-token-2022), to derive user ATAs.
+    /// Mints are usually not subscribed: the router overrides this with its token cache,
+    /// unknown mints fall back to spl-token.
+    fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.account(mint)
+            .map_or(anchor_spl::token::ID, |x| *x.account.owner())
+    }
 }
 
 pub struct ChainDataAccountProvider {
@@ -190,6 +210,21 @@ pub trait DexInterface: Sync + Send {
     /// to quote a trade from input mint to output mint.
     fn edges_per_pk(&self) -> HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>;
 
+    /// Called (when pool discovery is enabled) for updates of accounts owned
+    /// by one of the subscribed programs that are not referenced by any known
+    /// edge, typically a pool created after initialize(). Returns the same
+    /// kind of mapping as edges_per_pk() for the new edges, or an empty map if
+    /// the account is not a pool.
+    /// Called on the account update path, so unrelated accounts must be
+    /// rejected cheaply (size, discriminator) before any deserialization.
+    fn discover_edges(
+        &self,
+        _pk: &Pubkey,
+        _chain_data: &AccountProviderView,
+    ) -> anyhow::Result<HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>>> {
+        Ok(HashMap::new())
+    }
+
     /// Defines the programs that should be included in a snapshot for
     /// simulation tests.
     fn program_ids(&self) -> HashSet<Pubkey>;
@@ -1,8 +1,10 @@
+pub mod alt_manager;
 pub mod chain_data;
 pub mod dex;
 pub mod mango;
 pub mod model;
 pub mod price_feeds;
+pub mod referral;
 pub mod retry_counter;
 pub mod router_client;
 pub mod test_tools;
@@ -15,4 +15,10 @@ pub struct QuoteRequest {
     pub swap_mode: Option<SwapMode>,
     // mango UI uses mode and jupiter supports it so we add a support for it too.
     pub mode: Option<SwapMode>,
+    /// Fee taken on the output token, the quoted out amount is net of it
+    pub platform_fee_bps: Option<u16>,
+    /// Comma separated list of dexes the route may use, all when not set
+    pub dexes: Option<String>,
+    /// Comma separated list of dexes the route must not use
+    pub exclude_dexes: Option<String>,
 }
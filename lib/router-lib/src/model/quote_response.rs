@@ -17,6 +17,9 @@ pub struct QuoteResponse {
     pub accounts: Option<Vec<QuoteAccount>>,
     pub context_slot: u64,
     pub time_taken: f64,
+    /// HMAC of the quote, checked by the router when rebuilding the route for a swap
+    #[serde(default)]
+    pub quote_token: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -21,6 +21,8 @@ pub struct SwapRequest {
     #[serde(default)]
     pub use_shared_accounts: bool,
     pub fee_account: Option<String>,
+    /// Wallet of the referrer sharing the platform fee, its referral vault must exist
+    pub referrer: Option<String>,
     pub compute_unit_price_micro_lamports: Option<u64>,
     #[serde(default)]
     pub as_legacy_transaction: bool,
@@ -1,20 +1,24 @@
 use crate::price_feeds::birdeye::BirdeyePriceFeed;
 use crate::price_feeds::birdeye_single::BirdeyeSinglePriceFeed;
 use crate::price_feeds::fillcity::FillCityPriceFeed;
+use crate::price_feeds::onchain::{OnChainPriceFeed, OnChainPriceSource};
 use crate::price_feeds::price_feed::{PriceFeed, PriceUpdate};
 use itertools::Itertools;
 use router_config_lib::PriceFeedConfig;
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::broadcast;
 use tokio::sync::broadcast::Sender;
+use tokio::sync::watch;
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
 pub struct CompositePriceFeed {
     admin_channel_sender: async_channel::Sender<Pubkey>,
     update_sender: broadcast::Sender<PriceUpdate>,
+    source_sender: watch::Sender<Option<Arc<OnChainPriceSource>>>,
 }
 
 impl PriceFeed for CompositePriceFeed {
@@ -27,6 +31,9 @@ impl PriceFeed for CompositePriceFeed {
         let refresh_interval = Duration::from_secs(configuration.refresh_interval_secs);
         let token = configuration.birdeye_token;
         let birdeye_single_mode = configuration.birdeye_single_mode.unwrap_or(false);
+        // mints without an external price get one derived from chain data
+        let onchain_fallback = configuration.onchain_fallback.unwrap_or(false);
+        let (source_sender, source_receiver) = watch::channel(None);
 
         let update_sender_clone = update_sender.clone();
         let join_handle = tokio::spawn(async move {
@@ -50,7 +57,8 @@ impl PriceFeed for CompositePriceFeed {
                     _ = interval.tick() => {
                         debug!("Refreshing price for {} mint(s)", mints.len());
 
-                        let res = CompositePriceFeed::refresh(&token, birdeye_single_mode, &mints, update_sender_clone.clone()).await;
+                        let source = onchain_fallback.then(|| source_receiver.borrow().clone()).flatten();
+                        let res = CompositePriceFeed::refresh(&token, birdeye_single_mode, source, &mints, update_sender_clone.clone()).await;
                         if res.is_err() {
                             error!("Price feed error: {}", res.unwrap_err())
                         }
@@ -64,6 +72,7 @@ impl PriceFeed for CompositePriceFeed {
         let feed = CompositePriceFeed {
             admin_channel_sender,
             update_sender,
+            source_sender,
         };
 
         (feed, join_handle)
@@ -76,12 +85,17 @@ impl PriceFeed for CompositePriceFeed {
     fn register_mint_sender(&self) -> async_channel::Sender<Pubkey> {
         self.admin_channel_sender.clone()
     }
+
+    fn set_onchain_source(&self, source: OnChainPriceSource) {
+        self.source_sender.send_replace(Some(Arc::new(source)));
+    }
 }
 
 impl CompositePriceFeed {
     async fn refresh(
         birdeye_token: &String,
         birdeye_single_mode: bool,
+        onchain_source: Option<Arc<OnChainPriceSource>>,
         mints: &HashSet<Pubkey>,
         sender: broadcast::Sender<PriceUpdate>,
     ) -> anyhow::Result<()> {
@@ -89,7 +103,14 @@ impl CompositePriceFeed {
         for chunk in mints.chunks(10_000) {
             let chunk = chunk.iter().copied().collect();
 
-            Self::refresh_chunk(birdeye_token, birdeye_single_mode, &chunk, sender.clone()).await?;
+            Self::refresh_chunk(
+                birdeye_token,
+                birdeye_single_mode,
+                onchain_source.clone(),
+                &chunk,
+                sender.clone(),
+            )
+            .await?;
         }
 
         Ok(())
@@ -98,6 +119,7 @@ impl CompositePriceFeed {
     async fn refresh_chunk(
         birdeye_token: &String,
         birdeye_single_mode: bool,
+        onchain_source: Option<Arc<OnChainPriceSource>>,
         mints: &HashSet<Pubkey>,
         sender: Sender<PriceUpdate>,
     ) -> anyhow::Result<()> {
@@ -109,7 +131,7 @@ impl CompositePriceFeed {
         let mints =
             Self::handle_source_results(mints, sender.clone(), &mut local_receiver, result).await?;
 
-        if !mints.is_empty() {
+        let mints = if !mints.is_empty() {
             info!("Querying price with birdeye for {} mints", mints.len());
 
             let result = if birdeye_single_mode {
@@ -123,6 +145,17 @@ impl CompositePriceFeed {
                 BirdeyePriceFeed::refresh(birdeye_token.to_string(), &mints, local_sender.clone())
                     .await
             };
+            Self::handle_source_results(&mints, sender.clone(), &mut local_receiver, result).await?
+        } else {
+            mints
+        };
+
+        if let Some(source) = onchain_source.filter(|_| !mints.is_empty()) {
+            info!("Deriving onchain price for {} mints", mints.len());
+
+            let result = OnChainPriceFeed::refresh(Some(source), &mints, local_sender.clone())
+                .await
+                .map(|_| ());
             Self::handle_source_results(&mints, sender.clone(), &mut local_receiver, result)
                 .await?;
         }
@@ -2,5 +2,7 @@ pub mod birdeye;
 pub mod birdeye_single;
 pub mod composite;
 pub mod fillcity;
+pub mod onchain;
+pub mod oracle;
 pub mod price_cache;
 pub mod price_feed;
@@ -0,0 +1,496 @@
+use crate::dex::{AccountProviderView, DexEdgeIdentifier, DexInterface};
+use crate::price_feeds::price_feed::{PriceFeed, PriceUpdate};
+use router_config_lib::PriceFeedConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+
+/// Mints considered to be worth exactly 1 USD, prices are derived from them
+const USD_MINTS: [&str; 2] = [
+    "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", // USDC
+    "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", // USDT
+];
+
+/// Value quoted through each pool to get its rate
+const REFERENCE_AMOUNT_USD: f64 = 1_000.0;
+
+/// Depth is probed by doubling the reference amount, up to REFERENCE_AMOUNT_USD * 2^10
+const MAX_DEPTH_DOUBLINGS: usize = 10;
+
+/// Rate degradation, relative to the reference rate, above which a probe is out of the pool depth
+const MAX_DEPTH_PRICE_IMPACT: f64 = 0.01;
+
+/// Max number of pools between a USD mint and a priced mint
+const MAX_HOPS: usize = 3;
+
+/// Chain data is still loading when the source is set
+const STARTUP_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Pools and token decimals of the routing graph, read on every refresh
+/// so that pools discovered after startup are used to derive prices
+pub trait PriceGraphProvider: Sync + Send {
+    fn edges(&self) -> Vec<(Arc<dyn DexInterface>, Arc<dyn DexEdgeIdentifier>)>;
+    fn decimals(&self) -> HashMap<Pubkey, u8>;
+}
+
+/// Pools the prices are derived from
+pub struct OnChainPriceSource {
+    pub chain_data: AccountProviderView,
+    pub graph: Arc<dyn PriceGraphProvider>,
+}
+
+/// Derives USD prices from the router's own pools, walking from USDC/USDT through the
+/// deepest pools. Nothing is published until a source is set.
+pub struct OnChainPriceFeed {
+    admin_channel_sender: async_channel::Sender<Pubkey>,
+    update_sender: broadcast::Sender<PriceUpdate>,
+    source_sender: watch::Sender<Option<Arc<OnChainPriceSource>>>,
+}
+
+impl PriceFeed for OnChainPriceFeed {
+    fn start(
+        configuration: PriceFeedConfig,
+        mut exit: Receiver<()>,
+    ) -> (impl PriceFeed, JoinHandle<()>) {
+        info!("Starting onchain price feed..");
+        let (admin_channel_sender, admin_channel_receiver) = async_channel::unbounded::<Pubkey>();
+        let (update_sender, _) = broadcast::channel::<PriceUpdate>(10_000);
+        let (source_sender, mut source_receiver) = watch::channel(None);
+        let refresh_interval = Duration::from_secs(configuration.refresh_interval_secs);
+
+        let update_sender_clone = update_sender.clone();
+        let join_handle = tokio::spawn(async move {
+            let mut mints = HashSet::<Pubkey>::new();
+            let mut interval = tokio::time::interval(refresh_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            interval.tick().await;
+
+            loop {
+                tokio::select! {
+                    _ = exit.recv() => {
+                        info!("Exit signal received, stopping onchain price feed..");
+                        break;
+                    },
+                    Ok(new_mint) = admin_channel_receiver.recv() => {
+                        if mints.insert(new_mint) {
+                            debug!("Adding {} to price feed subscriptions", new_mint);
+                            interval.reset_after(Duration::from_millis(100));
+                        }
+                    },
+                    Ok(_) = source_receiver.changed() => {
+                        interval.reset_after(Duration::from_millis(100));
+                    },
+                    _ = interval.tick() => {
+                        debug!("Refreshing price for {} mint(s)", mints.len());
+
+                        let source = source_receiver.borrow().clone();
+                        match OnChainPriceFeed::refresh(source, &mints, update_sender_clone.clone()).await {
+                            // pools are not loaded yet on startup, retry sooner
+                            Ok(0) if !mints.is_empty() => interval.reset_after(STARTUP_RETRY_INTERVAL),
+                            Ok(_) => {}
+                            Err(e) => error!("Price feed error: {}", e),
+                        }
+                    }
+                }
+            }
+
+            info!("onchain price feed exited")
+        });
+
+        let feed = OnChainPriceFeed {
+            admin_channel_sender,
+            update_sender,
+            source_sender,
+        };
+
+        (feed, join_handle)
+    }
+
+    fn receiver(&mut self) -> Receiver<PriceUpdate> {
+        self.update_sender.subscribe()
+    }
+
+    fn register_mint_sender(&self) -> async_channel::Sender<Pubkey> {
+        self.admin_channel_sender.clone()
+    }
+
+    fn set_onchain_source(&self, source: OnChainPriceSource) {
+        self.source_sender.send_replace(Some(Arc::new(source)));
+    }
+}
+
+impl OnChainPriceFeed {
+    /// Returns the number of prices sent
+    pub async fn refresh(
+        source: Option<Arc<OnChainPriceSource>>,
+        mints: &HashSet<Pubkey>,
+        sender: broadcast::Sender<PriceUpdate>,
+    ) -> anyhow::Result<usize> {
+        let Some(source) = source else {
+            debug!("No onchain price source yet, skipping refresh");
+            return Ok(0);
+        };
+
+        // loading and quoting every pool is cpu bound
+        let mints = mints.clone();
+        let prices = tokio::task::spawn_blocking(move || derive_prices(&source, &mints)).await?;
+
+        let count = prices.len();
+        for res in prices {
+            if sender.receiver_count() > 0 {
+                sender.send(res)?;
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+/// Breadth first walk from the USD mints: at each hop, every pool going out of a priced mint
+/// is quoted with REFERENCE_AMOUNT_USD and a newly reached mint takes the price given
+/// by the deepest pool. Picking the best output instead would let a thin pool with a
+/// skewed price set the price.
+pub fn derive_prices(source: &OnChainPriceSource, mints: &HashSet<Pubkey>) -> Vec<PriceUpdate> {
+    let edges = source.graph.edges();
+    let decimals = source.graph.decimals();
+
+    let mut edges_per_input_mint = HashMap::<Pubkey, Vec<_>>::new();
+    for (dex, id) in &edges {
+        edges_per_input_mint
+            .entry(id.input_mint())
+            .or_default()
+            .push((dex, id));
+    }
+
+    let mut prices: HashMap<Pubkey, f64> = USD_MINTS
+        .iter()
+        .map(|m| (Pubkey::from_str(m).unwrap(), 1.0))
+        .collect();
+    let mut frontier = prices.keys().copied().collect::<Vec<_>>();
+
+    for _ in 0..MAX_HOPS {
+        if frontier.is_empty() || mints.iter().all(|m| prices.contains_key(m)) {
+            break;
+        }
+
+        // (depth, output) in ui amount of the deepest pool, per newly reached mint
+        let mut deepest = HashMap::<Pubkey, (f64, f64)>::new();
+        for input_mint in &frontier {
+            let Some(input_decimals) = decimals.get(input_mint) else {
+                continue;
+            };
+            let in_amount = (REFERENCE_AMOUNT_USD / prices[input_mint]
+                * 10f64.powi(*input_decimals as i32))
+            .round() as u64;
+            if in_amount == 0 {
+                continue;
+            }
+
+            for (dex, id) in edges_per_input_mint.get(input_mint).into_iter().flatten() {
+                let output_mint = id.output_mint();
+                if prices.contains_key(&output_mint) {
+                    continue;
+                }
+                let Some(output_decimals) = decimals.get(&output_mint) else {
+                    continue;
+                };
+
+                let Ok(edge) = dex.load(id, &source.chain_data) else {
+                    debug!("Failed to load {} for price", id.desc());
+                    continue;
+                };
+                let quote = |amount| {
+                    dex.quote(id, &edge, &source.chain_data, amount)
+                        .map(|q| q.out_amount)
+                        .unwrap_or(0)
+                };
+                let out_amount = quote(in_amount);
+                if out_amount == 0 {
+                    continue;
+                }
+
+                let depth = pool_depth(in_amount, out_amount, quote);
+                let scale = 10f64.powi(*output_decimals as i32);
+                let (out_ui, depth_ui) = (out_amount as f64 / scale, depth as f64 / scale);
+                let best = deepest.entry(output_mint).or_default();
+                if (depth_ui, out_ui) > *best {
+                    *best = (depth_ui, out_ui);
+                }
+            }
+        }
+
+        frontier = deepest.keys().copied().collect();
+        for (mint, (_, out_ui)) in deepest {
+            prices.insert(mint, REFERENCE_AMOUNT_USD / out_ui);
+        }
+    }
+
+    mints
+        .iter()
+        .filter_map(|mint| {
+            let price = prices.get(mint)?;
+            debug!(" - price derived for {} -> {}", mint, price);
+            Some(PriceUpdate {
+                mint: *mint,
+                price: *price,
+            })
+        })
+        .collect()
+}
+
+/// Output of the largest input, doubling from the reference amount, that the pool fills
+/// within MAX_DEPTH_PRICE_IMPACT of the reference rate
+fn pool_depth(in_amount: u64, out_amount: u64, quote: impl Fn(u64) -> u64) -> u64 {
+    let reference_rate = out_amount as f64 / in_amount as f64;
+    let (mut probe_in, mut depth) = (in_amount, out_amount);
+    for _ in 0..MAX_DEPTH_DOUBLINGS {
+        probe_in = probe_in.saturating_mul(2);
+        let probe_out = quote(probe_in);
+        let min_out = reference_rate * probe_in as f64 * (1.0 - MAX_DEPTH_PRICE_IMPACT);
+        if (probe_out as f64) < min_out {
+            break;
+        }
+        depth = probe_out;
+    }
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain_data::ChainDataArcRw;
+    use crate::dex::{
+        ChainDataAccountProvider, DexEdge, DexSubscriptionMode, Quote, SwapInstruction,
+    };
+    use router_feed_lib::router_rpc_client::RouterRpcClient;
+    use std::any::Any;
+    use std::sync::RwLock;
+
+    struct MockId {
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        price: f64,
+        /// Simulated pool depth, in input native units
+        depth: f64,
+    }
+
+    impl DexEdgeIdentifier for MockId {
+        fn key(&self) -> Pubkey {
+            Pubkey::default()
+        }
+
+        fn desc(&self) -> String {
+            format!("{} - {}", self.input_mint, self.output_mint)
+        }
+
+        fn input_mint(&self) -> Pubkey {
+            self.input_mint
+        }
+
+        fn output_mint(&self) -> Pubkey {
+            self.output_mint
+        }
+
+        fn accounts_needed(&self) -> usize {
+            0
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    struct MockEdge {}
+
+    impl DexEdge for MockEdge {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    struct MockDex {}
+
+    #[async_trait::async_trait]
+    impl DexInterface for MockDex {
+        async fn initialize(
+            _rpc: &mut RouterRpcClient,
+            _options: HashMap<String, String>,
+        ) -> anyhow::Result<Arc<dyn DexInterface>>
+        where
+            Self: Sized,
+        {
+            Ok(Arc::new(MockDex {}))
+        }
+
+        fn name(&self) -> String {
+            "Mock".to_string()
+        }
+
+        fn subscription_mode(&self) -> DexSubscriptionMode {
+            DexSubscriptionMode::Disabled
+        }
+
+        fn edges_per_pk(&self) -> HashMap<Pubkey, Vec<Arc<dyn DexEdgeIdentifier>>> {
+            HashMap::new()
+        }
+
+        fn program_ids(&self) -> HashSet<Pubkey> {
+            HashSet::new()
+        }
+
+        fn load(
+            &self,
+            _id: &Arc<dyn DexEdgeIdentifier>,
+            _chain_data: &AccountProviderView,
+        ) -> anyhow::Result<Arc<dyn DexEdge>> {
+            Ok(Arc::new(MockEdge {}))
+        }
+
+        fn quote(
+            &self,
+            id: &Arc<dyn DexEdgeIdentifier>,
+            _edge: &Arc<dyn DexEdge>,
+            _chain_data: &AccountProviderView,
+            in_amount: u64,
+        ) -> anyhow::Result<Quote> {
+            let id = id.as_any().downcast_ref::<MockId>().unwrap();
+            let impact = 1.0 + in_amount as f64 / id.depth;
+            Ok(Quote {
+                in_amount,
+                out_amount: (id.price * in_amount as f64 / impact) as u64,
+                fee_amount: 0,
+                fee_mint: id.input_mint,
+            })
+        }
+
+        fn quote_exact_out(
+            &self,
+            _id: &Arc<dyn DexEdgeIdentifier>,
+            _edge: &Arc<dyn DexEdge>,
+            _chain_data: &AccountProviderView,
+            _out_amount: u64,
+        ) -> anyhow::Result<Quote> {
+            anyhow::bail!("exact out not supported")
+        }
+
+        fn supports_exact_out(&self, _id: &Arc<dyn DexEdgeIdentifier>) -> bool {
+            false
+        }
+
+        fn build_swap_ix(
+            &self,
+            _id: &Arc<dyn DexEdgeIdentifier>,
+            _chain_data: &AccountProviderView,
+            _wallet_pk: &Pubkey,
+            _in_amount: u64,
+            _out_amount: u64,
+            _max_slippage_bps: i32,
+        ) -> anyhow::Result<SwapInstruction> {
+            anyhow::bail!("mock pools can't be swapped")
+        }
+    }
+
+    #[derive(Default)]
+    struct MockGraph {
+        edges: RwLock<Vec<(Arc<dyn DexInterface>, Arc<dyn DexEdgeIdentifier>)>>,
+        decimals: HashMap<Pubkey, u8>,
+    }
+
+    impl MockGraph {
+        fn add_pool(&self, input_mint: Pubkey, output_mint: Pubkey, price: f64, depth: f64) {
+            self.edges.write().unwrap().push((
+                Arc::new(MockDex {}),
+                Arc::new(MockId {
+                    input_mint,
+                    output_mint,
+                    price,
+                    depth,
+                }),
+            ));
+        }
+    }
+
+    impl PriceGraphProvider for MockGraph {
+        fn edges(&self) -> Vec<(Arc<dyn DexInterface>, Arc<dyn DexEdgeIdentifier>)> {
+            self.edges.read().unwrap().clone()
+        }
+
+        fn decimals(&self) -> HashMap<Pubkey, u8> {
+            self.decimals.clone()
+        }
+    }
+
+    fn make_source(graph: Arc<MockGraph>) -> OnChainPriceSource {
+        OnChainPriceSource {
+            chain_data: Arc::new(ChainDataAccountProvider::new(ChainDataArcRw::new(
+                Default::default(),
+            ))),
+            graph,
+        }
+    }
+
+    fn price(prices: &[PriceUpdate], mint: &Pubkey) -> Option<f64> {
+        prices.iter().find(|p| p.mint == *mint).map(|p| p.price)
+    }
+
+    #[test]
+    fn should_price_from_the_deepest_pool() {
+        let usdc = Pubkey::from_str(USD_MINTS[0]).unwrap();
+        let mint = Pubkey::new_unique();
+        let graph = Arc::new(MockGraph {
+            decimals: HashMap::from([(usdc, 6), (mint, 6)]),
+            ..MockGraph::default()
+        });
+        // thin pool with a skewed price gives the best output for the reference amount
+        graph.add_pool(usdc, mint, 2.0, 1e10);
+        graph.add_pool(usdc, mint, 1.0, 1e15);
+
+        let prices = derive_prices(&make_source(graph), &HashSet::from([mint]));
+
+        let price = price(&prices, &mint).unwrap();
+        assert!((price - 1.0).abs() < 0.001, "price {price}");
+    }
+
+    #[test]
+    fn should_use_pools_added_to_the_graph() {
+        let usdc = Pubkey::from_str(USD_MINTS[0]).unwrap();
+        let mint = Pubkey::new_unique();
+        let graph = Arc::new(MockGraph {
+            decimals: HashMap::from([(usdc, 6), (mint, 9)]),
+            ..MockGraph::default()
+        });
+        let source = make_source(graph.clone());
+
+        assert!(derive_prices(&source, &HashSet::from([mint])).is_empty());
+
+        // 1 mint (1e9 native) for 4 USDC (4e6 native)
+        graph.add_pool(usdc, mint, 250.0, 1e15);
+        let prices = derive_prices(&source, &HashSet::from([mint]));
+
+        let price = price(&prices, &mint).unwrap();
+        assert!((price - 4.0).abs() < 0.001, "price {price}");
+    }
+
+    #[test]
+    fn should_price_through_usdt() {
+        let usdt = Pubkey::from_str("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB").unwrap();
+        let mint = Pubkey::new_unique();
+        let graph = Arc::new(MockGraph {
+            decimals: HashMap::from([(usdt, 6), (mint, 6)]),
+            ..MockGraph::default()
+        });
+        // 1 mint for 2 USDT
+        graph.add_pool(usdt, mint, 0.5, 1e15);
+
+        let prices = derive_prices(&make_source(graph), &HashSet::from([mint]));
+
+        let price = price(&prices, &mint).unwrap();
+        assert!((price - 2.0).abs() < 0.001, "price {price}");
+    }
+}
@@ -0,0 +1,277 @@
+use crate::dex::AccountProviderView;
+use crate::price_feeds::price_feed::{PriceFeed, PriceUpdate};
+use anyhow::Context;
+use router_config_lib::PriceFeedConfig;
+use solana_sdk::account::ReadableAccount;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+pub const PYTH_RECEIVER_PROGRAM: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM: Pubkey =
+    pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+// PriceUpdateV2: discriminator, write_authority, then a borsh VerificationLevel enum
+// which is 2 bytes when Partial and 1 byte when Full, followed by the PriceFeedMessage
+const PYTH_VERIFICATION_LEVEL_OFFSET: usize = 8 + 32;
+// PriceFeedMessage: feed_id, price (i64), conf (u64), exponent (i32), ...
+const PYTH_PRICE_OFFSET_IN_MESSAGE: usize = 32;
+const PYTH_EXPONENT_OFFSET_IN_MESSAGE: usize = 32 + 8 + 8;
+
+// PullFeedAccountData: discriminator, 32 submissions of 64 bytes, then the feed settings,
+// CurrentResult starts with the median value as an i128 with 18 decimals
+const SWITCHBOARD_RESULT_VALUE_OFFSET: usize = 2264;
+const SWITCHBOARD_DECIMALS: i32 = 18;
+
+/// Where the oracle feed reads accounts from, the updates are (account, owner, slot)
+pub struct OracleAccountSource {
+    pub chain_data: AccountProviderView,
+    pub account_updates: broadcast::Receiver<(Pubkey, Pubkey, u64)>,
+}
+
+/// Publishes a USD price on every write of a Pyth pull oracle or Switchboard on-demand
+/// feed account, mints are mapped to oracles by the `oracles` config.
+/// Nothing is published until an account source is set.
+pub struct OraclePriceFeed {
+    admin_channel_sender: async_channel::Sender<Pubkey>,
+    update_sender: broadcast::Sender<PriceUpdate>,
+    source_sender: Option<oneshot::Sender<OracleAccountSource>>,
+    oracle_accounts: HashSet<Pubkey>,
+}
+
+impl PriceFeed for OraclePriceFeed {
+    fn start(
+        configuration: PriceFeedConfig,
+        exit: Receiver<()>,
+    ) -> (impl PriceFeed, JoinHandle<()>) {
+        Self::spawn(configuration, exit)
+    }
+
+    fn receiver(&mut self) -> Receiver<PriceUpdate> {
+        self.update_sender.subscribe()
+    }
+
+    fn register_mint_sender(&self) -> async_channel::Sender<Pubkey> {
+        self.admin_channel_sender.clone()
+    }
+}
+
+impl OraclePriceFeed {
+    /// Same as start() but the concrete type is returned, to be able to set the account source
+    pub fn spawn(
+        configuration: PriceFeedConfig,
+        mut exit: Receiver<()>,
+    ) -> (OraclePriceFeed, JoinHandle<()>) {
+        info!("Starting oracle price feed..");
+        let (admin_channel_sender, admin_channel_receiver) = async_channel::unbounded::<Pubkey>();
+        let (update_sender, _) = broadcast::channel::<PriceUpdate>(10_000);
+        let (source_sender, source_receiver) = oneshot::channel::<OracleAccountSource>();
+
+        let mut mints_per_oracle = HashMap::<Pubkey, Vec<Pubkey>>::new();
+        for oracle_config in configuration.oracles.iter().flatten() {
+            let mint = Pubkey::from_str(&oracle_config.mint).unwrap();
+            let oracle = Pubkey::from_str(&oracle_config.oracle).unwrap();
+            mints_per_oracle.entry(oracle).or_default().push(mint);
+        }
+        let oracle_accounts = mints_per_oracle.keys().copied().collect();
+
+        let update_sender_clone = update_sender.clone();
+        let join_handle = tokio::spawn(async move {
+            let source = tokio::select! {
+                _ = exit.recv() => None,
+                source = source_receiver => source.ok(),
+            };
+            let Some(OracleAccountSource {
+                chain_data,
+                mut account_updates,
+            }) = source
+            else {
+                info!("oracle price feed exited before receiving an account source");
+                return;
+            };
+
+            // oracles already received before the source was set
+            for oracle in mints_per_oracle.keys() {
+                publish(&chain_data, oracle, &mints_per_oracle, &update_sender_clone);
+            }
+
+            loop {
+                tokio::select! {
+                    _ = exit.recv() => {
+                        info!("Exit signal received, stopping oracle price feed..");
+                        break;
+                    },
+                    Ok(mint) = admin_channel_receiver.recv() => {
+                        debug!("Ignoring price subscription for {}, oracles are set by config", mint);
+                    },
+                    res = account_updates.recv() => {
+                        match res {
+                            Ok((pk, _, _)) => {
+                                if mints_per_oracle.contains_key(&pk) {
+                                    publish(&chain_data, &pk, &mints_per_oracle, &update_sender_clone);
+                                }
+                            }
+                            Err(RecvError::Lagged(n)) => {
+                                warn!("lagged {n} on account update channel in oracle price feed");
+                            }
+                            Err(RecvError::Closed) => break,
+                        }
+                    }
+                }
+            }
+
+            info!("oracle price feed exited")
+        });
+
+        let feed = OraclePriceFeed {
+            admin_channel_sender,
+            update_sender,
+            source_sender: Some(source_sender),
+            oracle_accounts,
+        };
+
+        (feed, join_handle)
+    }
+
+    /// Accounts to subscribe to
+    pub fn oracle_accounts(&self) -> HashSet<Pubkey> {
+        self.oracle_accounts.clone()
+    }
+
+    /// Can only be set once
+    pub fn set_account_source(&mut self, source: OracleAccountSource) {
+        match self.source_sender.take() {
+            Some(sender) => {
+                let _ = sender.send(source);
+            }
+            None => warn!("Oracle price feed account source is already set"),
+        }
+    }
+}
+
+fn publish(
+    chain_data: &AccountProviderView,
+    oracle: &Pubkey,
+    mints_per_oracle: &HashMap<Pubkey, Vec<Pubkey>>,
+    sender: &broadcast::Sender<PriceUpdate>,
+) {
+    let Ok(account) = chain_data.account(oracle) else {
+        return;
+    };
+    let price = match decode_oracle_price(account.account.owner(), account.account.data()) {
+        Ok(price) => price,
+        Err(e) => {
+            debug!("Failed to decode oracle {}: {:?}", oracle, e);
+            return;
+        }
+    };
+
+    for mint in mints_per_oracle.get(oracle).into_iter().flatten() {
+        debug!(" - oracle price updated for {} -> {}", mint, price);
+        if sender.receiver_count() > 0 {
+            let _ = sender.send(PriceUpdate { mint: *mint, price });
+        }
+    }
+}
+
+/// Decodes a Pyth PriceUpdateV2 or a Switchboard on-demand PullFeedAccountData,
+/// depending on the owner program
+pub fn decode_oracle_price(owner: &Pubkey, data: &[u8]) -> anyhow::Result<f64> {
+    let price = if *owner == PYTH_RECEIVER_PROGRAM {
+        decode_pyth_price(data)?
+    } else if *owner == SWITCHBOARD_ON_DEMAND_PROGRAM {
+        decode_switchboard_price(data)?
+    } else {
+        anyhow::bail!("unsupported oracle owner {}", owner);
+    };
+
+    if !price.is_finite() || price <= 0.0 {
+        anyhow::bail!("invalid oracle price {}", price);
+    }
+    Ok(price)
+}
+
+fn decode_pyth_price(data: &[u8]) -> anyhow::Result<f64> {
+    let message_offset = match data.get(PYTH_VERIFICATION_LEVEL_OFFSET) {
+        Some(0) => PYTH_VERIFICATION_LEVEL_OFFSET + 2, // Partial { num_signatures: u8 }
+        Some(1) => PYTH_VERIFICATION_LEVEL_OFFSET + 1, // Full
+        _ => anyhow::bail!("invalid pyth verification level"),
+    };
+
+    let price = i64::from_le_bytes(read_bytes(
+        data,
+        message_offset + PYTH_PRICE_OFFSET_IN_MESSAGE,
+    )?);
+    let exponent = i32::from_le_bytes(read_bytes(
+        data,
+        message_offset + PYTH_EXPONENT_OFFSET_IN_MESSAGE,
+    )?);
+
+    Ok(price as f64 * 10f64.powi(exponent))
+}
+
+fn decode_switchboard_price(data: &[u8]) -> anyhow::Result<f64> {
+    let value = i128::from_le_bytes(read_bytes(data, SWITCHBOARD_RESULT_VALUE_OFFSET)?);
+    Ok(value as f64 / 10f64.powi(SWITCHBOARD_DECIMALS))
+}
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> anyhow::Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .context("oracle account too small")?
+        .try_into()
+        .context("oracle account too small")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_decode_pyth_price_with_full_verification() {
+        let mut data = vec![0u8; PYTH_VERIFICATION_LEVEL_OFFSET];
+        data.push(1); // Full
+        data.extend_from_slice(&[7u8; 32]); // feed id
+        data.extend_from_slice(&14_512_345_678i64.to_le_bytes());
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&(-8i32).to_le_bytes());
+        data.extend_from_slice(&[0u8; 40]);
+
+        let price = decode_oracle_price(&PYTH_RECEIVER_PROGRAM, &data).unwrap();
+        assert!((price - 145.12345678).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_decode_pyth_price_with_partial_verification() {
+        let mut data = vec![0u8; PYTH_VERIFICATION_LEVEL_OFFSET];
+        data.extend_from_slice(&[0, 3]); // Partial { num_signatures: 3 }
+        data.extend_from_slice(&[7u8; 32]);
+        data.extend_from_slice(&99_990_000i64.to_le_bytes());
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&(-8i32).to_le_bytes());
+
+        let price = decode_oracle_price(&PYTH_RECEIVER_PROGRAM, &data).unwrap();
+        assert!((price - 0.9999).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_decode_switchboard_price() {
+        let mut data = vec![0u8; SWITCHBOARD_RESULT_VALUE_OFFSET];
+        data.extend_from_slice(&(2_500_000_000_000_000_000i128).to_le_bytes());
+
+        let price = decode_oracle_price(&SWITCHBOARD_ON_DEMAND_PROGRAM, &data).unwrap();
+        assert!((price - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_reject_unknown_owner_and_short_accounts() {
+        assert!(decode_oracle_price(&Pubkey::new_unique(), &[0u8; 3000]).is_err());
+        assert!(decode_oracle_price(&PYTH_RECEIVER_PROGRAM, &[0u8; 20]).is_err());
+        assert!(decode_oracle_price(&SWITCHBOARD_ON_DEMAND_PROGRAM, &[0u8; 20]).is_err());
+    }
+}
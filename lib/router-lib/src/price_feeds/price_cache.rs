@@ -12,13 +12,26 @@ pub struct PriceCache {
 
 impl PriceCache {
     pub fn new(
+        exit: tokio::sync::broadcast::Receiver<()>,
+        receiver: tokio::sync::broadcast::Receiver<PriceUpdate>,
+    ) -> (PriceCache, JoinHandle<()>) {
+        let price_cache = PriceCache {
+            latest_prices: Arc::new(DashMap::new()),
+        };
+        let job = price_cache.add_feed(exit, receiver);
+
+        (price_cache, job)
+    }
+
+    /// Listen to the updates of another feed, the latest update of a mint wins
+    pub fn add_feed(
+        &self,
         mut exit: tokio::sync::broadcast::Receiver<()>,
         mut receiver: tokio::sync::broadcast::Receiver<PriceUpdate>,
-    ) -> (PriceCache, JoinHandle<()>) {
-        let latest_prices = Arc::new(DashMap::new());
-        let latest_prices_write = latest_prices.clone();
+    ) -> JoinHandle<()> {
+        let latest_prices_write = self.latest_prices.clone();
 
-        let job = tokio::spawn(async move {
+        tokio::spawn(async move {
             loop {
                 tokio::select! {
                     _ = exit.recv() => {
@@ -32,12 +45,22 @@ impl PriceCache {
             }
 
             info!("price cache exited")
-        });
-
-        (PriceCache { latest_prices }, job)
+        })
     }
 
     pub fn price_ui(&self, mint: Pubkey) -> Option<f64> {
         self.latest_prices.get(&mint).map(|r| *r)
     }
+
+    pub fn prices(&self) -> Vec<(Pubkey, f64)> {
+        self.latest_prices
+            .iter()
+            .map(|r| (*r.key(), *r.value()))
+            .collect()
+    }
+
+    /// Used to restore previously known prices, a price already received from a feed is kept
+    pub fn insert_if_missing(&self, mint: Pubkey, price: f64) {
+        self.latest_prices.entry(mint).or_insert(price);
+    }
 }
@@ -3,6 +3,7 @@ use solana_sdk::pubkey::Pubkey;
 use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 
+use crate::price_feeds::onchain::OnChainPriceSource;
 use router_config_lib::PriceFeedConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,4 +21,6 @@ pub trait PriceFeed {
         Self: Sized;
     fn receiver(&mut self) -> broadcast::Receiver<PriceUpdate>;
     fn register_mint_sender(&self) -> async_channel::Sender<Pubkey>;
+    /// Pools to derive prices from, ignored by feeds only using external APIs
+    fn set_onchain_source(&self, _source: OnChainPriceSource) {}
 }
@@ -0,0 +1,128 @@
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+
+use autobahn_executor::Instructions;
+
+/// Token account (owned by itself) collecting the referrer share of the fees for a given mint
+pub fn referrer_vault_address(referrer: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"referrer", referrer.as_ref(), mint.as_ref()],
+        &autobahn_executor::id(),
+    )
+}
+
+/// Create the referral vault of `referrer` for `mint`, paid by `payer`
+pub fn create_referral_ix(
+    payer: &Pubkey,
+    referrer: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let (vault, bump) = referrer_vault_address(referrer, mint);
+
+    Instruction {
+        program_id: autobahn_executor::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*referrer, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![Instructions::CreateReferral as u8, bump],
+    }
+}
+
+/// Withdraw the whole referral vault balance of `referrer` for `mint` into `referrer_token_account`
+pub fn withdraw_referral_ix(
+    referrer: &Pubkey,
+    mint: &Pubkey,
+    referrer_token_account: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let (vault, bump) = referrer_vault_address(referrer, mint);
+
+    Instruction {
+        program_id: autobahn_executor::id(),
+        accounts: vec![
+            AccountMeta::new(*referrer, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*referrer_token_account, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![Instructions::WithdrawReferral as u8, bump],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bonfida_test_utils::{ProgramTestContextExt, ProgramTestExt};
+    use solana_program_test::{processor, ProgramTest};
+    use solana_sdk::account::AccountSharedData;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    // the executor checks the account order, the runtime the signer and writable flags
+    #[tokio::test]
+    async fn should_create_and_withdraw_referral_with_executor() {
+        let mut program_test = ProgramTest::new(
+            "autobahn_executor",
+            autobahn_executor::id(),
+            processor!(autobahn_executor::process_instruction),
+        );
+        let mint_authority = Keypair::new();
+        let (mint, _) = program_test.add_mint(None, 6, &mint_authority.pubkey());
+        let mut context = program_test.start_with_context().await;
+
+        let (payer, referrer) = (Keypair::new(), Keypair::new());
+        for user in [&payer, &referrer] {
+            context.set_account(
+                &user.pubkey(),
+                &AccountSharedData::new(1_000_000_000, 0, &system_program::id()),
+            );
+        }
+        let token_program = anchor_spl::token::ID;
+
+        let create_ix =
+            create_referral_ix(&payer.pubkey(), &referrer.pubkey(), &mint, &token_program);
+        context
+            .sign_send_instructions(&[create_ix], &[&payer])
+            .await
+            .unwrap();
+
+        let (vault, _) = referrer_vault_address(&referrer.pubkey(), &mint);
+        context
+            .mint_tokens(&mint_authority, &mint, &vault, 1_000)
+            .await
+            .unwrap();
+        let referrer_token_account = context
+            .initialize_token_accounts(mint, &[referrer.pubkey()])
+            .await
+            .unwrap()[0];
+
+        let withdraw_ix = withdraw_referral_ix(
+            &referrer.pubkey(),
+            &mint,
+            &referrer_token_account,
+            &token_program,
+        );
+        context
+            .sign_send_instructions(&[withdraw_ix], &[&referrer])
+            .await
+            .unwrap();
+
+        assert_eq!(0, context.get_token_account(vault).await.unwrap().amount);
+        assert_eq!(
+            1_000,
+            context
+                .get_token_account(referrer_token_account)
+                .await
+                .unwrap()
+                .amount
+        );
+    }
+}
@@ -88,6 +88,7 @@ impl RouterClient {
             auto_create_out_ata: true,
             use_shared_accounts: false,
             fee_account: None,
+            referrer: None,
             compute_unit_price_micro_lamports: None,
             as_legacy_transaction: false,
             use_token_ledger: false,
@@ -57,7 +57,7 @@ pub fn execute_charge_fees_v2(accounts: &[AccountInfo], instruction_data: &[u8])
     })?;
 
     if has_referrer {
-        let referrer_fee_account = &accounts[4];
+        let referrer_fee_account = &accounts[5];
         let referrer_fee_amount = fee_amount.saturating_sub(platform_fee_amount);
         token::transfer(
             token_program,
@@ -0,0 +1,74 @@
+use crate::utils::{read_u64, read_u8};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program::invoke;
+
+/// Instruction that forwards to phoenix Swap with an immediate-or-cancel order
+///
+/// Data:
+/// - in_amount: u64 in native
+/// - is_bid: u8 is 1 or 0
+///
+/// Accounts:
+/// - phoenix program
+/// - phoenix Swap accounts
+pub fn execute_phoenix_swap(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let (in_amount, instruction_data) = read_u64(instruction_data);
+    let (is_bid, _instruction_data) = read_u8(instruction_data);
+
+    // Load the phoenix market header to figure out the lot sizes
+    let market_data = accounts[3].try_borrow_data()?;
+    assert!(market_data.len() >= 576);
+    let base_lot_size = u64::from_le_bytes(market_data[112..120].try_into().unwrap());
+    let quote_lot_size = u64::from_le_bytes(market_data[192..200].try_into().unwrap());
+    drop(market_data);
+
+    // A zero size on the other side lets the order be limited by the input only
+    let is_bid = is_bid == 1;
+    let side: u8;
+    let num_base_lots;
+    let num_quote_lots;
+    if is_bid {
+        side = 0;
+        num_base_lots = 0;
+        num_quote_lots = in_amount / quote_lot_size;
+    } else {
+        side = 1;
+        num_base_lots = in_amount / base_lot_size;
+        num_quote_lots = 0;
+    }
+
+    let mut data = Vec::with_capacity(64);
+    data.push(0u8); // Swap discriminator
+    data.push(2u8); // OrderPacket::ImmediateOrCancel
+    data.push(side);
+    data.push(0u8); // price_in_ticks: None
+    data.extend_from_slice(&num_base_lots.to_le_bytes());
+    data.extend_from_slice(&num_quote_lots.to_le_bytes());
+    data.extend_from_slice(&0u64.to_le_bytes()); // min_base_lots_to_fill
+    data.extend_from_slice(&0u64.to_le_bytes()); // min_quote_lots_to_fill
+    data.push(2u8); // SelfTradeBehavior::DecrementTake
+    data.push(0u8); // match_limit: None
+    data.extend_from_slice(&0u128.to_le_bytes()); // client_order_id
+    data.push(0u8); // use_only_deposited_funds
+    data.push(0u8); // last_valid_slot: None
+    data.push(0u8); // last_valid_unix_timestamp_in_seconds: None
+
+    let instruction = Instruction {
+        program_id: *accounts[0].key,
+        accounts: accounts[1..]
+            .iter()
+            .map(|ai| AccountMeta {
+                pubkey: *ai.key,
+                is_signer: ai.is_signer,
+                is_writable: ai.is_writable,
+            })
+            .collect::<Vec<_>>(),
+        data,
+    };
+
+    invoke(&instruction, accounts)?;
+
+    Ok(())
+}
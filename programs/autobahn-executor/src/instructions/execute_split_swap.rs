@@ -0,0 +1,158 @@
+use crate::logs::{emit_stack, SplitSwapEvent, SwapEvent};
+use crate::token;
+use crate::utils::{read_bytes, read_u64, read_u8, read_ux16};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::msg;
+use solana_program::program::invoke;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+/// Instruction data layout
+/// first account is owner input_token_account
+/// - min_out_amount: u64 (checked against the sum of all legs output)
+/// - number of legs: u8
+/// - legs (at least one):
+///     - number of ix: u8 (at least one)
+///     - instructions: same layout as `execute_swap_v3`, the first ix of each leg
+///       carries its share of the input, next ones get the previous hop output
+///         - ix_size: ux16 (see `read_ux16`)
+///         - in_amount_offset: ux16
+///         - ix_data: [u8; ix_size]
+///         - ix_accounts:
+///             - ix_account_count: u8
+///         then we have amm program account + out account + ix accounts (in order)
+pub fn execute_split_swap(
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+    router_version: u8,
+) -> ProgramResult {
+    let (min_out_amount, instruction_data) = read_u64(instruction_data);
+    let (number_of_legs, instruction_data) = read_u8(instruction_data);
+
+    if number_of_legs == 0 {
+        msg!("Split swap needs at least one leg");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    msg!(
+        "Router v={} - Split swap in {} leg(s) - expected min out amount {}",
+        router_version,
+        number_of_legs,
+        min_out_amount,
+    );
+
+    let input_mint = token::get_mint(&accounts[0])?;
+    let mut ix_account_index = 1usize;
+    let mut ext_instruction_data = instruction_data;
+
+    let mut total_input_amount = 0u64;
+    let mut total_output_amount = 0u64;
+    let mut output_mint: Option<Pubkey> = None;
+
+    for _ in 0..number_of_legs {
+        let (number_of_ix, instruction_data) = read_u8(ext_instruction_data);
+        ext_instruction_data = instruction_data;
+
+        if number_of_ix == 0 {
+            msg!("Split swap leg needs at least one instruction");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let mut in_amount = 0u64;
+        let mut leg_input_amount = 0u64;
+        let mut leg_output_amount = 0u64;
+        let mut leg_output_mint = input_mint;
+
+        for ix_index in 0..number_of_ix {
+            let instruction_data = ext_instruction_data;
+            let (ix_size, instruction_data) = read_ux16(instruction_data);
+            let (in_amount_offset, instruction_data) = read_ux16(instruction_data);
+            let (ix_data, instruction_data) = read_bytes(ix_size as usize, instruction_data);
+
+            let (ix_account_count, instruction_data) = read_u8(instruction_data);
+
+            let ix_account_count = ix_account_count as usize;
+            let ix_accounts: Vec<AccountInfo> =
+                accounts[ix_account_index..ix_account_index + ix_account_count].to_vec();
+
+            let mut ix_data = ix_data.to_vec();
+
+            if ix_index > 0 {
+                let in_amount_offset = in_amount_offset as usize;
+                let in_amount_override = in_amount.to_le_bytes();
+                ix_data[in_amount_offset..in_amount_offset + 8]
+                    .copy_from_slice(&in_amount_override);
+            }
+
+            let instruction = Instruction {
+                program_id: *ix_accounts[1].key,
+                accounts: ix_accounts[2..]
+                    .iter()
+                    .map(|ai| AccountMeta {
+                        pubkey: *ai.key,
+                        is_signer: ai.is_signer,
+                        is_writable: ai.is_writable,
+                    })
+                    .collect::<Vec<_>>(),
+                data: ix_data,
+            };
+
+            let input_balance_before = token::get_balance(&accounts[0])?;
+            let ix_token_account = &ix_accounts[0];
+            let balance_before = token::get_balance(ix_token_account)?;
+            invoke(&instruction, &ix_accounts)?;
+            let balance_after = token::get_balance(ix_token_account)?;
+            let out_amount = balance_after - balance_before;
+
+            if ix_index == 0 {
+                leg_input_amount = input_balance_before - token::get_balance(&accounts[0])?;
+            }
+
+            in_amount = out_amount;
+            ext_instruction_data = instruction_data;
+            ix_account_index += ix_accounts.len();
+            leg_output_amount = out_amount;
+            leg_output_mint = token::get_mint(ix_token_account)?;
+        }
+
+        match output_mint {
+            None => output_mint = Some(leg_output_mint),
+            Some(mint) if mint != leg_output_mint => {
+                msg!("All legs should end with the same output mint");
+                return Err(ProgramError::InvalidArgument);
+            }
+            Some(_) => {}
+        }
+
+        emit_stack(SwapEvent {
+            input_mint,
+            input_amount: leg_input_amount,
+            output_mint: leg_output_mint,
+            output_amount: leg_output_amount,
+        })?;
+
+        total_input_amount += leg_input_amount;
+        total_output_amount += leg_output_amount;
+    }
+
+    emit_stack(SplitSwapEvent {
+        input_mint,
+        input_amount: total_input_amount,
+        output_mint: output_mint.unwrap_or_default(),
+        output_amount: total_output_amount,
+        leg_count: number_of_legs as u64,
+    })?;
+
+    if total_output_amount < min_out_amount {
+        msg!(
+            "Max slippage reached, expected at least {}, got {}",
+            min_out_amount,
+            total_output_amount
+        );
+        Err(ProgramError::Custom(1)) // TODO Error code
+    } else {
+        Ok(())
+    }
+}
@@ -2,6 +2,8 @@ mod execute_charge_fees;
 mod execute_charge_fees_v2;
 mod execute_create_referral;
 mod execute_openbook_v2_swap;
+mod execute_phoenix_swap;
+mod execute_split_swap;
 mod execute_swap_v2;
 mod execute_swap_v3;
 mod execute_withdraw_referral_fees;
@@ -10,6 +12,8 @@ pub use execute_charge_fees::execute_charge_fees;
 pub use execute_charge_fees_v2::execute_charge_fees_v2;
 pub use execute_create_referral::execute_create_referral;
 pub use execute_openbook_v2_swap::execute_openbook_v2_swap;
+pub use execute_phoenix_swap::execute_phoenix_swap;
+pub use execute_split_swap::execute_split_swap;
 pub use execute_swap_v2::execute_swap_v2;
 pub use execute_swap_v3::execute_swap_v3;
 pub use execute_withdraw_referral_fees::execute_withdraw_referral_fees;
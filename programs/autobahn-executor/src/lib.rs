@@ -7,7 +7,8 @@ pub mod utils;
 
 use instructions::{
     execute_charge_fees, execute_charge_fees_v2, execute_create_referral, execute_openbook_v2_swap,
-    execute_swap_v2, execute_swap_v3, execute_withdraw_referral_fees,
+    execute_phoenix_swap, execute_split_swap, execute_swap_v2, execute_swap_v3,
+    execute_withdraw_referral_fees,
 };
 use solana_program::declare_id;
 use solana_program::entrypoint::ProgramResult;
@@ -42,6 +43,8 @@ pub enum Instructions {
     CreateReferral = 5,
     WithdrawReferral = 6,
     ChargeFeesV2 = 7,
+    ExecuteSplitSwap = 8,
+    PhoenixSwap = 9,
 }
 
 pub fn process_instruction(
@@ -74,6 +77,12 @@ pub fn process_instruction(
         x if x == Instructions::ChargeFeesV2 as u8 => {
             execute_charge_fees_v2(accounts, &instruction_data[1..])
         }
+        x if x == Instructions::ExecuteSplitSwap as u8 => {
+            execute_split_swap(accounts, &instruction_data[1..], router_version)
+        }
+        x if x == Instructions::PhoenixSwap as u8 => {
+            execute_phoenix_swap(accounts, &instruction_data[1..])
+        }
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -60,6 +60,16 @@ pub struct SwapEvent {
     pub output_amount: u64,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod)]
+pub struct SplitSwapEvent {
+    pub input_mint: Pubkey,
+    pub input_amount: u64,
+    pub output_mint: Pubkey,
+    pub output_amount: u64,
+    pub leg_count: u64,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod)]
 pub struct PlatformFeeLog {
@@ -98,6 +108,7 @@ pub const REFERRER_FEE_LOG_DISCRIMINANT: [u8; 8] = [198, 149, 221, 27, 28, 103,
 pub const REFERRER_WITHDRAW_LOG_DISCRIMINANT: [u8; 8] = [25, 7, 239, 41, 67, 36, 141, 92];
 pub const SWAP_EVENT_DISCRIMINANT: [u8; 8] = [56, 178, 48, 245, 42, 152, 27, 75];
 pub const CREATE_REFERRAL_LOG_DISCRIMINANT: [u8; 8] = [114, 188, 157, 65, 100, 179, 129, 169];
+pub const SPLIT_SWAP_EVENT_DISCRIMINANT: [u8; 8] = [81, 58, 124, 16, 30, 47, 74, 211];
 
 discriminant!(
     PlatformFeeLog,
@@ -125,6 +136,12 @@ discriminant!(
 
 discriminant!(SwapEvent, SWAP_EVENT_DISCRIMINANT, test_swap_event);
 
+discriminant!(
+    SplitSwapEvent,
+    SPLIT_SWAP_EVENT_DISCRIMINANT,
+    test_split_swap_event
+);
+
 /// Canonical discriminant of the given struct. It is the hash of program ID and
 /// the name of the type.
 #[cfg(test)]
@@ -60,3 +60,110 @@ pub fn generate_swap_ix_data(
         data,
     }
 }
+
+/// One leg of a split swap, a chain of hops, the first one swapping the leg share of the input token
+pub struct SplitSwapLeg {
+    pub instructions: Vec<Instruction>,
+    pub in_amount_offsets: Vec<u16>,
+    pub out_accounts: Vec<Pubkey>,
+}
+
+pub fn generate_split_swap_ix_data(
+    min_out_amount: u64,
+    legs: &[SplitSwapLeg],
+    in_account: Pubkey,
+    program_id: Pubkey,
+    router_version: u8,
+) -> Instruction {
+    let mut accounts = vec![];
+    accounts.push(AccountMeta {
+        pubkey: in_account,
+        is_signer: false,
+        is_writable: true,
+    });
+
+    let mut result = vec![0; 1232];
+
+    let pointer = result.as_mut_slice();
+    let mut offset = 0;
+    offset += write_u8(
+        &mut pointer[offset..],
+        (Instructions::ExecuteSplitSwap as u8) + (router_version << 4),
+    );
+    offset += write_u64(&mut pointer[offset..], min_out_amount);
+    offset += write_u8(&mut pointer[offset..], legs.len() as u8);
+
+    for leg in legs {
+        offset += write_u8(&mut pointer[offset..], leg.instructions.len() as u8);
+
+        for ((ix, in_amount_offset), out_account) in leg
+            .instructions
+            .iter()
+            .zip(&leg.in_amount_offsets)
+            .zip(&leg.out_accounts)
+        {
+            offset += write_ux16(&mut pointer[offset..], ix.data.len() as u16);
+            offset += write_ux16(&mut pointer[offset..], *in_amount_offset);
+            offset += write_bytes(&mut pointer[offset..], &ix.data);
+            offset += write_u8(&mut pointer[offset..], 2 + ix.accounts.len() as u8); // Add 1 for program and 1 for owner (step) out ATA
+            accounts.push(AccountMeta {
+                pubkey: *out_account,
+                is_signer: false,
+                is_writable: true,
+            });
+
+            accounts.push(AccountMeta {
+                pubkey: ix.program_id,
+                is_signer: false,
+                is_writable: false,
+            });
+
+            accounts.extend(ix.accounts.clone());
+        }
+    }
+
+    let data: Vec<u8> = result[0..offset].to_vec();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Platform (and optional referrer) fee transfer from the `owner` token account,
+/// see `execute_charge_fees_v2`
+pub fn generate_charge_fees_ix_data(
+    fee_amount: u64,
+    platform_fee_percent: u8,
+    token_program: Pubkey,
+    mint: Pubkey,
+    token_account: Pubkey,
+    platform_fee_account: Pubkey,
+    owner: Pubkey,
+    referrer_fee_account: Option<Pubkey>,
+    program_id: Pubkey,
+) -> Instruction {
+    let mut data = vec![0; 10];
+    let mut offset = 0;
+    offset += write_u8(&mut data[offset..], Instructions::ChargeFeesV2 as u8);
+    offset += write_u64(&mut data[offset..], fee_amount);
+    write_u8(&mut data[offset..], platform_fee_percent);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(token_program, false),
+        AccountMeta::new_readonly(mint, false),
+        AccountMeta::new(token_account, false),
+        AccountMeta::new(platform_fee_account, false),
+        AccountMeta::new_readonly(owner, true),
+    ];
+    if let Some(referrer_fee_account) = referrer_fee_account {
+        accounts.push(AccountMeta::new(referrer_fee_account, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
@@ -7,7 +7,9 @@ use std::collections::HashMap;
 use std::str::FromStr;
 
 use autobahn_executor;
-use autobahn_executor::swap_ix::generate_swap_ix_data;
+use autobahn_executor::swap_ix::{
+    generate_split_swap_ix_data, generate_swap_ix_data, SplitSwapLeg,
+};
 use solana_sdk::signer::{keypair::Keypair, Signer};
 
 use crate::utils::*;
@@ -278,6 +280,130 @@ async fn should_fail_when_max_slippage_is_reached() {
     }
 }
 
+#[tokio::test]
+async fn should_do_a_split_execution() {
+    for (min_out_amount, expected_err) in [(36_000_000, true), (35_000_000, false)] {
+        let mut test = TestData::new(
+            &["Alice".to_string(), "Bob".to_string()],
+            &["USDC".to_string(), "EURC".to_string()],
+            HashMap::from([
+                ("Alice:USDC".to_string(), 100_000_000),
+                ("Bob:EURC".to_string(), 100_000_000),
+            ]),
+        )
+        .await;
+
+        let legs = [(5_000_000u64, 25_000_000u64), (3_000_000u64, 10_000_000u64)]
+            .into_iter()
+            .map(|(amount_a, amount_b)| SplitSwapLeg {
+                instructions: vec![build_mock_swap_ix(
+                    &test.users["Alice"],
+                    &test.users["Bob"],
+                    test.mock_swap_program_id,
+                    test.users_ata["Alice:USDC"],
+                    test.users_ata["Alice:EURC"],
+                    test.users_ata["Bob:USDC"],
+                    test.users_ata["Bob:EURC"],
+                    amount_a,
+                    amount_b,
+                )],
+                in_amount_offsets: vec![0],
+                out_accounts: vec![test.users_ata["Alice:EURC"]],
+            })
+            .collect::<Vec<_>>();
+
+        let ix = generate_split_swap_ix_data(
+            min_out_amount,
+            &legs,
+            test.users_ata["Alice:USDC"],
+            autobahn_executor::id(),
+            0,
+        );
+
+        let is_err = test
+            .program_test_context
+            .sign_send_instructions(&[ix], &[&test.users["Alice"], &test.users["Bob"]])
+            .await
+            .is_err();
+
+        assert_eq!(is_err, expected_err);
+
+        if !expected_err {
+            assert_ata_balances(
+                &mut test,
+                vec![
+                    ("Alice:USDC", 92_000_000),
+                    ("Alice:EURC", 35_000_000),
+                    ("Bob:USDC", 8_000_000),
+                    ("Bob:EURC", 65_000_000),
+                ],
+            )
+            .await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn should_reject_split_swap_without_leg_or_with_empty_leg() {
+    for empty_leg in [false, true] {
+        let mut test = TestData::new(
+            &["Alice".to_string(), "Bob".to_string()],
+            &["USDC".to_string(), "EURC".to_string()],
+            HashMap::from([
+                ("Alice:USDC".to_string(), 100_000_000),
+                ("Bob:EURC".to_string(), 100_000_000),
+            ]),
+        )
+        .await;
+
+        let mut legs = vec![SplitSwapLeg {
+            instructions: vec![build_mock_swap_ix(
+                &test.users["Alice"],
+                &test.users["Bob"],
+                test.mock_swap_program_id,
+                test.users_ata["Alice:USDC"],
+                test.users_ata["Alice:EURC"],
+                test.users_ata["Bob:USDC"],
+                test.users_ata["Bob:EURC"],
+                5_000_000,
+                25_000_000,
+            )],
+            in_amount_offsets: vec![0],
+            out_accounts: vec![test.users_ata["Alice:EURC"]],
+        }];
+        if empty_leg {
+            legs.push(SplitSwapLeg {
+                instructions: vec![],
+                in_amount_offsets: vec![],
+                out_accounts: vec![],
+            });
+        } else {
+            legs.clear();
+        }
+
+        let ix = generate_split_swap_ix_data(
+            0,
+            &legs,
+            test.users_ata["Alice:USDC"],
+            autobahn_executor::id(),
+            0,
+        );
+
+        let is_err = test
+            .program_test_context
+            .sign_send_instructions(&[ix], &[&test.users["Alice"], &test.users["Bob"]])
+            .await
+            .is_err();
+
+        assert!(is_err);
+        assert_ata_balances(
+            &mut test,
+            vec![("Alice:USDC", 100_000_000), ("Alice:EURC", 0)],
+        )
+        .await;
+    }
+}
+
 fn build_mock_swap_ix(
     swapper: &Keypair,
     other: &Keypair,
@@ -2,6 +2,7 @@
 
 use crate::utils::*;
 use autobahn_executor;
+use autobahn_executor::swap_ix::generate_charge_fees_ix_data;
 use autobahn_executor::Instructions;
 use bonfida_test_utils::ProgramTestContextExt;
 use solana_program::instruction::{AccountMeta, Instruction};
@@ -230,6 +231,58 @@ async fn should_work_with_referrer_derived_address() {
     }
 }
 
+#[tokio::test]
+async fn should_split_fee_v2_between_platform_and_referrer() {
+    for (pct, expected_bob, expected_platform) in [
+        (80, 100_400_000, 101_600_000),
+        (0, 102_000_000, 100_000_000),
+    ] {
+        println!("With {pct} %");
+
+        let mut test: TestData = TestData::new(
+            &[
+                "Alice".to_string(),
+                "Bob".to_string(),
+                "Platform".to_string(),
+            ],
+            &["USDC".to_string()],
+            HashMap::from([
+                ("Alice:USDC".to_string(), 100_000_000),
+                ("Bob:USDC".to_string(), 100_000_000),
+                ("Platform:USDC".to_string(), 100_000_000),
+            ]),
+        )
+        .await;
+
+        let ix = generate_charge_fees_ix_data(
+            2_000_000u64,
+            pct,
+            spl_token::ID,
+            test.mint_keys["USDC"],
+            test.users_ata["Alice:USDC"],
+            test.users_ata["Platform:USDC"],
+            test.users["Alice"].pubkey(),
+            Some(test.users_ata["Bob:USDC"]),
+            autobahn_executor::id(),
+        );
+
+        test.program_test_context
+            .sign_send_instructions(&[ix], &[&test.users["Alice"]])
+            .await
+            .unwrap();
+
+        assert_ata_balances(
+            &mut test,
+            vec![
+                ("Alice:USDC", 98_000_000),
+                ("Bob:USDC", expected_bob),
+                ("Platform:USDC", expected_platform),
+            ],
+        )
+        .await;
+    }
+}
+
 fn build_fee_ix_data(fee_amount: u64, platform_fee_pct: u8) -> Vec<u8> {
     let mut data = vec![];
     data.push(Instructions::ChargeFees as u8);
@@ -62,11 +62,26 @@ async fn test_quote_match_swap_for_raydium_cp() -> anyhow::Result<()> {
     run_all_swap_from_dump("raydium_cp_swap.lz4").await?
 }
 
+#[tokio::test]
+async fn test_quote_match_swap_for_raydium_clmm() -> anyhow::Result<()> {
+    run_all_swap_from_dump("raydium_clmm_swap.lz4").await?
+}
+
+#[tokio::test]
+async fn test_quote_match_swap_for_meteora_dlmm() -> anyhow::Result<()> {
+    run_all_swap_from_dump("meteora_dlmm_swap.lz4").await?
+}
+
 #[tokio::test]
 async fn test_quote_match_swap_for_openbook_v2() -> anyhow::Result<()> {
     run_all_swap_from_dump("openbook_v2_swap.lz4").await?
 }
 
+#[tokio::test]
+async fn test_quote_match_swap_for_phoenix() -> anyhow::Result<()> {
+    run_all_swap_from_dump("phoenix_swap.lz4").await?
+}
+
 #[tokio::test]
 async fn test_quote_match_swap_for_infinity() -> anyhow::Result<()> {
     run_all_swap_from_dump("infinity_swap.lz4").await?
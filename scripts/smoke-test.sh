@@ -38,6 +38,19 @@ cargo test-sbf --package simulator -- --nocapture --exact cases::test_swap_from_
 SIM_OPENBOOK_V2_RC=$?
 SIM_OPENBOOK_V2_RT=$(expr $(date +%s) - $START)
 
+# phoenix
+DUMP_PHOENIX_START=$(date)
+START=$(date +%s)
+cargo test --package dex-phoenix -- --nocapture
+DUMP_PHOENIX_RC=$?
+DUMP_PHOENIX_RT=$(expr $(date +%s) - $START)
+
+SIM_PHOENIX_START=$(date)
+START=$(date +%s)
+cargo test-sbf --package simulator -- --nocapture --exact cases::test_swap_from_dump::test_quote_match_swap_for_phoenix
+SIM_PHOENIX_RC=$?
+SIM_PHOENIX_RT=$(expr $(date +%s) - $START)
+
 # infinity
 DUMP_INFINITY_START=$(date)
 START=$(date +%s)
@@ -77,6 +90,32 @@ cargo test-sbf --package simulator -- --nocapture --exact cases::test_swap_from_
 SIM_RAYDIUM_RC=$?
 SIM_RAYDIUM_RT=$(expr $(date +%s) - $START)
 
+# raydium clmm
+DUMP_RAYDIUM_CLMM_START=$(date)
+START=$(date +%s)
+cargo test --package dex-raydium-clmm -- --nocapture
+DUMP_RAYDIUM_CLMM_RC=$?
+DUMP_RAYDIUM_CLMM_RT=$(expr $(date +%s) - $START)
+
+SIM_RAYDIUM_CLMM_START=$(date)
+START=$(date +%s)
+cargo test-sbf --package simulator -- --nocapture --exact cases::test_swap_from_dump::test_quote_match_swap_for_raydium_clmm
+SIM_RAYDIUM_CLMM_RC=$?
+SIM_RAYDIUM_CLMM_RT=$(expr $(date +%s) - $START)
+
+# meteora dlmm
+DUMP_METEORA_DLMM_START=$(date)
+START=$(date +%s)
+cargo test --package dex-meteora-dlmm -- --nocapture
+DUMP_METEORA_DLMM_RC=$?
+DUMP_METEORA_DLMM_RT=$(expr $(date +%s) - $START)
+
+SIM_METEORA_DLMM_START=$(date)
+START=$(date +%s)
+cargo test-sbf --package simulator -- --nocapture --exact cases::test_swap_from_dump::test_quote_match_swap_for_meteora_dlmm
+SIM_METEORA_DLMM_RC=$?
+SIM_METEORA_DLMM_RT=$(expr $(date +%s) - $START)
+
 # orca
 DUMP_ORCA_START=$(date)
 START=$(date +%s)
@@ -97,12 +136,18 @@ saber       dump    `pad $DUMP_SABER_RC 11`   `pad $DUMP_SABER_RT 8`   $DUMP_SAB
 saber       sim     `pad $SIM_SABER_RC 11`   `pad $SIM_SABER_RT 8`   $SIM_SABER_START
 openbook-v2 dump    `pad $DUMP_OPENBOOK_V2_RC 11`   `pad $DUMP_OPENBOOK_V2_RT 8`   $DUMP_OPENBOOK_V2_START
 openbook-v2 sim     `pad $SIM_OPENBOOK_V2_RC 11`   `pad $SIM_OPENBOOK_V2_RT 8`   $SIM_OPENBOOK_V2_START
+phoenix     dump    `pad $DUMP_PHOENIX_RC 11`   `pad $DUMP_PHOENIX_RT 8`   $DUMP_PHOENIX_START
+phoenix     sim     `pad $SIM_PHOENIX_RC 11`   `pad $SIM_PHOENIX_RT 8`   $SIM_PHOENIX_START
 infinity    dump    `pad $DUMP_INFINITY_RC 11`   `pad $DUMP_INFINITY_RT 8`   $DUMP_INFINITY_START
 infinity    sim     `pad $SIM_INFINITY_RC 11`   `pad $SIM_INFINITY_RT 8`   $SIM_INFINITY_START
 raydium-cp  dump    `pad $DUMP_RAYDIUM_CP_RC 11`   `pad $DUMP_RAYDIUM_CP_RT 8`   $DUMP_RAYDIUM_CP_START
 raydium-cp  sim     `pad $SIM_RAYDIUM_CP_RC 11`   `pad $SIM_RAYDIUM_CP_RT 8`   $SIM_RAYDIUM_CP_START
 raydium     dump    `pad $DUMP_RAYDIUM_RC 11`   `pad $DUMP_RAYDIUM_RT 8`   $DUMP_RAYDIUM_START
 raydium     sim     `pad $SIM_RAYDIUM_RC 11`   `pad $SIM_RAYDIUM_RT 8`   $SIM_RAYDIUM_START
+raydium-clmm dump   `pad $DUMP_RAYDIUM_CLMM_RC 11`   `pad $DUMP_RAYDIUM_CLMM_RT 8`   $DUMP_RAYDIUM_CLMM_START
+raydium-clmm sim    `pad $SIM_RAYDIUM_CLMM_RC 11`   `pad $SIM_RAYDIUM_CLMM_RT 8`   $SIM_RAYDIUM_CLMM_START
+meteora-dlmm dump   `pad $DUMP_METEORA_DLMM_RC 11`   `pad $DUMP_METEORA_DLMM_RT 8`   $DUMP_METEORA_DLMM_START
+meteora-dlmm sim    `pad $SIM_METEORA_DLMM_RC 11`   `pad $SIM_METEORA_DLMM_RT 8`   $SIM_METEORA_DLMM_START
 orca        dump    `pad $DUMP_ORCA_RC 11`   `pad $DUMP_ORCA_RT 8`   $DUMP_ORCA_START
 orca        sim     `pad $SIM_ORCA_RC 11`   `pad $SIM_ORCA_RT 8`   $SIM_ORCA_START
 \0